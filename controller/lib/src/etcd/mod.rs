@@ -1,6 +1,9 @@
-use etcd_client::{Client, DeleteResponse, Error, GetOptions, PutResponse};
+use etcd_client::{
+    Client, DeleteResponse, ElectionClient, Error, GetOptions, LeaseClient, PutOptions, PutResponse,
+};
 use log::info;
 
+#[derive(Clone)]
 pub struct EtcdClient {
     inner: Client,
 }
@@ -11,6 +14,16 @@ impl EtcdClient {
         Ok(Self { inner })
     }
 
+    /// Client of the etcd leases, which expire unless they are kept alive
+    pub fn lease_client(&self) -> LeaseClient {
+        self.inner.lease_client()
+    }
+
+    /// Client of the etcd elections, whose candidates are bound to a lease
+    pub fn election_client(&self) -> ElectionClient {
+        self.inner.election_client()
+    }
+
     pub async fn get(&mut self, key: &str) -> Option<String> {
         match self.inner.get(key, None).await {
             Ok(response) => match response.kvs().first() {
@@ -27,12 +40,26 @@ impl EtcdClient {
         );
         self.inner.put(key, value, None).await
     }
+
+    /// Grants a lease which expires once `ttl` seconds have elapsed, and returns its id
+    pub async fn grant_lease(&mut self, ttl: i64) -> Result<i64, Error> {
+        Ok(self.inner.lease_grant(ttl, None).await?.id())
+    }
+
+    /// Inserts a value which is deleted by etcd once the lease `lease` expires
+    pub async fn put_with_lease(
+        &mut self,
+        key: &str,
+        value: &str,
+        lease: i64,
+    ) -> Result<PutResponse, Error> {
+        self.inner
+            .put(key, value, Some(PutOptions::new().with_lease(lease)))
+            .await
+    }
     pub async fn delete(&mut self, key: &str) -> Option<DeleteResponse> {
         match self.get(key).await {
-            Some(_) => match self.inner.delete(key, None).await {
-                Ok(response) => Some(response),
-                Err(_) => None,
-            },
+            Some(_) => self.inner.delete(key, None).await.ok(),
             None => None,
         }
     }
@@ -55,6 +82,40 @@ impl EtcdClient {
             values
         })
     }
+
+    pub async fn get_prefix(&mut self, prefix: &str) -> Option<Vec<String>> {
+        self.list_prefix(prefix).await.ok()
+    }
+
+    /// Returns the values whose key starts with `prefix`, or the error of etcd
+    pub async fn list_prefix(&mut self, prefix: &str) -> Result<Vec<String>, Error> {
+        Ok(self
+            .list_prefix_entries(prefix)
+            .await?
+            .into_iter()
+            .map(|(_, value)| value)
+            .collect())
+    }
+
+    /// Returns the keys starting with `prefix` along with their values, or the error of etcd
+    pub async fn list_prefix_entries(
+        &mut self,
+        prefix: &str,
+    ) -> Result<Vec<(String, String)>, Error> {
+        info!("Retrieving all keys with prefix \"{}\" in ETCD", prefix);
+        let res = self
+            .inner
+            .get(prefix, Some(GetOptions::new().with_prefix()))
+            .await?;
+
+        let mut entries: Vec<(String, String)> = vec![];
+        for kv in res.kvs() {
+            if let (Ok(key), Ok(value)) = (kv.key_str(), kv.value_str()) {
+                entries.push((key.to_string(), value.to_string()));
+            }
+        }
+        Ok(entries)
+    }
 }
 /*
 
@@ -0,0 +1,82 @@
+use actix_web::HttpResponse;
+use serde::{Deserialize, Serialize};
+
+pub enum EventError {
+    Etcd(String),
+    EventToJson(String),
+}
+
+impl EventError {
+    pub fn to_http(&self) -> HttpResponse {
+        match self {
+            EventError::Etcd(err) => {
+                HttpResponse::InternalServerError().body(format!("Etcd error: {} ", err))
+            }
+            EventError::EventToJson(err) => HttpResponse::InternalServerError()
+                .body(format!("Error while converting the event to JSON: {}", err)),
+        }
+    }
+}
+
+impl std::fmt::Display for EventError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EventError::Etcd(err) => write!(f, "Etcd error: {}", err),
+            EventError::EventToJson(err) => write!(f, "Event to JSON error: {}", err),
+        }
+    }
+}
+
+/// Kind of the object an event is about
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InvolvedKind {
+    Instance,
+    Node,
+}
+
+/// Something which happened to an instance or a node of the cluster.
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct Event {
+    /// Seconds since the UNIX epoch
+    pub timestamp: u64,
+    pub namespace: String,
+    pub involved_kind: InvolvedKind,
+    /// Id of the instance or node
+    pub involved: String,
+    /// Short, machine readable description of what happened (eg. `Scheduled`, `Failing`)
+    pub reason: String,
+    /// Human readable details, as reported by the scheduler or the node
+    pub message: String,
+}
+
+#[derive(Deserialize)]
+pub struct EventQuery {
+    pub namespace: Option<String>,
+    /// Kind of the involved object, `Instance` or `Node`
+    pub kind: Option<InvolvedKind>,
+    pub involved: Option<String>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct EventVector {
+    pub count: usize,
+    pub events: Vec<Event>,
+}
+
+impl EventVector {
+    pub fn new(events: Vec<Event>) -> EventVector {
+        EventVector {
+            count: events.len(),
+            events,
+        }
+    }
+    pub fn to_http(&self) -> HttpResponse {
+        match serde_json::to_string(&self) {
+            Ok(json) => HttpResponse::Ok().body(json),
+            Err(err) => HttpResponse::InternalServerError().body(format!(
+                "Error while converting the events to json: {}",
+                err
+            )),
+        }
+    }
+}
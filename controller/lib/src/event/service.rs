@@ -0,0 +1,329 @@
+use std::net::SocketAddr;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use log::debug;
+
+use super::model::{Event, EventError, EventVector, InvolvedKind};
+use crate::etcd::EtcdClient;
+
+/// Namespace of the events which are not about a namespaced object, like nodes
+pub const CLUSTER_EVENTS_NAMESPACE: &str = "default";
+
+/// Longest time during which the events share the same lease, in seconds
+const LEASE_WINDOW: u64 = 60;
+
+/// The lease of the events recorded lately, shared by every `EventService` of the process as
+/// leases aren't bound to a connection.
+static LEASE: Mutex<Option<EventLease>> = Mutex::new(None);
+
+/// A lease shared by the events recorded during a window of time. It outlives the window by the
+/// time to live of the events, so each event lives at least `ttl` seconds.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct EventLease {
+    id: i64,
+    /// Seconds since the UNIX epoch
+    granted_at: u64,
+    ttl: i64,
+}
+
+impl EventLease {
+    /// It returns the window during which the events with the time to live `ttl` share a lease
+    fn window(ttl: i64) -> u64 {
+        LEASE_WINDOW.min(ttl.max(1) as u64)
+    }
+
+    /// It returns the time to live of a lease shared by the events with the time to live `ttl`
+    fn lease_ttl(ttl: i64) -> i64 {
+        ttl + Self::window(ttl) as i64
+    }
+
+    /// Returns true if an event with the time to live `ttl`, recorded at `now`, can be attached
+    /// to the lease.
+    fn covers(&self, now: u64, ttl: i64) -> bool {
+        self.ttl == ttl && now >= self.granted_at && now < self.granted_at + Self::window(self.ttl)
+    }
+}
+
+/// `EventService` records the events of the cluster in etcd. The events are attached to a lease
+/// shared by the events recorded during the same window, so etcd deletes them once their time
+/// to live is over.
+/// Properties:
+///
+/// * `etcd_service`: This is the service that will be used to interact with etcd.
+/// * `ttl`: Time to live of the recorded events, in seconds.
+pub struct EventService {
+    etcd_service: EtcdClient,
+    ttl: i64,
+}
+
+impl EventService {
+    pub async fn new(etcd_address: &SocketAddr, ttl: i64) -> Result<EventService, EventError> {
+        let etcd_service = EtcdClient::new(etcd_address.to_string())
+            .await
+            .map_err(|err| EventError::Etcd(err.to_string()))?;
+        Ok(EventService::from_client(etcd_service, ttl))
+    }
+
+    pub fn from_client(etcd_service: EtcdClient, ttl: i64) -> EventService {
+        EventService { etcd_service, ttl }
+    }
+
+    /// It records a new event
+    ///
+    /// # Arguments:
+    ///
+    /// * `namespace`: The namespace of the object the event is about
+    /// * `involved_kind`: The kind of the object the event is about
+    /// * `involved`: The id of the object the event is about
+    /// * `reason`: What happened
+    /// * `message`: Details about what happened
+    pub async fn record(
+        &mut self,
+        namespace: &str,
+        involved_kind: InvolvedKind,
+        involved: &str,
+        reason: &str,
+        message: &str,
+    ) -> Result<Event, EventError> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        let event = Event {
+            timestamp: now.as_secs(),
+            namespace: namespace.to_string(),
+            involved_kind,
+            involved: involved.to_string(),
+            reason: reason.to_string(),
+            message: message.to_string(),
+        };
+        debug!("Recording event {:?}", event);
+
+        let key = event_key(&event, now.as_nanos(), rand::random::<u32>());
+        let json = serde_json::to_string(&event)
+            .map_err(|err| EventError::EventToJson(err.to_string()))?;
+        let lease = self.lease(now.as_secs()).await?;
+        self.etcd_service
+            .put_with_lease(&key, &json, lease)
+            .await
+            .map_err(|err| EventError::Etcd(err.to_string()))?;
+        Ok(event)
+    }
+
+    /// It returns the events which are still alive, from the oldest to the newest
+    ///
+    /// # Arguments:
+    ///
+    /// * `namespace`: Only return the events of this namespace if set
+    /// * `involved_kind`: Only return the events about this kind of object if set
+    /// * `involved`: Only return the events about this object if set
+    pub async fn get_events(
+        &mut self,
+        namespace: Option<&str>,
+        involved_kind: Option<InvolvedKind>,
+        involved: Option<&str>,
+    ) -> EventVector {
+        let prefix = events_prefix(namespace, involved_kind, involved);
+        let events = match self.etcd_service.get_prefix(&prefix).await {
+            Some(events) => events
+                .iter()
+                .filter_map(|event| serde_json::from_str::<Event>(event).ok())
+                .collect(),
+            None => vec![],
+        };
+        EventVector::new(select_events(events, namespace, involved_kind, involved))
+    }
+
+    /// It returns the lease to attach an event recorded at `now` to, and grants a new one once
+    /// the window of the current lease is over.
+    async fn lease(&mut self, now: u64) -> Result<i64, EventError> {
+        let current = *LEASE.lock().unwrap_or_else(|err| err.into_inner());
+        if let Some(lease) = current.filter(|lease| lease.covers(now, self.ttl)) {
+            return Ok(lease.id);
+        }
+
+        let id = self
+            .etcd_service
+            .grant_lease(EventLease::lease_ttl(self.ttl))
+            .await
+            .map_err(|err| EventError::Etcd(err.to_string()))?;
+        *LEASE.lock().unwrap_or_else(|err| err.into_inner()) = Some(EventLease {
+            id,
+            granted_at: now,
+            ttl: self.ttl,
+        });
+        Ok(id)
+    }
+}
+
+/// It returns the key of an event, so the events can be listed by namespace, kind and object.
+/// The events of an object are ordered by time, then made unique by a random suffix.
+fn event_key(event: &Event, nanos: u128, suffix: u32) -> String {
+    format!(
+        "event.{}.{:?}.{}.{:020}.{:08x}",
+        event.namespace, event.involved_kind, event.involved, nanos, suffix
+    )
+}
+
+/// It returns the longest key prefix shared by the events matching the filters
+fn events_prefix(
+    namespace: Option<&str>,
+    involved_kind: Option<InvolvedKind>,
+    involved: Option<&str>,
+) -> String {
+    let mut prefix = String::from("event.");
+    let Some(namespace) = namespace else {
+        return prefix;
+    };
+    prefix.push_str(&format!("{}.", namespace));
+    let Some(involved_kind) = involved_kind else {
+        return prefix;
+    };
+    prefix.push_str(&format!("{:?}.", involved_kind));
+    if let Some(involved) = involved {
+        prefix.push_str(&format!("{}.", involved));
+    }
+    prefix
+}
+
+/// It keeps the events matching the filters, from the oldest to the newest. A prefix may match
+/// other events, e.g. the namespace `a` is a prefix of the namespace `a.b`.
+fn select_events(
+    mut events: Vec<Event>,
+    namespace: Option<&str>,
+    involved_kind: Option<InvolvedKind>,
+    involved: Option<&str>,
+) -> Vec<Event> {
+    events.retain(|event| {
+        namespace.is_none_or(|ns| event.namespace == ns)
+            && involved_kind.is_none_or(|kind| event.involved_kind == kind)
+            && involved.is_none_or(|id| event.involved == id)
+    });
+    events.sort_by_key(|event| event.timestamp);
+    events
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(timestamp: u64, namespace: &str, kind: InvolvedKind, involved: &str) -> Event {
+        Event {
+            timestamp,
+            namespace: namespace.to_string(),
+            involved_kind: kind,
+            involved: involved.to_string(),
+            reason: "Scheduled".to_string(),
+            message: String::new(),
+        }
+    }
+
+    fn involved(events: &[Event]) -> Vec<&str> {
+        events.iter().map(|event| event.involved.as_str()).collect()
+    }
+
+    #[test]
+    fn test_event_key_matches_prefix() {
+        let event = event(1, "default", InvolvedKind::Instance, "web-1");
+        let key = event_key(&event, 42, 7);
+
+        assert_eq!(
+            key,
+            "event.default.Instance.web-1.00000000000000000042.00000007"
+        );
+        for prefix in [
+            events_prefix(None, None, None),
+            events_prefix(Some("default"), None, None),
+            events_prefix(Some("default"), Some(InvolvedKind::Instance), None),
+            events_prefix(Some("default"), Some(InvolvedKind::Instance), Some("web-1")),
+        ] {
+            assert!(key.starts_with(&prefix), "{} {}", key, prefix);
+        }
+        assert!(!key.starts_with(&events_prefix(
+            Some("default"),
+            Some(InvolvedKind::Instance),
+            Some("web")
+        )));
+        assert!(!key.starts_with(&events_prefix(
+            Some("default"),
+            Some(InvolvedKind::Node),
+            None
+        )));
+    }
+
+    #[test]
+    fn test_events_prefix_needs_the_kind_of_the_object() {
+        assert_eq!(
+            events_prefix(Some("default"), None, Some("web-1")),
+            "event.default."
+        );
+        assert_eq!(events_prefix(None, None, Some("web-1")), "event.");
+    }
+
+    #[test]
+    fn test_select_events() {
+        let events = vec![
+            event(3, "default", InvolvedKind::Instance, "web-2"),
+            event(1, "default", InvolvedKind::Instance, "web-1"),
+            event(2, "default", InvolvedKind::Node, "node-1"),
+            event(4, "default.staging", InvolvedKind::Instance, "web-1"),
+        ];
+
+        let all = select_events(events.clone(), None, None, None);
+        assert_eq!(involved(&all), vec!["web-1", "node-1", "web-2", "web-1"]);
+
+        let default = select_events(events.clone(), Some("default"), None, None);
+        assert_eq!(involved(&default), vec!["web-1", "node-1", "web-2"]);
+
+        let instances = select_events(
+            events.clone(),
+            Some("default"),
+            Some(InvolvedKind::Instance),
+            None,
+        );
+        assert_eq!(involved(&instances), vec!["web-1", "web-2"]);
+
+        let web = select_events(events, None, None, Some("web-1"));
+        assert_eq!(
+            web.iter().map(|e| e.timestamp).collect::<Vec<_>>(),
+            vec![1, 4]
+        );
+    }
+
+    #[test]
+    fn test_lease_covers_its_window() {
+        let lease = EventLease {
+            id: 1,
+            granted_at: 1000,
+            ttl: 3600,
+        };
+
+        assert!(lease.covers(1000, 3600));
+        assert!(lease.covers(1059, 3600));
+        assert!(!lease.covers(1060, 3600));
+        assert!(!lease.covers(999, 3600));
+        // events with another time to live need another lease
+        assert!(!lease.covers(1000, 600));
+    }
+
+    #[test]
+    fn test_events_live_at_least_their_ttl() {
+        assert_eq!(EventLease::lease_ttl(3600), 3660);
+        // short lived events don't share a lease for longer than they live
+        assert_eq!(EventLease::window(10), 10);
+        assert_eq!(EventLease::lease_ttl(10), 20);
+        assert_eq!(EventLease::window(0), 1);
+
+        let lease = EventLease {
+            id: 1,
+            granted_at: 1000,
+            ttl: 10,
+        };
+        let expiry = lease.granted_at + EventLease::lease_ttl(lease.ttl) as u64;
+        let last = (lease.granted_at..)
+            .take_while(|now| lease.covers(*now, 10))
+            .last();
+        assert_eq!(last, Some(1009));
+        assert!(expiry - last.unwrap() >= lease.ttl as u64);
+    }
+}
@@ -0,0 +1,36 @@
+use crate::event::model::EventQuery;
+use crate::event::service::EventService;
+use crate::external_api::interface::ActixAppState;
+use actix_web::{web, Responder, Scope};
+pub struct EventController {}
+impl EventController {
+    pub fn services(&self) -> Scope {
+        web::scope("/events")
+            .service(web::resource("").route(web::get().to(EventController::get_events)))
+    }
+
+    /// `get_events` is an async function that handle **/events** route (GET)
+    /// # Description:
+    /// * Get the events of the cluster which are still alive
+    /// # Arguments:
+    ///
+    /// * `query`: web::Query<EventQuery> - Optional `namespace`, `kind` and `involved` object filters.
+    pub async fn get_events(
+        query: web::Query<EventQuery>,
+        data: web::Data<ActixAppState>,
+    ) -> impl Responder {
+        let mut event_service = match EventService::new(&data.etcd_address, data.event_ttl).await {
+            Ok(event) => event,
+            Err(e) => return e.to_http(),
+        };
+
+        event_service
+            .get_events(
+                query.namespace.as_deref(),
+                query.kind,
+                query.involved.as_deref(),
+            )
+            .await
+            .to_http()
+    }
+}
@@ -24,8 +24,7 @@ impl FilterService {
     /// # Returns:
     ///
     /// A vector.
-
-    pub fn limit<T: Clone>(&mut self, vector: &Vec<T>, mut limit: u32) -> Vec<T> {
+    pub fn limit<T: Clone>(&mut self, vector: &[T], mut limit: u32) -> Vec<T> {
         if limit > vector.len() as u32 {
             limit = vector.len() as u32;
         }
@@ -45,12 +44,7 @@ impl FilterService {
     /// # Returns:
     ///
     /// A vector.
-
-    pub fn offset<T: Clone>(
-        &mut self,
-        vector: &Vec<T>,
-        offset: u32,
-    ) -> Result<Vec<T>, FilterError> {
+    pub fn offset<T: Clone>(&mut self, vector: &[T], offset: u32) -> Result<Vec<T>, FilterError> {
         if offset > vector.len() as u32 {
             return Err(FilterError::OutOfRange);
         }
@@ -0,0 +1,105 @@
+use crate::external_api::interface::ActixAppState;
+
+use super::service::InstanceService;
+use crate::external_api::generic::model::Pagination;
+use actix_web::{web, Responder, Scope};
+pub struct InstanceController {}
+impl InstanceController {
+    /// The instances are read only, they are created and destroyed along with their workload
+    pub fn services(&self) -> Scope {
+        web::scope("/instance")
+            .service(
+                web::resource("/{namespace}/{instance_id}")
+                    .route(web::get().to(InstanceController::instance)),
+            )
+            .service(
+                web::resource("/{namespace}")
+                    .route(web::get().to(InstanceController::get_all_instances)),
+            )
+    }
+
+    /// `instance` is an async function that handle **/instance/\<namespace>/<instance_id>** route (GET)
+    /// # Description:
+    /// * Get an instance
+    /// # Arguments:
+    ///
+    /// * `params`: web::Path<(String, String)> - The first Path parameter is the namespace and the second the instance id.
+    pub async fn instance(
+        params: web::Path<(String, String)>,
+        data: web::Data<ActixAppState>,
+    ) -> impl Responder {
+        let (namespace, instance_id) = params.into_inner();
+
+        let mut instance_service =
+            match InstanceService::new(&data.scheduler_address, &data.etcd_address, data.event_ttl)
+                .await
+            {
+                Ok(instance) => instance,
+                Err(e) => return e.to_http(),
+            };
+
+        instance_service
+            .get_instance(&instance_id, &namespace)
+            .await
+            .map_or_else(|e| e.to_http(), |i| i.to_http())
+    }
+
+    /// `get_all_instances` is an async function that handle **/instance/\<namespace>** route (GET)
+    /// # Description:
+    /// * Get all instances in the namespace
+    /// # Arguments:
+    ///
+    /// * `namespace`: The namespace of the instances you want to retrieve.
+    /// * `pagination`: Option<web::Query<Pagination>>
+    pub async fn get_all_instances(
+        namespace: web::Path<String>,
+        pagination: Option<web::Query<Pagination>>,
+        data: web::Data<ActixAppState>,
+    ) -> impl Responder {
+        let mut instance_service =
+            match InstanceService::new(&data.scheduler_address, &data.etcd_address, data.event_ttl)
+                .await
+            {
+                Ok(instance) => instance,
+                Err(e) => return e.to_http(),
+            };
+
+        let (limit, offset) = pagination.map_or((0, 0), |p| (p.limit, p.offset));
+        instance_service
+            .get_all_instances(limit, offset, &namespace)
+            .await
+            .to_http()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::http::StatusCode;
+    use actix_web::{test, App};
+
+    #[actix_web::test]
+    async fn test_instance_routes_are_read_only() {
+        let app = test::init_service(App::new().service(InstanceController {}.services())).await;
+
+        // the routes used by kudoctl are served, the handlers fail without their state
+        for uri in ["/instance/default", "/instance/default/web-1"] {
+            let response =
+                test::call_service(&app, test::TestRequest::get().uri(uri).to_request()).await;
+            assert_ne!(response.status(), StatusCode::NOT_FOUND, "{}", uri);
+            assert_ne!(response.status(), StatusCode::METHOD_NOT_ALLOWED, "{}", uri);
+        }
+
+        let delete = test::TestRequest::delete()
+            .uri("/instance/default/web-1")
+            .to_request();
+        let response = test::call_service(&app, delete).await;
+        assert_eq!(response.status(), StatusCode::METHOD_NOT_ALLOWED);
+
+        let put = test::TestRequest::put()
+            .uri("/instance/default")
+            .to_request();
+        let response = test::call_service(&app, put).await;
+        assert_eq!(response.status(), StatusCode::METHOD_NOT_ALLOWED);
+    }
+}
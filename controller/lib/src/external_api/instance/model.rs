@@ -0,0 +1,383 @@
+use std::collections::HashMap;
+
+use actix_web::HttpResponse;
+use proto::{controller, scheduler};
+use serde::{Deserialize, Serialize};
+
+use crate::external_api::workload::model::{
+    Affinity, NodeSelectorTerm, Ports, Probe, Ressources, RestartPolicy, SelectorOperator,
+    TaintEffect, Toleration, Type, Workload,
+};
+
+pub enum InstanceError {
+    InstanceNotFound,
+    Etcd(String),
+    Grpc(String),
+    JsonToInstance(String),
+    InstanceToJson(String),
+}
+
+impl InstanceError {
+    pub fn to_http(&self) -> HttpResponse {
+        match self {
+            InstanceError::InstanceNotFound => HttpResponse::NotFound().body("Instance not found"),
+            InstanceError::Etcd(err) => {
+                HttpResponse::InternalServerError().body(format!("Etcd error: {} ", err))
+            }
+            InstanceError::Grpc(err) => {
+                HttpResponse::InternalServerError().body(format!("Scheduler error: {} ", err))
+            }
+            InstanceError::JsonToInstance(err) => HttpResponse::InternalServerError().body(
+                format!("Error while converting JSON string to instance : {}", err),
+            ),
+            InstanceError::InstanceToJson(err) => HttpResponse::InternalServerError().body(
+                format!("Error while converting the instance to JSON: {}", err),
+            ),
+        }
+    }
+}
+
+impl std::fmt::Display for InstanceError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InstanceError::InstanceNotFound => write!(f, "Instance not found"),
+            InstanceError::Etcd(err) => write!(f, "Etcd error: {}", err),
+            InstanceError::Grpc(err) => write!(f, "Scheduler error: {}", err),
+            InstanceError::JsonToInstance(err) => write!(f, "JSON to instance error: {}", err),
+            InstanceError::InstanceToJson(err) => write!(f, "Instance to JSON error: {}", err),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InstanceStatus {
+    Running,
+    Starting,
+    Stopped,
+    Stopping,
+    Destroying,
+    Terminated,
+    Failed,
+    Scheduling,
+    Scheduled,
+    /// No node can run the instance yet, the scheduler places it once capacity frees up
+    Pending,
+    /// The node keeps restarting the instance, which keeps exiting
+    CrashLoopBackOff,
+    /// The node of the instance failed, so the instance is replaced
+    Lost,
+}
+
+impl InstanceStatus {
+    /// Returns true if the instance is running or on its way to run. Crash looping instances
+    /// are still restarted by their node, and lost instances are moved to another node by the
+    /// scheduler.
+    pub fn is_active(&self) -> bool {
+        matches!(
+            self,
+            InstanceStatus::Running
+                | InstanceStatus::Starting
+                | InstanceStatus::Scheduling
+                | InstanceStatus::Scheduled
+                | InstanceStatus::Pending
+                | InstanceStatus::CrashLoopBackOff
+                | InstanceStatus::Lost
+        )
+    }
+}
+
+impl From<scheduler::Status> for InstanceStatus {
+    fn from(status: scheduler::Status) -> Self {
+        match status {
+            scheduler::Status::Running => InstanceStatus::Running,
+            scheduler::Status::Starting => InstanceStatus::Starting,
+            scheduler::Status::Stopped => InstanceStatus::Stopped,
+            scheduler::Status::Stopping => InstanceStatus::Stopping,
+            scheduler::Status::Destroying => InstanceStatus::Destroying,
+            scheduler::Status::Terminated => InstanceStatus::Terminated,
+            scheduler::Status::Failed => InstanceStatus::Failed,
+            scheduler::Status::Scheduling => InstanceStatus::Scheduling,
+            scheduler::Status::Scheduled => InstanceStatus::Scheduled,
+            scheduler::Status::CrashLoopBackOff => InstanceStatus::CrashLoopBackOff,
+            scheduler::Status::Pending => InstanceStatus::Pending,
+        }
+    }
+}
+
+impl From<controller::InstanceState> for InstanceStatus {
+    fn from(state: controller::InstanceState) -> Self {
+        match state {
+            controller::InstanceState::Running => InstanceStatus::Running,
+            controller::InstanceState::Starting => InstanceStatus::Starting,
+            controller::InstanceState::Stopped => InstanceStatus::Stopped,
+            controller::InstanceState::Stopping => InstanceStatus::Stopping,
+            controller::InstanceState::Destroying => InstanceStatus::Destroying,
+            controller::InstanceState::Terminated => InstanceStatus::Terminated,
+            controller::InstanceState::Failed => InstanceStatus::Failed,
+            controller::InstanceState::Scheduling => InstanceStatus::Scheduling,
+            controller::InstanceState::Scheduled => InstanceStatus::Scheduled,
+            controller::InstanceState::CrashLoopBackOff => InstanceStatus::CrashLoopBackOff,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct Instance {
+    pub id: String,
+    /// Name of the workload the instance was created from
+    pub name: String,
+    pub namespace: String,
+    pub r#type: Type,
+    pub uri: String,
+    pub environment: Vec<String>,
+    pub resources: Ressources,
+    pub ports: Vec<Ports>,
+    pub status: InstanceStatus,
+    pub ip: String,
+    /// Revision of the workload the instance was created from
+    pub workload_revision: u64,
+    #[serde(default)]
+    pub restart_policy: RestartPolicy,
+    /// Number of times the node restarted the instance
+    #[serde(default)]
+    pub restart_count: u32,
+    #[serde(default)]
+    pub liveness_probe: Option<Probe>,
+    #[serde(default)]
+    pub readiness_probe: Option<Probe>,
+    /// The instance is running and its readiness probe succeeds
+    #[serde(default)]
+    pub ready: bool,
+    #[serde(default)]
+    pub node_selector: HashMap<String, String>,
+    #[serde(default)]
+    pub affinity: Option<Affinity>,
+    #[serde(default)]
+    pub tolerations: Vec<Toleration>,
+    #[serde(default)]
+    pub priority: i32,
+}
+
+impl Instance {
+    pub fn from_workload(id: String, workload: &Workload) -> Self {
+        Instance {
+            id,
+            name: workload.name.clone(),
+            namespace: workload.namespace.clone(),
+            r#type: workload.workload_type.clone(),
+            uri: workload.uri.clone(),
+            environment: workload.environment.clone(),
+            resources: workload.resources.clone(),
+            ports: workload.ports.clone(),
+            status: InstanceStatus::Scheduling,
+            ip: String::new(),
+            workload_revision: workload.revision,
+            restart_policy: workload.restart_policy,
+            restart_count: 0,
+            liveness_probe: workload.liveness_probe.clone(),
+            readiness_probe: workload.readiness_probe.clone(),
+            ready: false,
+            node_selector: workload.node_selector.clone(),
+            affinity: workload.affinity.clone(),
+            tolerations: workload.tolerations.clone(),
+            priority: workload.priority,
+        }
+    }
+
+    pub fn to_http(&self) -> HttpResponse {
+        match serde_json::to_string(&self) {
+            Ok(json) => HttpResponse::Ok().body(json),
+            Err(err) => HttpResponse::InternalServerError().body(format!(
+                "Error while converting the instance to json: {}",
+                err
+            )),
+        }
+    }
+}
+
+impl From<&Instance> for scheduler::Instance {
+    fn from(instance: &Instance) -> Self {
+        let limit = scheduler::ResourceSummary {
+            cpu: instance.resources.cpu,
+            memory: instance.resources.memory,
+            disk: instance.resources.disk,
+        };
+
+        scheduler::Instance {
+            id: instance.id.clone(),
+            name: instance.name.clone(),
+            r#type: scheduler::Type::Container.into(),
+            status: scheduler::Status::Scheduling.into(),
+            uri: instance.uri.clone(),
+            environnement: instance.environment.clone(),
+            resource: Some(scheduler::Resource {
+                limit: Some(limit),
+                usage: None,
+                request: Some(instance.resources.requested()),
+            }),
+            ports: instance
+                .ports
+                .iter()
+                .map(|port| scheduler::Port {
+                    source: port.source,
+                    destination: port.destination,
+                })
+                .collect(),
+            ip: instance.ip.clone(),
+            restart_policy: match instance.restart_policy {
+                RestartPolicy::Always => scheduler::RestartPolicy::Always,
+                RestartPolicy::OnFailure => scheduler::RestartPolicy::OnFailure,
+                RestartPolicy::Never => scheduler::RestartPolicy::Never,
+            }
+            .into(),
+            liveness_probe: instance.liveness_probe.as_ref().map(scheduler::Probe::from),
+            readiness_probe: instance
+                .readiness_probe
+                .as_ref()
+                .map(scheduler::Probe::from),
+            node_selector: instance.node_selector.clone(),
+            affinity: instance.affinity.as_ref().map(scheduler::Affinity::from),
+            tolerations: instance
+                .tolerations
+                .iter()
+                .map(scheduler::Toleration::from)
+                .collect(),
+            workload: format!("{}/{}", instance.namespace, instance.name),
+            priority: instance.priority,
+        }
+    }
+}
+
+impl From<&Affinity> for scheduler::Affinity {
+    fn from(affinity: &Affinity) -> Self {
+        scheduler::Affinity {
+            required: affinity
+                .required
+                .iter()
+                .map(scheduler::NodeSelectorTerm::from)
+                .collect(),
+            preferred: affinity
+                .preferred
+                .iter()
+                .map(|preferred| scheduler::PreferredTerm {
+                    weight: preferred.weight,
+                    term: Some(scheduler::NodeSelectorTerm::from(&preferred.term)),
+                })
+                .collect(),
+            anti_affinity: affinity.anti_affinity.as_ref().map(|anti_affinity| {
+                scheduler::AntiAffinity {
+                    required: anti_affinity.required,
+                    weight: anti_affinity.weight,
+                }
+            }),
+        }
+    }
+}
+
+impl From<&NodeSelectorTerm> for scheduler::NodeSelectorTerm {
+    fn from(term: &NodeSelectorTerm) -> Self {
+        scheduler::NodeSelectorTerm {
+            requirements: term
+                .requirements
+                .iter()
+                .map(|requirement| scheduler::NodeSelectorRequirement {
+                    key: requirement.key.clone(),
+                    operator: match requirement.operator {
+                        SelectorOperator::In => scheduler::node_selector_requirement::Operator::In,
+                        SelectorOperator::NotIn => {
+                            scheduler::node_selector_requirement::Operator::NotIn
+                        }
+                        SelectorOperator::Exists => {
+                            scheduler::node_selector_requirement::Operator::Exists
+                        }
+                        SelectorOperator::DoesNotExist => {
+                            scheduler::node_selector_requirement::Operator::DoesNotExist
+                        }
+                    }
+                    .into(),
+                    values: requirement.values.clone(),
+                })
+                .collect(),
+        }
+    }
+}
+
+impl From<&Toleration> for scheduler::Toleration {
+    fn from(toleration: &Toleration) -> Self {
+        scheduler::Toleration {
+            key: toleration.key.clone(),
+            value: toleration.value.clone(),
+            effects: toleration
+                .effect
+                .iter()
+                .map(|effect| scheduler::TaintEffect::from(*effect).into())
+                .collect(),
+        }
+    }
+}
+
+impl From<TaintEffect> for scheduler::TaintEffect {
+    fn from(effect: TaintEffect) -> Self {
+        match effect {
+            TaintEffect::NoSchedule => scheduler::TaintEffect::NoSchedule,
+            TaintEffect::PreferNoSchedule => scheduler::TaintEffect::PreferNoSchedule,
+        }
+    }
+}
+
+impl From<&Probe> for scheduler::Probe {
+    fn from(probe: &Probe) -> Self {
+        let action = if let Some(http_get) = &probe.http_get {
+            Some(scheduler::probe::Action::HttpGet(
+                scheduler::HttpGetAction {
+                    path: http_get.path.clone(),
+                    port: http_get.port,
+                },
+            ))
+        } else if let Some(tcp_socket) = &probe.tcp_socket {
+            Some(scheduler::probe::Action::TcpSocket(
+                scheduler::TcpSocketAction {
+                    port: tcp_socket.port,
+                },
+            ))
+        } else {
+            probe.exec.as_ref().map(|exec| {
+                scheduler::probe::Action::Exec(scheduler::ExecAction {
+                    command: exec.command.clone(),
+                })
+            })
+        };
+
+        scheduler::Probe {
+            action,
+            initial_delay_seconds: probe.initial_delay_seconds,
+            period_seconds: probe.period_seconds,
+            timeout_seconds: probe.timeout_seconds,
+            failure_threshold: probe.failure_threshold,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct InstanceVector {
+    pub count: usize,
+    pub instances: Vec<Instance>,
+}
+
+impl InstanceVector {
+    pub fn new(instances: Vec<Instance>) -> InstanceVector {
+        InstanceVector {
+            count: instances.len(),
+            instances,
+        }
+    }
+    pub fn to_http(&self) -> HttpResponse {
+        match serde_json::to_string(&self) {
+            Ok(json) => HttpResponse::Ok().body(json),
+            Err(err) => HttpResponse::InternalServerError().body(format!(
+                "Error while converting the instances to json: {}",
+                err
+            )),
+        }
+    }
+}
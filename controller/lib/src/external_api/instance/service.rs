@@ -0,0 +1,499 @@
+use std::collections::{HashMap, HashSet};
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use log::{error, warn};
+use proto::scheduler::{self, InstanceIdentifier};
+use tonic::{Request, Streaming};
+
+/// Time between two attempts to watch an instance again, once its status stream failed
+const REATTACH_INTERVAL: Duration = Duration::from_secs(2);
+
+use super::model::{Instance, InstanceError, InstanceStatus, InstanceVector};
+use crate::etcd::EtcdClient;
+use crate::event::model::InvolvedKind;
+use crate::event::service::EventService;
+use crate::external_api::generic::filter::FilterService;
+use crate::external_api::workload::model::Workload;
+use crate::grpc_client::interface::SchedulerClientInterface;
+
+/// `InstanceService` is a service that manages the instances of the workloads. Instances are
+/// stored in etcd and created, started, stopped or destroyed through the scheduler.
+/// Properties:
+///
+/// * `grpc_service`: This is the client used to interact with the scheduler.
+/// * `etcd_service`: This is the service that will be used to interact with etcd.
+/// * `filter_service`: This is the service that will be used to filter the instances.
+/// * `event_ttl`: Time to live of the events recorded about the instances, in seconds.
+pub struct InstanceService {
+    grpc_service: SchedulerClientInterface,
+    etcd_service: EtcdClient,
+    filter_service: FilterService,
+    event_ttl: i64,
+}
+
+impl InstanceService {
+    pub async fn new(
+        grpc_address: &SocketAddr,
+        etcd_address: &SocketAddr,
+        event_ttl: i64,
+    ) -> Result<InstanceService, InstanceError> {
+        let inner = InstanceService {
+            grpc_service: SchedulerClientInterface::new(format!("http://{}", grpc_address))
+                .await
+                .map_err(|err| InstanceError::Grpc(format!("{:?}", err)))?,
+            etcd_service: EtcdClient::new(etcd_address.to_string())
+                .await
+                .map_err(|err| InstanceError::Etcd(err.to_string()))?,
+            filter_service: FilterService::new(),
+            event_ttl,
+        };
+        Ok(inner)
+    }
+
+    pub async fn get_instance(
+        &mut self,
+        instance_id: &str,
+        namespace: &str,
+    ) -> Result<Instance, InstanceError> {
+        get_instance(&mut self.etcd_service, &Self::id(instance_id, namespace)).await
+    }
+
+    /// This function gets all the instances of a namespace from etcd and slice the result by
+    /// limit and offset. If there is an error , the function always return an empty vector
+    ///
+    /// # Arguments:
+    ///
+    /// * `limit`: The number of instances to return.
+    /// * `offset`: The offset of the instances to be returned.
+    /// * `namespace`: The namespace to filter by.
+    ///
+    /// # Returns:
+    ///
+    /// A vector of instances
+    pub async fn get_all_instances(
+        &mut self,
+        limit: u32,
+        offset: u32,
+        namespace: &str,
+    ) -> InstanceVector {
+        let mut instances = match self.etcd_service.get_prefix(&Self::id("", namespace)).await {
+            Some(instances) => instances
+                .iter()
+                .filter_map(|instance| serde_json::from_str::<Instance>(instance).ok())
+                .filter(|instance| instance.namespace == namespace)
+                .collect(),
+            None => vec![],
+        };
+
+        if offset > 0 {
+            match self.filter_service.offset(&instances, offset) {
+                Ok(filtered) => instances = filtered,
+                Err(_) => return InstanceVector::new(vec![]),
+            }
+        }
+        if limit > 0 {
+            instances = self.filter_service.limit(&instances, limit);
+        }
+        InstanceVector::new(instances)
+    }
+
+    /// It creates an instance of a workload, stores it in etcd and asks the scheduler to run it.
+    /// The status of the instance is then kept up to date in the background with the updates
+    /// streamed back by the scheduler.
+    ///
+    /// # Arguments:
+    ///
+    /// * `workload`: The workload to create an instance of
+    ///
+    /// # Returns:
+    ///
+    /// The created instance
+    pub async fn create_instance(
+        &mut self,
+        workload: &Workload,
+    ) -> Result<Instance, InstanceError> {
+        let instance = Instance::from_workload(format!("{:016x}", rand::random::<u64>()), workload);
+        let key = Self::id(&instance.id, &instance.namespace);
+        put_instance(&mut self.etcd_service, &key, &instance).await?;
+
+        let stream = match self
+            .grpc_service
+            .create_instance(Request::new(scheduler::Instance::from(&instance)))
+            .await
+        {
+            Ok(response) => response.into_inner(),
+            Err(err) => {
+                _ = self.etcd_service.delete(&key).await;
+                return Err(InstanceError::Grpc(format!("{:?}", err)));
+            }
+        };
+
+        Self::watch_instance(
+            self.grpc_service.clone(),
+            self.etcd_service.clone(),
+            EventService::from_client(self.etcd_service.clone(), self.event_ttl),
+            instance.clone(),
+            stream,
+        );
+        Ok(instance)
+    }
+
+    /// It asks the scheduler to destroy an instance and removes it from etcd
+    pub async fn delete_instance(
+        &mut self,
+        instance_id: &str,
+        namespace: &str,
+    ) -> Result<(), InstanceError> {
+        let instance = self.get_instance(instance_id, namespace).await?;
+
+        self.grpc_service
+            .destroy_instance(Request::new(InstanceIdentifier {
+                id: instance.id.clone(),
+            }))
+            .await
+            .map_err(|err| InstanceError::Grpc(format!("{:?}", err)))?;
+
+        _ = self
+            .etcd_service
+            .delete(&Self::id(&instance.id, namespace))
+            .await;
+        Ok(())
+    }
+
+    /// It returns the instances of every namespace. It fails if etcd can't be reached or if an
+    /// instance can't be deserialized.
+    pub async fn get_instances(&mut self) -> Result<Vec<Instance>, InstanceError> {
+        get_instances(&mut self.etcd_service).await
+    }
+
+    /// It removes an instance which is not running anymore from etcd, without asking the
+    /// scheduler to destroy it.
+    pub async fn forget_instance(&mut self, instance: &Instance) {
+        _ = self
+            .etcd_service
+            .delete(&Self::id(&instance.id, &instance.namespace))
+            .await;
+    }
+
+    /// It spawns a task updating the status of the instance stored in etcd each time the
+    /// scheduler sends a new one. Each update is recorded as an event of the instance, whose
+    /// reason is the one given by the scheduler, e.g. `Preempted`, or else the status. The
+    /// instance keeps its last status when the stream fails, e.g. because the scheduler
+    /// restarted, until it is watched again.
+    fn watch_instance(
+        mut grpc_service: SchedulerClientInterface,
+        mut etcd_service: EtcdClient,
+        mut event_service: EventService,
+        instance: Instance,
+        mut stream: Streaming<scheduler::InstanceStatus>,
+    ) {
+        let key = Self::id(&instance.id, &instance.namespace);
+
+        tokio::spawn(async move {
+            loop {
+                let status = match received(&key, stream.message().await) {
+                    Some(status) => status,
+                    None => {
+                        match reattach(&mut grpc_service, &mut etcd_service, &instance.id, &key)
+                            .await
+                        {
+                            Some(reattached) => {
+                                stream = reattached;
+                                continue;
+                            }
+                            // the instance has been deleted
+                            None => break,
+                        }
+                    }
+                };
+                let reason = match status.reason.is_empty() {
+                    true => format!("{:?}", InstanceStatus::from(status.status())),
+                    false => status.reason.clone(),
+                };
+
+                if let Err(err) = event_service
+                    .record(
+                        &instance.namespace,
+                        InvolvedKind::Instance,
+                        &instance.id,
+                        &reason,
+                        &status.status_description,
+                    )
+                    .await
+                {
+                    error!("Failed to record instance {} event : {}", key, err);
+                }
+
+                let mut instance = match get_instance(&mut etcd_service, &key).await {
+                    Ok(instance) => instance,
+                    // the instance has been deleted
+                    Err(_) => break,
+                };
+                apply_status(&mut instance, &status);
+                if let Err(err) = put_instance(&mut etcd_service, &key, &instance).await {
+                    error!("Failed to update instance {} status : {}", key, err);
+                }
+
+                if status.status() == scheduler::Status::Failed {
+                    break;
+                }
+            }
+        });
+    }
+
+    pub fn id(instance_id: &str, namespace: &str) -> String {
+        format!("instance.{}.{}", namespace, instance_id)
+    }
+}
+
+/// It returns the status sent by the scheduler, or None once the stream ended or failed, e.g.
+/// because the scheduler restarted, as the instance still runs on its node
+fn received(
+    key: &str,
+    message: Result<Option<scheduler::InstanceStatus>, tonic::Status>,
+) -> Option<scheduler::InstanceStatus> {
+    match message {
+        Ok(Some(status)) => Some(status),
+        Ok(None) => {
+            warn!("Instance {} status stream closed, watching it again", key);
+            None
+        }
+        Err(err) => {
+            warn!(
+                "Instance {} status stream failed, watching it again : {}",
+                key, err
+            );
+            None
+        }
+    }
+}
+
+/// It applies a status sent by the scheduler to an instance
+fn apply_status(instance: &mut Instance, status: &scheduler::InstanceStatus) {
+    instance.status = InstanceStatus::from(status.status());
+    instance.restart_count = status.restart_count;
+    instance.ready = status.ready;
+    // the scheduler gives the address once the node is chosen, and takes it back when the
+    // instance is pending again, e.g. once preempted
+    if !status.ip.is_empty() || status.status() == scheduler::Status::Pending {
+        instance.ip = status.ip.clone();
+    }
+}
+
+/// It asks the scheduler for the statuses of an instance again, until it answers or the
+/// instance is deleted.
+///
+/// # Returns:
+///
+/// The new stream of the statuses of the instance, or None if it has been deleted
+async fn reattach(
+    grpc_service: &mut SchedulerClientInterface,
+    etcd_service: &mut EtcdClient,
+    instance_id: &str,
+    key: &str,
+) -> Option<Streaming<scheduler::InstanceStatus>> {
+    loop {
+        tokio::time::sleep(REATTACH_INTERVAL).await;
+        get_instance(etcd_service, key).await.ok()?;
+
+        let request = Request::new(InstanceIdentifier {
+            id: instance_id.to_string(),
+        });
+        match grpc_service.watch_instance(request).await {
+            Ok(response) => return Some(response.into_inner()),
+            Err(err) => warn!("Failed to watch instance {} : {:?}", key, err),
+        }
+    }
+}
+
+/// The status, restart count and readiness of an instance, as reported by its node
+pub type InstanceReport = (InstanceStatus, u32, bool);
+
+/// It sets the status, restart count and readiness of the instances stored in etcd, for the
+/// known instances one of them changed for. The instances are listed once, and looked up by id
+/// only as nodes don't know about namespaces.
+///
+/// # Arguments:
+///
+/// * `reports`: The last report of each instance of a node, by instance id
+///
+/// # Returns:
+///
+/// The result of the update of each instance, `InstanceNotFound` for the instances which
+/// aren't stored, or an error if the instances can't be listed
+pub async fn update_instance_statuses(
+    etcd_service: &mut EtcdClient,
+    reports: &[(String, InstanceReport)],
+) -> Result<Vec<(String, Result<(), InstanceError>)>, InstanceError> {
+    if reports.is_empty() {
+        return Ok(vec![]);
+    }
+    let mut instances: HashMap<String, Instance> = get_instances(etcd_service)
+        .await?
+        .into_iter()
+        .map(|instance| (instance.id.clone(), instance))
+        .collect();
+
+    let mut results = vec![];
+    for (id, report) in reports {
+        let result = match instances.remove(id) {
+            Some(mut instance) => match apply_report(&mut instance, *report) {
+                true => {
+                    let key = InstanceService::id(&instance.id, &instance.namespace);
+                    put_instance(etcd_service, &key, &instance).await
+                }
+                false => Ok(()),
+            },
+            None => Err(InstanceError::InstanceNotFound),
+        };
+        results.push((id.clone(), result));
+    }
+    Ok(results)
+}
+
+/// It applies the report of its node to an instance, and returns true if it changed
+fn apply_report(instance: &mut Instance, (status, restart_count, ready): InstanceReport) -> bool {
+    let changed = instance.status != status
+        || instance.restart_count != restart_count
+        || instance.ready != ready;
+    instance.status = status;
+    instance.restart_count = restart_count;
+    instance.ready = ready;
+    changed
+}
+
+/// It marks the instances stored in etcd with the given ids as lost along with their node, and
+/// returns them. The instances are listed once, and looked up by id only as nodes don't know
+/// about namespaces. The ids of the instances which aren't stored anymore are skipped.
+pub async fn mark_instances_lost(
+    etcd_service: &mut EtcdClient,
+    instance_ids: &[String],
+) -> Result<Vec<Instance>, InstanceError> {
+    if instance_ids.is_empty() {
+        return Ok(vec![]);
+    }
+    let instance_ids: HashSet<&str> = instance_ids.iter().map(String::as_str).collect();
+
+    let mut lost = vec![];
+    for mut instance in get_instances(etcd_service).await? {
+        if !instance_ids.contains(instance.id.as_str()) {
+            continue;
+        }
+        instance.status = InstanceStatus::Lost;
+        instance.ready = false;
+        let key = InstanceService::id(&instance.id, &instance.namespace);
+        match put_instance(etcd_service, &key, &instance).await {
+            Ok(()) => lost.push(instance),
+            Err(err) => error!("Failed to mark instance {} as lost : {}", instance.id, err),
+        }
+    }
+    Ok(lost)
+}
+
+async fn get_instances(etcd_service: &mut EtcdClient) -> Result<Vec<Instance>, InstanceError> {
+    etcd_service
+        .list_prefix("instance.")
+        .await
+        .map_err(|err| InstanceError::Etcd(err.to_string()))?
+        .iter()
+        .map(|instance| {
+            serde_json::from_str::<Instance>(instance)
+                .map_err(|err| InstanceError::JsonToInstance(err.to_string()))
+        })
+        .collect()
+}
+
+async fn get_instance(etcd_service: &mut EtcdClient, key: &str) -> Result<Instance, InstanceError> {
+    match etcd_service.get(key).await {
+        Some(instance) => serde_json::from_str(&instance)
+            .map_err(|err| InstanceError::JsonToInstance(err.to_string())),
+        None => Err(InstanceError::InstanceNotFound),
+    }
+}
+
+async fn put_instance(
+    etcd_service: &mut EtcdClient,
+    key: &str,
+    instance: &Instance,
+) -> Result<(), InstanceError> {
+    let json = serde_json::to_string(instance)
+        .map_err(|err| InstanceError::InstanceToJson(err.to_string()))?;
+    etcd_service
+        .put(key, &json)
+        .await
+        .map_err(|err| InstanceError::Etcd(err.to_string()))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::external_api::workload::model::WorkloadDTO;
+
+    fn instance() -> Instance {
+        let dto: WorkloadDTO = serde_json::from_str(
+            r#"{"name": "web", "environment": [], "ports": [], "uri": "nginx"}"#,
+        )
+        .unwrap();
+        let workload = dto.into_workload("default.web".to_string(), "default", 1);
+        Instance::from_workload("web-1".to_string(), &workload)
+    }
+
+    #[test]
+    fn test_scheduler_restart_keeps_the_instances() {
+        let mut instances = vec![instance(), instance()];
+        for instance in &mut instances {
+            let running = scheduler::InstanceStatus {
+                status: scheduler::Status::Running.into(),
+                ip: "10.0.1.2".to_string(),
+                ready: true,
+                ..Default::default()
+            };
+            apply_status(instance, &received("web-1", Ok(Some(running))).unwrap());
+        }
+
+        // the stream fails then ends when the scheduler stops, and the scheduler sends no status
+        // once the instances are watched again
+        let restart = [Err(tonic::Status::unavailable("transport error")), Ok(None)];
+        for message in restart {
+            assert!(received("web-1", message).is_none());
+        }
+
+        for instance in &instances {
+            assert_eq!(instance.status, InstanceStatus::Running);
+            assert_eq!(instance.ip, "10.0.1.2");
+            assert!(instance.ready);
+        }
+        let replicas = instances
+            .iter()
+            .filter(|instance| instance.status.is_active())
+            .count();
+        assert_eq!(replicas, 2);
+    }
+
+    #[test]
+    fn test_apply_report() {
+        let mut instance = instance();
+
+        assert!(apply_report(
+            &mut instance,
+            (InstanceStatus::Running, 0, true)
+        ));
+        assert_eq!(instance.status, InstanceStatus::Running);
+        assert!(instance.ready);
+
+        // the same report doesn't change the instance
+        assert!(!apply_report(
+            &mut instance,
+            (InstanceStatus::Running, 0, true)
+        ));
+
+        assert!(apply_report(
+            &mut instance,
+            (InstanceStatus::Running, 1, false)
+        ));
+        assert_eq!(instance.restart_count, 1);
+        assert!(!instance.ready);
+    }
+}
@@ -1,36 +1,109 @@
+use super::event;
+use super::instance;
+use super::node;
 use super::workload;
+use crate::leader::Leadership;
+use actix_web::dev::ServerHandle;
 use actix_web::middleware::Logger;
-use actix_web::{web, App, HttpResponse, HttpServer};
+use actix_web::{web, App, HttpResponse, HttpServer, Responder};
 use log::info;
+use serde::Serialize;
+use shutdown::Shutdown;
 use std::net::SocketAddr;
+use std::time::Duration;
+use tokio::task::JoinHandle;
 
-pub struct ExternalAPIInterface {}
+pub struct ExternalAPIInterface {
+    server: JoinHandle<std::io::Result<()>>,
+}
 
 pub struct ActixAppState {
     pub etcd_address: SocketAddr,
+    pub scheduler_address: SocketAddr,
+    pub revision_history_limit: usize,
+    pub event_ttl: i64,
+}
+
+/// Body of the `/ready` route
+#[derive(Serialize)]
+struct ReadinessDTO {
+    identity: String,
+    leader: Option<String>,
+    is_leader: bool,
+}
+
+/// A controller is ready once the controllers have elected a leader
+async fn ready(leadership: web::Data<Leadership>) -> impl Responder {
+    let readiness = ReadinessDTO {
+        identity: leadership.identity().to_string(),
+        leader: leadership.leader(),
+        is_leader: leadership.is_leader(),
+    };
+    match readiness.leader {
+        Some(_) => HttpResponse::Ok().json(readiness),
+        None => HttpResponse::ServiceUnavailable().json(readiness),
+    }
 }
 
 impl ExternalAPIInterface {
-    pub async fn new(address: SocketAddr, num_workers: usize, etcd_address: SocketAddr) -> Self {
+    /// It starts the HTTP server. Once `shutdown` is triggered, it stops accepting connections
+    /// and gives the requests in flight `shutdown_timeout` to complete.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn new(
+        address: SocketAddr,
+        num_workers: usize,
+        etcd_address: SocketAddr,
+        scheduler_address: SocketAddr,
+        revision_history_limit: usize,
+        event_ttl: i64,
+        shutdown_timeout: Duration,
+        leadership: Leadership,
+        mut shutdown: Shutdown,
+    ) -> std::io::Result<Self> {
         info!(
             "Starting {} HTTP worker(s) listening on {}",
             num_workers, address
         );
 
-        HttpServer::new(move || {
+        let server = HttpServer::new(move || {
             App::new()
-                .app_data(web::Data::new(ActixAppState { etcd_address }))
+                .app_data(web::Data::new(ActixAppState {
+                    etcd_address,
+                    scheduler_address,
+                    revision_history_limit,
+                    event_ttl,
+                }))
+                .app_data(web::Data::new(leadership.clone()))
                 .route("/health", web::get().to(HttpResponse::Ok))
+                .route("/ready", web::get().to(ready))
                 .service(workload::controller::WorkloadController {}.services())
+                .service(instance::controller::InstanceController {}.services())
+                .service(event::controller::EventController {}.services())
+                .service(node::controller::NodeController {}.services())
                 .wrap(Logger::default())
         })
         .workers(num_workers)
-        .bind(address)
-        .unwrap()
-        .run()
-        .await
-        .unwrap();
+        // signals are handled along with the other servers
+        .disable_signals()
+        .shutdown_timeout(shutdown_timeout.as_secs())
+        .bind(address)?
+        .run();
+
+        let handle: ServerHandle = server.handle();
+        tokio::spawn(async move {
+            shutdown.wait().await;
+            handle.stop(true).await;
+        });
+
+        Ok(Self {
+            server: tokio::spawn(server),
+        })
+    }
 
-        Self {}
+    /// It waits for the HTTP server to stop
+    pub async fn wait(self) -> Result<(), Box<dyn std::error::Error>> {
+        self.server.await??;
+        info!("HTTP server stopped");
+        Ok(())
     }
 }
@@ -1,3 +1,6 @@
+mod event;
 pub mod generic;
+pub mod instance;
 pub mod interface;
-mod workload;
+pub mod node;
+pub mod workload;
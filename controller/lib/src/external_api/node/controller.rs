@@ -0,0 +1,146 @@
+use crate::external_api::interface::ActixAppState;
+
+use super::model::{DrainDTO, NodeCordon, NodeDTO};
+use super::service::NodeService;
+use actix_web::http::StatusCode;
+use actix_web::{web, HttpResponse, Responder, Scope};
+
+pub struct NodeController {}
+impl NodeController {
+    pub fn services(&self) -> Scope {
+        web::scope("/node")
+            .service(
+                web::resource("/{node_id}/cordon")
+                    .route(web::put().to(NodeController::cordon_node))
+                    .route(web::delete().to(NodeController::uncordon_node)),
+            )
+            .service(
+                web::resource("/{node_id}/drain")
+                    .route(web::post().to(NodeController::drain_node))
+                    .route(web::get().to(NodeController::get_drain)),
+            )
+            .service(web::resource("/{node_id}").route(web::patch().to(NodeController::patch_node)))
+    }
+
+    /// `patch_node` is an async function that handle **/node/\<node_id>** route (PATCH)
+    /// # Description:
+    /// * Replace the labels or the taints of a node
+    /// # Arguments:
+    ///
+    /// * `node_id`: web::Path<String> - The id of the node.
+    /// * `body`: web::Json<NodeDTO> - The new labels or taints of the node.
+    pub async fn patch_node(
+        node_id: web::Path<String>,
+        body: web::Json<NodeDTO>,
+        data: web::Data<ActixAppState>,
+    ) -> impl Responder {
+        let mut node_service = match NodeService::new(&data.scheduler_address).await {
+            Ok(node_service) => node_service,
+            Err(e) => return e.to_http(),
+        };
+
+        node_service
+            .update_node(&node_id, body.into_inner())
+            .await
+            .map_or_else(
+                |e| e.to_http(),
+                |_| HttpResponse::build(StatusCode::NO_CONTENT).finish(),
+            )
+    }
+
+    /// `cordon_node` is an async function that handle **/node/\<node_id>/cordon** route (PUT)
+    /// # Description:
+    /// * Stop placing new instances on a node, the instances already there keep running
+    /// # Arguments:
+    ///
+    /// * `node_id`: web::Path<String> - The id of the node.
+    pub async fn cordon_node(
+        node_id: web::Path<String>,
+        data: web::Data<ActixAppState>,
+    ) -> impl Responder {
+        NodeController::set_cordoned(&node_id, true, &data).await
+    }
+
+    /// `uncordon_node` is an async function that handle **/node/\<node_id>/cordon** route (DELETE)
+    /// # Description:
+    /// * Place new instances on a node again, stopping its drain if any
+    /// # Arguments:
+    ///
+    /// * `node_id`: web::Path<String> - The id of the node.
+    pub async fn uncordon_node(
+        node_id: web::Path<String>,
+        data: web::Data<ActixAppState>,
+    ) -> impl Responder {
+        NodeController::set_cordoned(&node_id, false, &data).await
+    }
+
+    /// `drain_node` is an async function that handle **/node/\<node_id>/drain** route (POST)
+    /// # Description:
+    /// * Cordon a node, then move its instances to the other nodes
+    /// # Arguments:
+    ///
+    /// * `node_id`: web::Path<String> - The id of the node.
+    /// * `body`: web::Json<DrainDTO> - The instances of a workload moved at the same time.
+    pub async fn drain_node(
+        node_id: web::Path<String>,
+        body: web::Json<DrainDTO>,
+        data: web::Data<ActixAppState>,
+    ) -> impl Responder {
+        let mut node_service = match NodeService::new(&data.scheduler_address).await {
+            Ok(node_service) => node_service,
+            Err(e) => return e.to_http(),
+        };
+
+        node_service
+            .drain_node(&node_id, body.into_inner())
+            .await
+            .map_or_else(|e| e.to_http(), |drain| drain.to_http())
+    }
+
+    /// `get_drain` is an async function that handle **/node/\<node_id>/drain** route (GET)
+    /// # Description:
+    /// * Get the progress of the drain of a node
+    /// # Arguments:
+    ///
+    /// * `node_id`: web::Path<String> - The id of the node.
+    pub async fn get_drain(
+        node_id: web::Path<String>,
+        data: web::Data<ActixAppState>,
+    ) -> impl Responder {
+        let mut node_service = match NodeService::new(&data.scheduler_address).await {
+            Ok(node_service) => node_service,
+            Err(e) => return e.to_http(),
+        };
+
+        node_service
+            .drain_status(&node_id)
+            .await
+            .map_or_else(|e| e.to_http(), |drain| drain.to_http())
+    }
+
+    /// It cordons or uncordons a node through the scheduler
+    async fn set_cordoned(
+        node_id: &str,
+        cordoned: bool,
+        data: &web::Data<ActixAppState>,
+    ) -> HttpResponse {
+        let mut node_service = match NodeService::new(&data.scheduler_address).await {
+            Ok(node_service) => node_service,
+            Err(e) => return e.to_http(),
+        };
+
+        node_service
+            .cordon_node(node_id, cordoned)
+            .await
+            .map_or_else(
+                |e| e.to_http(),
+                |_| {
+                    NodeCordon {
+                        id: node_id.to_string(),
+                        cordoned,
+                    }
+                    .to_http()
+                },
+            )
+    }
+}
@@ -0,0 +1,144 @@
+use std::collections::HashMap;
+
+use actix_web::HttpResponse;
+use proto::scheduler;
+use serde::{Deserialize, Serialize};
+
+use crate::external_api::workload::model::TaintEffect;
+
+pub enum NodeError {
+    NodeNotFound(String),
+    Grpc(String),
+}
+
+impl NodeError {
+    pub fn to_http(&self) -> HttpResponse {
+        match self {
+            NodeError::NodeNotFound(id) => {
+                HttpResponse::NotFound().body(format!("Node {} not found", id))
+            }
+            NodeError::Grpc(err) => {
+                HttpResponse::InternalServerError().body(format!("Scheduler error: {} ", err))
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for NodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NodeError::NodeNotFound(id) => write!(f, "Node {} not found", id),
+            NodeError::Grpc(err) => write!(f, "Scheduler error: {}", err),
+        }
+    }
+}
+
+/// Repels the instances which don't tolerate it from a node
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct Taint {
+    pub key: String,
+    #[serde(default)]
+    pub value: String,
+    pub effect: TaintEffect,
+}
+
+impl From<&Taint> for scheduler::Taint {
+    fn from(taint: &Taint) -> Self {
+        scheduler::Taint {
+            key: taint.key.clone(),
+            value: taint.value.clone(),
+            effect: scheduler::TaintEffect::from(taint.effect).into(),
+        }
+    }
+}
+
+/// Body of the `/node/<node_id>` route, the labels or taints not set are kept
+#[derive(Deserialize, Serialize)]
+pub struct NodeDTO {
+    pub labels: Option<HashMap<String, String>>,
+    pub taints: Option<Vec<Taint>>,
+}
+
+/// Whether new instances are kept off a node, returned by the `/node/<node_id>/cordon` route
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct NodeCordon {
+    pub id: String,
+    pub cordoned: bool,
+}
+
+impl NodeCordon {
+    pub fn to_http(&self) -> HttpResponse {
+        match serde_json::to_string(&self) {
+            Ok(json) => HttpResponse::Ok().body(json),
+            Err(err) => HttpResponse::InternalServerError()
+                .body(format!("Error while converting the node to json: {}", err)),
+        }
+    }
+}
+
+/// Body of the `/node/<node_id>/drain` route
+#[derive(Deserialize, Serialize, Default)]
+pub struct DrainDTO {
+    /// Instances of a workload moved at the same time, 1 if not set
+    pub max_unavailable: Option<u32>,
+}
+
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DrainState {
+    /// The node isn't drained, its instances are kept
+    NotDrained,
+    /// The instances of the node are being moved to the other nodes
+    Draining,
+    /// No instance is left on the node
+    Drained,
+}
+
+impl From<scheduler::DrainState> for DrainState {
+    fn from(state: scheduler::DrainState) -> Self {
+        match state {
+            scheduler::DrainState::NotDrained => DrainState::NotDrained,
+            scheduler::DrainState::Draining => DrainState::Draining,
+            scheduler::DrainState::Drained => DrainState::Drained,
+        }
+    }
+}
+
+/// Progress of the drain of a node.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct NodeDrain {
+    pub id: String,
+    pub state: DrainState,
+    /// Instances still on the node
+    pub remaining: u32,
+    /// Instances moved which aren't running on another node yet
+    pub moving: u32,
+    /// Instances running on another node
+    pub moved: u32,
+    /// Instances no other node could run, waiting to be placed
+    pub evicted: u32,
+    pub message: String,
+}
+
+impl From<scheduler::NodeDrainStatus> for NodeDrain {
+    fn from(status: scheduler::NodeDrainStatus) -> Self {
+        NodeDrain {
+            state: status.state().into(),
+            id: status.id,
+            remaining: status.remaining,
+            moving: status.moving,
+            moved: status.moved,
+            evicted: status.evicted,
+            message: status.description,
+        }
+    }
+}
+
+impl NodeDrain {
+    pub fn to_http(&self) -> HttpResponse {
+        match serde_json::to_string(&self) {
+            Ok(json) => HttpResponse::Ok().body(json),
+            Err(err) => HttpResponse::InternalServerError()
+                .body(format!("Error while converting the drain to json: {}", err)),
+        }
+    }
+}
@@ -0,0 +1,128 @@
+use std::net::SocketAddr;
+
+use proto::scheduler::{
+    NodeCordonRequest, NodeDrainRequest, NodeDrainStatusRequest, NodeLabels, NodeTaints,
+    NodeUpdateRequest,
+};
+use tonic::Request;
+
+use super::model::{DrainDTO, NodeDTO, NodeDrain, NodeError};
+use crate::grpc_client::interface::{SchedulerClientInterface, SchedulerClientInterfaceError};
+
+/// `NodeService` is a service that manages the nodes of the cluster through the scheduler.
+/// Properties:
+///
+/// * `grpc_service`: This is the client used to interact with the scheduler.
+pub struct NodeService {
+    grpc_service: SchedulerClientInterface,
+}
+
+impl NodeService {
+    pub async fn new(grpc_address: &SocketAddr) -> Result<NodeService, NodeError> {
+        Ok(NodeService {
+            grpc_service: SchedulerClientInterface::new(format!("http://{}", grpc_address))
+                .await
+                .map_err(|err| NodeError::Grpc(format!("{:?}", err)))?,
+        })
+    }
+
+    /// It replaces the labels or the taints of a node, the instances placed from then on are
+    /// matched against them.
+    ///
+    /// # Arguments:
+    ///
+    /// * `node_id`: The id of the node, given by the scheduler when it registered.
+    /// * `node_dto`: The new labels or taints, the ones not set are kept.
+    pub async fn update_node(&mut self, node_id: &str, node_dto: NodeDTO) -> Result<(), NodeError> {
+        let request = NodeUpdateRequest {
+            id: node_id.to_string(),
+            labels: node_dto.labels.map(|labels| NodeLabels { labels }),
+            taints: node_dto.taints.map(|taints| NodeTaints {
+                taints: taints.iter().map(Into::into).collect(),
+            }),
+        };
+
+        self.grpc_service
+            .update_node(Request::new(request))
+            .await
+            .map(|_| ())
+            .map_err(|err| Self::node_error(node_id, err))
+    }
+
+    /// It cordons a node so that no new instance is placed on it, or uncordons it. The instances
+    /// already placed on the node keep running.
+    ///
+    /// # Arguments:
+    ///
+    /// * `node_id`: The id of the node.
+    /// * `cordoned`: Whether new instances are kept off the node.
+    pub async fn cordon_node(&mut self, node_id: &str, cordoned: bool) -> Result<(), NodeError> {
+        let request = NodeCordonRequest {
+            id: node_id.to_string(),
+            cordoned,
+        };
+
+        self.grpc_service
+            .cordon_node(Request::new(request))
+            .await
+            .map(|_| ())
+            .map_err(|err| Self::node_error(node_id, err))
+    }
+
+    /// It cordons a node, then has the scheduler move its instances to the other nodes, a few
+    /// instances of each workload at a time.
+    ///
+    /// # Arguments:
+    ///
+    /// * `node_id`: The id of the node.
+    /// * `drain_dto`: The instances of a workload moved at the same time.
+    ///
+    /// # Returns:
+    ///
+    /// The progress of the drain once it started.
+    pub async fn drain_node(
+        &mut self,
+        node_id: &str,
+        drain_dto: DrainDTO,
+    ) -> Result<NodeDrain, NodeError> {
+        let request = NodeDrainRequest {
+            id: node_id.to_string(),
+            max_unavailable: drain_dto.max_unavailable.unwrap_or(1),
+        };
+
+        self.grpc_service
+            .drain_node(Request::new(request))
+            .await
+            .map(|response| response.into_inner().into())
+            .map_err(|err| Self::node_error(node_id, err))
+    }
+
+    /// It returns the progress of the drain of a node.
+    ///
+    /// # Arguments:
+    ///
+    /// * `node_id`: The id of the node.
+    pub async fn drain_status(&mut self, node_id: &str) -> Result<NodeDrain, NodeError> {
+        let request = NodeDrainStatusRequest {
+            id: node_id.to_string(),
+        };
+
+        self.grpc_service
+            .drain_status(Request::new(request))
+            .await
+            .map(|response| response.into_inner().into())
+            .map_err(|err| Self::node_error(node_id, err))
+    }
+
+    /// It converts the error of a request about a node, the node being unknown to the scheduler
+    fn node_error(node_id: &str, err: SchedulerClientInterfaceError) -> NodeError {
+        match err {
+            SchedulerClientInterfaceError::RequestFailed(status)
+                if status.code() == tonic::Code::NotFound =>
+            {
+                NodeError::NodeNotFound(node_id.to_string())
+            }
+            err => NodeError::Grpc(format!("{:?}", err)),
+        }
+    }
+}
@@ -1,14 +1,38 @@
 use crate::external_api::interface::ActixAppState;
 
-use super::model::WorkloadDTO;
+use super::model::{RollbackDTO, ScaleDTO, WorkloadDTO};
 use super::service::WorkloadService;
 use crate::external_api::generic::model::Pagination;
 use actix_web::http::StatusCode;
-use actix_web::{web, HttpResponse, Responder, Scope};
+use actix_web::{web, HttpRequest, HttpResponse, Responder, Scope};
+
+/// Header used by clients to identify the user behind a request
+const AUTHOR_HEADER: &str = "X-Kudo-Author";
+
 pub struct WorkloadController {}
 impl WorkloadController {
     pub fn services(&self) -> Scope {
         web::scope("/workload")
+            .service(
+                web::resource("/{namespace}/{workload_id}/revisions")
+                    .route(web::get().to(WorkloadController::get_revisions)),
+            )
+            .service(
+                web::resource("/{namespace}/{workload_id}/dry-run")
+                    .route(web::post().to(WorkloadController::dry_run_workload)),
+            )
+            .service(
+                web::resource("/{namespace}/{workload_id}/rollback")
+                    .route(web::post().to(WorkloadController::rollback_workload)),
+            )
+            .service(
+                web::resource("/{namespace}/{workload_id}/rollout")
+                    .route(web::get().to(WorkloadController::get_rollout)),
+            )
+            .service(
+                web::resource("/{namespace}/{workload_id}/scale")
+                    .route(web::post().to(WorkloadController::scale_workload)),
+            )
             .service(
                 web::resource("/{namespace}/{workload_id}")
                     .route(web::delete().to(WorkloadController::delete_workload))
@@ -32,17 +56,17 @@ impl WorkloadController {
     /// # Returns:
     ///
     /// A Result<String, WorkloadError>
-
     pub async fn workload(
         params: web::Path<(String, String)>,
         data: web::Data<ActixAppState>,
     ) -> impl Responder {
         let (namespace, workload_id) = params.into_inner();
 
-        let mut workload_service = match WorkloadService::new(&data.etcd_address).await {
-            Ok(workload) => workload,
-            Err(e) => return e.to_http(),
-        };
+        let mut workload_service =
+            match WorkloadService::new(&data.etcd_address, data.revision_history_limit).await {
+                Ok(workload) => workload,
+                Err(e) => return e.to_http(),
+            };
 
         workload_service
             .get_workload(&workload_id, &namespace)
@@ -57,19 +81,20 @@ impl WorkloadController {
     ///
     /// * `namespace`: web::Path<String> - This is the namespace that the workload will be created in.
     /// * `body`: web::Json<WorkloadDTO> - Contain all information required to create the workload.
-
     pub async fn put_workload(
         namespace: web::Path<String>,
         body: web::Json<WorkloadDTO>,
+        req: HttpRequest,
         data: web::Data<ActixAppState>,
     ) -> impl Responder {
-        let mut workload_service = match WorkloadService::new(&data.etcd_address).await {
-            Ok(workload) => workload,
-            Err(e) => return e.to_http(),
-        };
+        let mut workload_service =
+            match WorkloadService::new(&data.etcd_address, data.revision_history_limit).await {
+                Ok(workload) => workload,
+                Err(e) => return e.to_http(),
+            };
         let workload_dto = body.into_inner();
         workload_service
-            .create_workload(workload_dto, &namespace)
+            .create_workload(workload_dto, &namespace, &WorkloadController::author(&req))
             .await
             .map_or_else(|e| e.to_http(), |w| w.to_http())
     }
@@ -81,16 +106,16 @@ impl WorkloadController {
     ///
     /// * `namespace`: The namespace of the workloads you want to retrieve.
     /// * `pagination`: Option<web::Query<Pagination>>
-
     pub async fn get_all_workloads(
         namespace: web::Path<String>,
         pagination: Option<web::Query<Pagination>>,
         data: web::Data<ActixAppState>,
     ) -> impl Responder {
-        let mut workload_service = match WorkloadService::new(&data.etcd_address).await {
-            Ok(workload) => workload,
-            Err(e) => return e.to_http(),
-        };
+        let mut workload_service =
+            match WorkloadService::new(&data.etcd_address, data.revision_history_limit).await {
+                Ok(workload) => workload,
+                Err(e) => return e.to_http(),
+            };
 
         match pagination {
             Some(pagination) => {
@@ -113,22 +138,28 @@ impl WorkloadController {
     ///
     /// * `params`: web::Path<(String, String)> - The first Path parameter is the namespace and the second the workload id.
     /// * `body`: web::Json<WorkloadDTO> - Contain all information required to create the workload.
-
     pub async fn patch_workload(
         params: web::Path<(String, String)>,
         body: web::Json<WorkloadDTO>,
+        req: HttpRequest,
         data: web::Data<ActixAppState>,
     ) -> impl Responder {
-        let mut workload_service = match WorkloadService::new(&data.etcd_address).await {
-            Ok(workload) => workload,
-            Err(e) => return e.to_http(),
-        };
+        let mut workload_service =
+            match WorkloadService::new(&data.etcd_address, data.revision_history_limit).await {
+                Ok(workload) => workload,
+                Err(e) => return e.to_http(),
+            };
 
         let (namespace, workload_id) = params.into_inner();
         let workload_dto = body.into_inner();
 
         workload_service
-            .update_workload(workload_dto, &workload_id, &namespace)
+            .update_workload(
+                workload_dto,
+                &workload_id,
+                &namespace,
+                &WorkloadController::author(&req),
+            )
             .await
             .map_or_else(|e| e.to_http(), |w| w.to_http())
     }
@@ -143,15 +174,15 @@ impl WorkloadController {
     /// # Returns:
     ///
     /// A Result<(), WorkloadError>
-
     pub async fn delete_workload(
         params: web::Path<(String, String)>,
         data: web::Data<ActixAppState>,
     ) -> impl Responder {
-        let mut workload_service = match WorkloadService::new(&data.etcd_address).await {
-            Ok(workload) => workload,
-            Err(e) => return e.to_http(),
-        };
+        let mut workload_service =
+            match WorkloadService::new(&data.etcd_address, data.revision_history_limit).await {
+                Ok(workload) => workload,
+                Err(e) => return e.to_http(),
+            };
 
         let (namespace, workload_id) = params.into_inner();
 
@@ -160,4 +191,143 @@ impl WorkloadController {
             .await;
         HttpResponse::build(StatusCode::NO_CONTENT).body("Remove successfully")
     }
+
+    /// `get_revisions` is an async function that handle **/workload/\<namespace>/<workload_id>/revisions** route (GET)
+    /// # Description:
+    /// * Get the revisions kept for a workload
+    /// # Arguments:
+    ///
+    /// * `params`: web::Path<(String, String)> - The first Path parameter is the namespace and the second the workload id.
+    pub async fn get_revisions(
+        params: web::Path<(String, String)>,
+        data: web::Data<ActixAppState>,
+    ) -> impl Responder {
+        let mut workload_service =
+            match WorkloadService::new(&data.etcd_address, data.revision_history_limit).await {
+                Ok(workload) => workload,
+                Err(e) => return e.to_http(),
+            };
+
+        let (namespace, workload_id) = params.into_inner();
+
+        workload_service
+            .get_revisions(&workload_id, &namespace)
+            .await
+            .map_or_else(|e| e.to_http(), |r| r.to_http())
+    }
+
+    /// `rollback_workload` is an async function that handle **/workload/\<namespace>/<workload_id>/rollback** route (POST)
+    /// # Description:
+    /// * Restore a previous revision of a workload, its instances are then rolled by the reconciler
+    /// # Arguments:
+    ///
+    /// * `params`: web::Path<(String, String)> - The first Path parameter is the namespace and the second the workload id.
+    /// * `body`: web::Json<RollbackDTO> - The revision to restore.
+    pub async fn rollback_workload(
+        params: web::Path<(String, String)>,
+        body: web::Json<RollbackDTO>,
+        req: HttpRequest,
+        data: web::Data<ActixAppState>,
+    ) -> impl Responder {
+        let mut workload_service =
+            match WorkloadService::new(&data.etcd_address, data.revision_history_limit).await {
+                Ok(workload) => workload,
+                Err(e) => return e.to_http(),
+            };
+
+        let (namespace, workload_id) = params.into_inner();
+
+        workload_service
+            .rollback_workload(
+                &workload_id,
+                &namespace,
+                body.revision,
+                &WorkloadController::author(&req),
+            )
+            .await
+            .map_or_else(|e| e.to_http(), |w| w.to_http())
+    }
+
+    /// `get_rollout` is an async function that handle **/workload/\<namespace>/<workload_id>/rollout** route (GET)
+    /// # Description:
+    /// * Get the progress of the rollout of the current revision of a workload
+    /// # Arguments:
+    ///
+    /// * `params`: web::Path<(String, String)> - The first Path parameter is the namespace and the second the workload id.
+    pub async fn get_rollout(
+        params: web::Path<(String, String)>,
+        data: web::Data<ActixAppState>,
+    ) -> impl Responder {
+        let mut workload_service =
+            match WorkloadService::new(&data.etcd_address, data.revision_history_limit).await {
+                Ok(workload) => workload,
+                Err(e) => return e.to_http(),
+            };
+
+        let (namespace, workload_id) = params.into_inner();
+
+        workload_service
+            .get_workload_rollout(&workload_id, &namespace)
+            .await
+            .map_or_else(|e| e.to_http(), |r| r.to_http())
+    }
+
+    /// `scale_workload` is an async function that handle **/workload/\<namespace>/<workload_id>/scale** route (POST)
+    /// # Description:
+    /// * Change the number of instances to keep running for a workload
+    /// # Arguments:
+    ///
+    /// * `params`: web::Path<(String, String)> - The first Path parameter is the namespace and the second the workload id.
+    /// * `body`: web::Json<ScaleDTO> - The number of replicas.
+    pub async fn scale_workload(
+        params: web::Path<(String, String)>,
+        body: web::Json<ScaleDTO>,
+        data: web::Data<ActixAppState>,
+    ) -> impl Responder {
+        let mut workload_service =
+            match WorkloadService::new(&data.etcd_address, data.revision_history_limit).await {
+                Ok(workload) => workload,
+                Err(e) => return e.to_http(),
+            };
+
+        let (namespace, workload_id) = params.into_inner();
+
+        workload_service
+            .scale_workload(&workload_id, &namespace, body.replicas)
+            .await
+            .map_or_else(|e| e.to_http(), |w| w.to_http())
+    }
+
+    /// `dry_run_workload` is an async function that handle **/workload/\<namespace>/<workload_id>/dry-run** route (POST)
+    /// # Description:
+    /// * Tell where the instances of a workload would be placed, without creating or updating it
+    /// # Arguments:
+    ///
+    /// * `params`: web::Path<(String, String)> - The first Path parameter is the namespace and the second the workload id.
+    /// * `body`: web::Json<WorkloadDTO> - The workload to create or update.
+    pub async fn dry_run_workload(
+        params: web::Path<(String, String)>,
+        body: web::Json<WorkloadDTO>,
+        data: web::Data<ActixAppState>,
+    ) -> impl Responder {
+        let (namespace, workload_id) = params.into_inner();
+
+        WorkloadService::dry_run(
+            &data.scheduler_address,
+            body.into_inner(),
+            &workload_id,
+            &namespace,
+        )
+        .await
+        .map_or_else(|e| e.to_http(), |dry_run| dry_run.to_http())
+    }
+
+    /// It returns the author of a request, as sent by the client
+    fn author(req: &HttpRequest) -> String {
+        req.headers()
+            .get(AUTHOR_HEADER)
+            .and_then(|author| author.to_str().ok())
+            .unwrap_or("unknown")
+            .to_string()
+    }
 }
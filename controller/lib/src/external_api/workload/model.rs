@@ -1,12 +1,22 @@
+use std::collections::HashMap;
+
 use actix_web::HttpResponse;
+use proto::scheduler;
 use serde::{Deserialize, Serialize};
 
+#[derive(Debug)]
 pub enum WorkloadError {
     WorkloadNotFound,
     Etcd(String),
     NameAlreadyExists(String),
     JsonToWorkload(String),
     WorkloadToJson(String),
+    RevisionNotFound(u64),
+    NoPreviousRevision,
+    InvalidProbe(String),
+    InvalidAffinity(String),
+    InvalidResources(String),
+    Grpc(String),
 }
 
 impl WorkloadError {
@@ -25,20 +35,282 @@ impl WorkloadError {
             WorkloadError::WorkloadToJson(err) => HttpResponse::InternalServerError().body(
                 format!("Error while converting the workload to JSON: {}", err),
             ),
+            WorkloadError::RevisionNotFound(revision) => {
+                HttpResponse::NotFound().body(format!("Revision {} not found", revision))
+            }
+            WorkloadError::NoPreviousRevision => {
+                HttpResponse::BadRequest().body("Workload has no previous revision")
+            }
+            WorkloadError::InvalidProbe(err) => {
+                HttpResponse::BadRequest().body(format!("Invalid probe: {}", err))
+            }
+            WorkloadError::InvalidAffinity(err) => {
+                HttpResponse::BadRequest().body(format!("Invalid affinity: {}", err))
+            }
+            WorkloadError::InvalidResources(err) => {
+                HttpResponse::BadRequest().body(format!("Invalid resources: {}", err))
+            }
+            WorkloadError::Grpc(err) => {
+                HttpResponse::InternalServerError().body(format!("Scheduler error: {} ", err))
+            }
         }
     }
 }
-#[derive(Deserialize, Serialize, Clone, Debug)]
+
+impl std::fmt::Display for WorkloadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WorkloadError::WorkloadNotFound => write!(f, "Workload not found"),
+            WorkloadError::Etcd(err) => write!(f, "Etcd error: {}", err),
+            WorkloadError::NameAlreadyExists(name) => {
+                write!(f, "Workload with name {} already exists", name)
+            }
+            WorkloadError::JsonToWorkload(err) => write!(f, "JSON to workload error: {}", err),
+            WorkloadError::WorkloadToJson(err) => write!(f, "Workload to JSON error: {}", err),
+            WorkloadError::RevisionNotFound(revision) => {
+                write!(f, "Revision {} not found", revision)
+            }
+            WorkloadError::NoPreviousRevision => write!(f, "Workload has no previous revision"),
+            WorkloadError::InvalidProbe(err) => write!(f, "Invalid probe: {}", err),
+            WorkloadError::InvalidAffinity(err) => write!(f, "Invalid affinity: {}", err),
+            WorkloadError::InvalidResources(err) => write!(f, "Invalid resources: {}", err),
+            WorkloadError::Grpc(err) => write!(f, "Scheduler error: {}", err),
+        }
+    }
+}
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
 pub enum Type {
     Container = 0,
 }
-#[derive(Deserialize, Serialize, Clone, Debug)]
+/// When the node restarts an instance which exited
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum RestartPolicy {
+    #[default]
+    Always,
+    OnFailure,
+    Never,
+}
+/// A periodic check of the health of the instances of a workload, run by their node. Exactly
+/// one of `http_get`, `tcp_socket` and `exec` must be set, the node defaults are used for the
+/// fields set to 0.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq, Default)]
+#[serde(default)]
+pub struct Probe {
+    pub http_get: Option<HttpGetAction>,
+    pub tcp_socket: Option<TcpSocketAction>,
+    pub exec: Option<ExecAction>,
+    pub initial_delay_seconds: u32,
+    pub period_seconds: u32,
+    pub timeout_seconds: u32,
+    /// Consecutive failures before the probe is considered failed
+    pub failure_threshold: u32,
+}
+impl Probe {
+    pub fn validate(&self) -> Result<(), WorkloadError> {
+        let actions = [
+            self.http_get.is_some(),
+            self.tcp_socket.is_some(),
+            self.exec.is_some(),
+        ];
+        match actions.iter().filter(|set| **set).count() {
+            1 => Ok(()),
+            _ => Err(WorkloadError::InvalidProbe(
+                "exactly one of http_get, tcp_socket and exec must be set".to_string(),
+            )),
+        }
+    }
+}
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct HttpGetAction {
+    #[serde(default)]
+    pub path: String,
+    pub port: i32,
+}
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct TcpSocketAction {
+    pub port: i32,
+}
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct ExecAction {
+    pub command: Vec<String>,
+}
+/// Constraints on the nodes the instances of a workload are placed on
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq, Default)]
+#[serde(default)]
+pub struct Affinity {
+    /// The node must match one of the terms, if any
+    pub required: Vec<NodeSelectorTerm>,
+    /// The nodes matching these terms are preferred
+    pub preferred: Vec<PreferredTerm>,
+    /// Spreads the instances of the workload over the nodes
+    pub anti_affinity: Option<AntiAffinity>,
+}
+impl Affinity {
+    pub fn validate(&self) -> Result<(), WorkloadError> {
+        let terms = self
+            .required
+            .iter()
+            .chain(self.preferred.iter().map(|preferred| &preferred.term));
+        for requirement in terms.flat_map(|term| term.requirements.iter()) {
+            let needs_values = matches!(
+                requirement.operator,
+                SelectorOperator::In | SelectorOperator::NotIn
+            );
+            if needs_values == requirement.values.is_empty() {
+                return Err(WorkloadError::InvalidAffinity(format!(
+                    "values of {} must be set with in and not-in only",
+                    requirement.key
+                )));
+            }
+        }
+
+        let weights = self
+            .preferred
+            .iter()
+            .map(|preferred| preferred.weight)
+            .chain(self.anti_affinity.iter().map(|anti| anti.weight));
+        for weight in weights {
+            if !(1..=100).contains(&weight) {
+                return Err(WorkloadError::InvalidAffinity(format!(
+                    "weight {} is not between 1 and 100",
+                    weight
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+/// Requirements a node must all match
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq, Default)]
+#[serde(default)]
+pub struct NodeSelectorTerm {
+    pub requirements: Vec<NodeSelectorRequirement>,
+}
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct NodeSelectorRequirement {
+    /// Label of the node
+    pub key: String,
+    pub operator: SelectorOperator,
+    #[serde(default)]
+    pub values: Vec<String>,
+}
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum SelectorOperator {
+    In,
+    NotIn,
+    Exists,
+    DoesNotExist,
+}
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct PreferredTerm {
+    /// Between 1 and 100
+    pub weight: i32,
+    pub term: NodeSelectorTerm,
+}
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct AntiAffinity {
+    /// Two instances of the workload are never placed on the same node
+    #[serde(default)]
+    pub required: bool,
+    /// Between 1 and 100, how much the nodes already running an instance are avoided when
+    /// not required
+    #[serde(default = "default_weight")]
+    pub weight: i32,
+}
+/// How a taint of a node repels the instances which don't tolerate it
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum TaintEffect {
+    NoSchedule,
+    PreferNoSchedule,
+}
+/// Allows the instances of a workload on the nodes with a matching taint
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct Toleration {
+    pub key: String,
+    /// Any value is tolerated if empty
+    #[serde(default)]
+    pub value: String,
+    /// Any effect is tolerated if not set
+    #[serde(default)]
+    pub effect: Option<TaintEffect>,
+}
+/// Limits of the instances of a workload, enforced by their node, 0 if unlimited
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq, Default)]
 pub struct Ressources {
+    /// In milliCPU
     pub cpu: u64,
+    /// In MB
     pub memory: u64,
+    /// In GB
     pub disk: u64,
+    /// Resources reserved for each instance on its node, the limits if not set
+    #[serde(default)]
+    pub requests: Option<ResourceRequests>,
 }
-#[derive(Deserialize, Serialize, Clone, Debug)]
+/// Resources the instances are placed by, the limit of each resource which isn't set
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq, Default)]
+pub struct ResourceRequests {
+    #[serde(default)]
+    pub cpu: Option<u64>,
+    #[serde(default)]
+    pub memory: Option<u64>,
+    #[serde(default)]
+    pub disk: Option<u64>,
+}
+impl Ressources {
+    pub fn validate(&self) -> Result<(), WorkloadError> {
+        // the nodes enforce the limits in nanoCPU and bytes, as 64-bit signed integers
+        let limits = [
+            ("cpu", self.cpu, 1_000_000),
+            ("memory", self.memory, 1024 * 1024),
+            ("disk", self.disk, 1024 * 1024 * 1024),
+        ];
+        for (resource, limit, unit) in limits {
+            let enforced = i64::try_from(limit)
+                .ok()
+                .and_then(|limit| limit.checked_mul(unit));
+            if enforced.is_none() {
+                return Err(WorkloadError::InvalidResources(format!(
+                    "{} limit {} is too large",
+                    resource, limit
+                )));
+            }
+        }
+
+        let requests = match &self.requests {
+            Some(requests) => requests,
+            None => return Ok(()),
+        };
+        let resources = [
+            ("cpu", requests.cpu, self.cpu),
+            ("memory", requests.memory, self.memory),
+            ("disk", requests.disk, self.disk),
+        ];
+        for (resource, request, limit) in resources {
+            if let Some(request) = request.filter(|request| limit > 0 && *request > limit) {
+                return Err(WorkloadError::InvalidResources(format!(
+                    "{} request {} is above its limit {}",
+                    resource, request, limit
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// It returns the resources reserved for each instance on its node
+    pub fn requested(&self) -> scheduler::ResourceSummary {
+        let requests = self.requests.clone().unwrap_or_default();
+        scheduler::ResourceSummary {
+            cpu: requests.cpu.unwrap_or(self.cpu),
+            memory: requests.memory.unwrap_or(self.memory),
+            disk: requests.disk.unwrap_or(self.disk),
+        }
+    }
+}
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
 pub struct Ports {
     pub source: i32,
     pub destination: i32,
@@ -53,8 +325,68 @@ pub struct Workload {
     pub resources: Ressources,
     pub ports: Vec<Ports>,
     pub namespace: String,
+    #[serde(default)]
+    pub revision: u64,
+    /// Number of instances of the workload to keep running
+    #[serde(default = "default_replicas")]
+    pub replicas: u32,
+    #[serde(default)]
+    pub strategy: RolloutStrategy,
+    #[serde(default)]
+    pub restart_policy: RestartPolicy,
+    /// The instances are restarted when it fails
+    #[serde(default)]
+    pub liveness_probe: Option<Probe>,
+    /// The instances receive no traffic while it fails
+    #[serde(default)]
+    pub readiness_probe: Option<Probe>,
+    /// Labels the nodes of the instances must have
+    #[serde(default)]
+    pub node_selector: HashMap<String, String>,
+    #[serde(default)]
+    pub affinity: Option<Affinity>,
+    #[serde(default)]
+    pub tolerations: Vec<Toleration>,
+    /// The instances of a lower priority are preempted to make room for the instances
+    #[serde(default)]
+    pub priority: i32,
 }
 impl Workload {
+    /// Returns true if both workloads would run the same instances,
+    /// regardless of their revision number, replica count, rollout strategy and priority.
+    pub fn same_spec(&self, other: &Workload) -> bool {
+        self.workload_type == other.workload_type
+            && self.uri == other.uri
+            && self.environment == other.environment
+            && self.resources == other.resources
+            && self.ports == other.ports
+            && self.restart_policy == other.restart_policy
+            && self.liveness_probe == other.liveness_probe
+            && self.readiness_probe == other.readiness_probe
+            && self.node_selector == other.node_selector
+            && self.affinity == other.affinity
+            && self.tolerations == other.tolerations
+    }
+
+    /// It returns the workload running the instances of `other`, keeping its own name,
+    /// revision number, replica count, rollout strategy and priority.
+    pub fn with_spec_of(&self, other: &Workload) -> Workload {
+        Workload {
+            workload_type: other.workload_type.clone(),
+            uri: other.uri.clone(),
+            environment: other.environment.clone(),
+            resources: other.resources.clone(),
+            ports: other.ports.clone(),
+            restart_policy: other.restart_policy,
+            liveness_probe: other.liveness_probe.clone(),
+            readiness_probe: other.readiness_probe.clone(),
+            node_selector: other.node_selector.clone(),
+            affinity: other.affinity.clone(),
+            tolerations: other.tolerations.clone(),
+            ..self.clone()
+        }
+    }
+
     pub fn to_http(&self) -> HttpResponse {
         match serde_json::to_string(&self) {
             Ok(json) => HttpResponse::Ok().body(json),
@@ -71,6 +403,85 @@ pub struct WorkloadDTO {
     pub environment: Vec<String>,
     pub ports: Vec<Ports>,
     pub uri: String,
+    #[serde(default)]
+    pub resources: Ressources,
+    #[serde(default = "default_replicas")]
+    pub replicas: u32,
+    #[serde(default)]
+    pub strategy: RolloutStrategy,
+    #[serde(default)]
+    pub restart_policy: RestartPolicy,
+    #[serde(default)]
+    pub liveness_probe: Option<Probe>,
+    #[serde(default)]
+    pub readiness_probe: Option<Probe>,
+    #[serde(default)]
+    pub node_selector: HashMap<String, String>,
+    #[serde(default)]
+    pub affinity: Option<Affinity>,
+    #[serde(default)]
+    pub tolerations: Vec<Toleration>,
+    #[serde(default)]
+    pub priority: i32,
+}
+impl WorkloadDTO {
+    pub fn validate(&self) -> Result<(), WorkloadError> {
+        for probe in self
+            .liveness_probe
+            .iter()
+            .chain(self.readiness_probe.iter())
+        {
+            probe.validate()?;
+        }
+        if let Some(affinity) = &self.affinity {
+            affinity.validate()?;
+        }
+        self.resources.validate()?;
+        Ok(())
+    }
+
+    /// It creates the workload described by the DTO
+    ///
+    /// # Arguments:
+    ///
+    /// * `id`: The id of the workload in etcd.
+    /// * `namespace`: The namespace of the workload.
+    /// * `revision`: The revision of the workload.
+    pub fn into_workload(self, id: String, namespace: &str, revision: u64) -> Workload {
+        Workload {
+            id,
+            name: self.name,
+            workload_type: Type::Container,
+            uri: self.uri,
+            environment: self.environment,
+            resources: self.resources,
+            ports: self.ports,
+            namespace: namespace.to_string(),
+            revision,
+            replicas: self.replicas,
+            strategy: self.strategy,
+            restart_policy: self.restart_policy,
+            liveness_probe: self.liveness_probe,
+            readiness_probe: self.readiness_probe,
+            node_selector: self.node_selector,
+            affinity: self.affinity,
+            tolerations: self.tolerations,
+            priority: self.priority,
+        }
+    }
+}
+
+fn default_replicas() -> u32 {
+    1
+}
+
+fn default_weight() -> i32 {
+    100
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct ScaleDTO {
+    pub replicas: u32,
 }
 #[derive(Deserialize, Serialize)]
 pub struct WorkloadVector {
@@ -90,3 +501,294 @@ impl WorkloadVector {
         }
     }
 }
+
+/// A snapshot of a workload, recorded each time its specification changes.
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct WorkloadRevision {
+    pub revision: u64,
+    /// Seconds since the UNIX epoch
+    pub timestamp: u64,
+    pub author: String,
+    pub workload: Workload,
+}
+#[derive(Deserialize, Serialize, Default)]
+pub struct WorkloadRevisionVector {
+    pub revisions: Vec<WorkloadRevision>,
+}
+impl WorkloadRevisionVector {
+    /// It appends a revision to the history and drops the oldest revisions over `limit`. At
+    /// least the appended revision is kept.
+    pub fn push(&mut self, revision: WorkloadRevision, limit: usize) {
+        self.revisions.push(revision);
+        let limit = limit.max(1);
+        if self.revisions.len() > limit {
+            self.revisions.drain(..self.revisions.len() - limit);
+        }
+    }
+
+    /// It returns the revision a workload at the `current` revision is rolled back to: the given
+    /// `revision` if set, or else the newest revision older than the current one.
+    pub fn rollback_target(
+        &self,
+        current: u64,
+        revision: Option<u64>,
+    ) -> Result<&WorkloadRevision, WorkloadError> {
+        match revision {
+            Some(revision) => self
+                .revisions
+                .iter()
+                .find(|r| r.revision == revision)
+                .ok_or(WorkloadError::RevisionNotFound(revision)),
+            None => self
+                .revisions
+                .iter()
+                .rev()
+                .find(|r| r.revision < current)
+                .ok_or(WorkloadError::NoPreviousRevision),
+        }
+    }
+
+    pub fn to_http(&self) -> HttpResponse {
+        match serde_json::to_string(&self) {
+            Ok(json) => HttpResponse::Ok().body(json),
+            Err(err) => HttpResponse::InternalServerError().body(format!(
+                "Error while converting the revisions to json: {}",
+                err
+            )),
+        }
+    }
+}
+#[derive(Deserialize, Serialize)]
+pub struct RollbackDTO {
+    /// Revision to restore, the previous one if not set
+    pub revision: Option<u64>,
+}
+
+/// How the instances of a workload are replaced when its specification changes.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+#[serde(default)]
+pub struct RolloutStrategy {
+    /// Number of replicas which can be unavailable during the rollout
+    pub max_unavailable: u32,
+    /// Number of instances which can be created over the number of replicas during the rollout
+    pub max_surge: u32,
+}
+impl Default for RolloutStrategy {
+    fn default() -> Self {
+        RolloutStrategy {
+            max_unavailable: 0,
+            max_surge: 1,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RolloutState {
+    /// Instances are being replaced by instances of the current revision
+    Progressing,
+    /// Instances of the current revision failed, no more instance is replaced until the
+    /// next revision
+    Paused,
+    /// Every replica runs the current revision
+    Complete,
+}
+
+/// Progress of the rollout of the current revision of a workload.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct Rollout {
+    pub revision: u64,
+    pub state: RolloutState,
+    pub replicas: u32,
+    /// Active instances of the current revision
+    pub updated: u32,
+    /// Running instances of the current revision
+    pub ready: u32,
+    /// Active instances of the previous revisions
+    pub outdated: u32,
+    pub message: String,
+}
+impl Rollout {
+    pub fn new(workload: &Workload) -> Self {
+        Rollout {
+            revision: workload.revision,
+            state: RolloutState::Progressing,
+            replicas: workload.replicas,
+            updated: 0,
+            ready: 0,
+            outdated: 0,
+            message: String::new(),
+        }
+    }
+
+    pub fn to_http(&self) -> HttpResponse {
+        match serde_json::to_string(&self) {
+            Ok(json) => HttpResponse::Ok().body(json),
+            Err(err) => HttpResponse::InternalServerError().body(format!(
+                "Error while converting the rollout to json: {}",
+                err
+            )),
+        }
+    }
+}
+
+/// Where an instance of a workload would be placed by the scheduler.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct DryRunPlacement {
+    pub instance: String,
+    /// None if no node can run the instance
+    pub node: Option<String>,
+    /// Why no node can run the instance
+    pub reason: String,
+    /// Instances of a lower priority evicted to make room for the instance
+    pub preempted: Vec<String>,
+}
+
+/// Outcome of the simulation of the placement of the instances of a workload, nothing is
+/// created.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct DryRun {
+    /// Every instance can be placed
+    pub fits: bool,
+    pub placements: Vec<DryRunPlacement>,
+}
+
+impl From<scheduler::DryRunResponse> for DryRun {
+    fn from(response: scheduler::DryRunResponse) -> Self {
+        let placements: Vec<DryRunPlacement> = response
+            .placements
+            .into_iter()
+            .map(|placement| {
+                let explanation = placement.explanation.unwrap_or_default();
+                DryRunPlacement {
+                    instance: explanation.instance_id,
+                    node: Some(explanation.node_id).filter(|node| !node.is_empty()),
+                    reason: explanation.reason,
+                    preempted: placement.preempted,
+                }
+            })
+            .collect();
+
+        DryRun {
+            fits: placements.iter().all(|placement| placement.node.is_some()),
+            placements,
+        }
+    }
+}
+
+impl DryRun {
+    pub fn to_http(&self) -> HttpResponse {
+        match serde_json::to_string(&self) {
+            Ok(json) => HttpResponse::Ok().body(json),
+            Err(err) => HttpResponse::InternalServerError().body(format!(
+                "Error while converting the dry run to json: {}",
+                err
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn revision(revision: u64) -> WorkloadRevision {
+        let dto: WorkloadDTO = serde_json::from_str(
+            r#"{"name": "web", "environment": [], "ports": [], "uri": "nginx"}"#,
+        )
+        .unwrap();
+        WorkloadRevision {
+            revision,
+            timestamp: 0,
+            author: "admin".to_string(),
+            workload: dto.into_workload("workload.default.web".to_string(), "default", revision),
+        }
+    }
+
+    fn history(revisions: &[u64]) -> WorkloadRevisionVector {
+        WorkloadRevisionVector {
+            revisions: revisions.iter().copied().map(revision).collect(),
+        }
+    }
+
+    fn numbers(history: &WorkloadRevisionVector) -> Vec<u64> {
+        history.revisions.iter().map(|r| r.revision).collect()
+    }
+
+    #[test]
+    fn test_push_trims_oldest_revisions() {
+        let mut history = history(&[1, 2, 3]);
+
+        history.push(revision(4), 3);
+        assert_eq!(numbers(&history), vec![2, 3, 4]);
+
+        history.push(revision(5), 2);
+        assert_eq!(numbers(&history), vec![4, 5]);
+    }
+
+    #[test]
+    fn test_push_keeps_at_least_one_revision() {
+        let mut history = history(&[1, 2]);
+
+        history.push(revision(3), 0);
+        assert_eq!(numbers(&history), vec![3]);
+    }
+
+    #[test]
+    fn test_rollback_target_defaults_to_previous_revision() {
+        let history = history(&[1, 2, 3]);
+
+        assert_eq!(history.rollback_target(3, None).unwrap().revision, 2);
+        // the current revision may have been restored from an older one
+        assert_eq!(history.rollback_target(2, None).unwrap().revision, 1);
+        assert!(matches!(
+            history.rollback_target(1, None),
+            Err(WorkloadError::NoPreviousRevision)
+        ));
+    }
+
+    #[test]
+    fn test_rollback_target_to_given_revision() {
+        let history = history(&[2, 3, 4]);
+
+        assert_eq!(history.rollback_target(4, Some(2)).unwrap().revision, 2);
+        assert!(matches!(
+            history.rollback_target(4, Some(1)),
+            Err(WorkloadError::RevisionNotFound(1))
+        ));
+    }
+
+    #[test]
+    fn test_rollback_keeps_the_scale_of_the_workload() {
+        let target = revision(2).workload;
+        let mut current = revision(3).workload;
+        current.uri = "nginx:1.25".to_string();
+        current.replicas = 5;
+        current.strategy.max_unavailable = 2;
+        current.priority = 10;
+
+        let restored = current.with_spec_of(&target);
+        assert!(restored.same_spec(&target));
+        assert_eq!(restored.uri, "nginx");
+        assert_eq!(restored.revision, 3);
+        assert_eq!(restored.replicas, 5);
+        assert_eq!(restored.strategy.max_unavailable, 2);
+        assert_eq!(restored.priority, 10);
+    }
+
+    #[test]
+    fn test_resources_reject_limits_too_large_for_the_nodes() {
+        let resources = |cpu: u64, memory: u64, disk: u64| Ressources {
+            cpu,
+            memory,
+            disk,
+            requests: None,
+        };
+
+        assert!(resources(500, 256, 10).validate().is_ok());
+        assert!(resources(0, 0, 0).validate().is_ok());
+        assert!(resources(u64::MAX, 256, 10).validate().is_err());
+        assert!(resources(500, 1 << 44, 10).validate().is_err());
+        assert!(resources(500, 256, 1 << 34).validate().is_err());
+        assert!(resources(500, 256, 1 << 32).validate().is_ok());
+    }
+}
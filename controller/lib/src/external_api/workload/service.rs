@@ -1,8 +1,18 @@
 use std::net::SocketAddr;
+use std::time::{SystemTime, UNIX_EPOCH};
 
-use super::model::{Ressources, Type, Workload, WorkloadDTO, WorkloadError, WorkloadVector};
+use log::info;
+use proto::scheduler::{self, DryRunRequest};
+use tonic::Request;
+
+use super::model::{
+    DryRun, Rollout, Workload, WorkloadDTO, WorkloadError, WorkloadRevision,
+    WorkloadRevisionVector, WorkloadVector,
+};
 use crate::etcd::EtcdClient;
 use crate::external_api::generic::filter::FilterService;
+use crate::external_api::instance::model::Instance;
+use crate::grpc_client::interface::SchedulerClientInterface;
 use serde_json;
 
 /// `WorkloadService` is a struct that inpired from Controllers Provider Modules architectures. It can be used as a service in the WorkloadController .A service can use other services.
@@ -10,18 +20,24 @@ use serde_json;
 ///
 /// * `etcd_service`: This is the service that will be used to interact with etcd.
 /// * `filter_service`: This is the service that will be used to filter the workloads.
+/// * `revision_history_limit`: The number of revisions kept for each workload.
 pub struct WorkloadService {
     etcd_service: EtcdClient,
     filter_service: FilterService,
+    revision_history_limit: usize,
 }
 
 impl WorkloadService {
-    pub async fn new(etcd_address: &SocketAddr) -> Result<WorkloadService, WorkloadError> {
+    pub async fn new(
+        etcd_address: &SocketAddr,
+        revision_history_limit: usize,
+    ) -> Result<WorkloadService, WorkloadError> {
         let inner = WorkloadService {
             etcd_service: EtcdClient::new(etcd_address.to_string())
                 .await
                 .map_err(|err| WorkloadError::Etcd(err.to_string()))?,
             filter_service: FilterService::new(),
+            revision_history_limit,
         };
         Ok(inner)
     }
@@ -30,7 +46,7 @@ impl WorkloadService {
         workload_name: &str,
         namespace: &str,
     ) -> Result<Workload, WorkloadError> {
-        let id = self.id(workload_name, namespace);
+        let id = Self::id(workload_name, namespace);
         match self.etcd_service.get(&id).await {
             Some(workload) => {
                 let workload: Workload = serde_json::from_str(&workload)
@@ -63,7 +79,7 @@ impl WorkloadService {
         namespace: &str,
     ) -> WorkloadVector {
         let mut new_vec: Vec<Workload> = Vec::new();
-        match self.etcd_service.get_all().await {
+        match self.etcd_service.get_prefix(&Self::id("", namespace)).await {
             Some(workloads) => {
                 for workload in workloads {
                     // if workload deserialize failed , we don't want to throw error , so we just don't add it to the vector
@@ -94,6 +110,7 @@ impl WorkloadService {
     ///
     /// * `workload_dto`: WorkloadDTO containt the workload data
     /// * `namespace`: The namespace of the workload
+    /// * `author`: The user creating the workload, recorded in the first revision
     ///
     /// # Returns:
     ///
@@ -102,32 +119,23 @@ impl WorkloadService {
         &mut self,
         workload_dto: WorkloadDTO,
         namespace: &str,
+        author: &str,
     ) -> Result<Workload, WorkloadError> {
-        let new_id = self.id(&workload_dto.name, namespace);
+        workload_dto.validate()?;
+        let new_id = Self::id(&workload_dto.name, namespace);
         match self.get_workload(&workload_dto.name, namespace).await {
             Ok(workload) => Err(WorkloadError::NameAlreadyExists(workload.name)),
             Err(err) => match err {
                 WorkloadError::WorkloadNotFound => {
-                    let workload = Workload {
-                        id: new_id.to_string(),
-                        name: workload_dto.name,
-                        workload_type: Type::Container,
-                        uri: workload_dto.uri,
-                        environment: workload_dto.environment,
-                        resources: Ressources {
-                            cpu: 0,
-                            memory: 0,
-                            disk: 0,
-                        },
-                        ports: workload_dto.ports,
-                        namespace: namespace.to_string(),
-                    };
-                    let json = serde_json::to_string(&workload)
-                        .map_err(|err| WorkloadError::WorkloadToJson(err.to_string()))?;
-                    self.etcd_service
-                        .put(&new_id, &json)
-                        .await
-                        .map_err(|err| WorkloadError::Etcd(err.to_string()))?;
+                    // a workload with the same name may have been deleted, its history is stale
+                    _ = self
+                        .etcd_service
+                        .delete(&self.revisions_id(&workload_dto.name, namespace))
+                        .await;
+
+                    let mut workload = workload_dto.into_workload(new_id.to_string(), namespace, 0);
+                    self.record_revision(&mut workload, author).await?;
+                    self.put_workload(&workload).await?;
                     Ok(workload)
                 }
                 _ => Err(err),
@@ -142,6 +150,7 @@ impl WorkloadService {
     /// * `workload_dto`: WorkloadDTO
     /// * `workload_id`: The id of the workload to update
     /// * `namespace`: The namespace of the workload
+    /// * `author`: The user updating the workload, recorded in the new revision
     ///
     /// # Returns:
     ///
@@ -151,39 +160,374 @@ impl WorkloadService {
         workload_dto: WorkloadDTO,
         workload_name: &str,
         namespace: &str,
+        author: &str,
     ) -> Result<Workload, WorkloadError> {
+        workload_dto.validate()?;
         // we get the id before update , and the new id after update
-        let new_id = self.id(&workload_dto.name, namespace);
-        self.get_workload(workload_name, namespace).await?;
-        let workload = Workload {
-            id: new_id.to_string(),
-            name: workload_dto.name,
-            workload_type: Type::Container,
-            uri: workload_dto.uri,
-            environment: workload_dto.environment.to_vec(),
-            resources: Ressources {
-                cpu: 0,
-                memory: 0,
-                disk: 0,
-            },
-            ports: workload_dto.ports.to_vec(),
-            namespace: namespace.to_string(),
+        let new_id = Self::id(&workload_dto.name, namespace);
+        let current = self.get_workload(workload_name, namespace).await?;
+        let mut workload =
+            workload_dto.into_workload(new_id.to_string(), namespace, current.revision);
+
+        // only a change of specification creates a new revision
+        if current.name != workload.name || !current.same_spec(&workload) {
+            self.record_revision(&mut workload, author).await?;
+        }
+        self.put_workload(&workload).await?;
+        Ok(workload)
+    }
+
+    /// It asks the scheduler where the instances of a workload would be placed, taking the place
+    /// of the instances of the current workload if it exists. Nothing is stored nor created.
+    ///
+    /// # Arguments:
+    ///
+    /// * `scheduler_address`: The address of the scheduler.
+    /// * `workload_dto`: The workload to create or update.
+    /// * `workload_name`: The name of the workload to update.
+    /// * `namespace`: The namespace of the workload
+    ///
+    /// # Returns:
+    ///
+    /// The node each instance would be placed on, or why it can't be placed
+    pub async fn dry_run(
+        scheduler_address: &SocketAddr,
+        workload_dto: WorkloadDTO,
+        workload_name: &str,
+        namespace: &str,
+    ) -> Result<DryRun, WorkloadError> {
+        workload_dto.validate()?;
+        let id = Self::id(&workload_dto.name, namespace);
+        let workload = workload_dto.into_workload(id, namespace, 0);
+        let instances = (0..workload.replicas)
+            .map(|index| {
+                let id = format!("{}-dry-run-{}", workload.name, index);
+                scheduler::Instance::from(&Instance::from_workload(id, &workload))
+            })
+            .collect();
+        let request = DryRunRequest {
+            instances,
+            replaces: format!("{}/{}", namespace, workload_name),
         };
-        let json = serde_json::to_string(&workload)
+
+        let mut grpc_service =
+            SchedulerClientInterface::new(format!("http://{}", scheduler_address))
+                .await
+                .map_err(|err| WorkloadError::Grpc(format!("{:?}", err)))?;
+        grpc_service
+            .dry_run(Request::new(request))
+            .await
+            .map(|response| response.into_inner().into())
+            .map_err(|err| WorkloadError::Grpc(format!("{:?}", err)))
+    }
+
+    /// It changes the number of instances to keep running for a workload. The instances are
+    /// then created or destroyed by the reconciler, no revision is recorded.
+    ///
+    /// # Arguments:
+    ///
+    /// * `workload_name`: The name of the workload
+    /// * `namespace`: The namespace of the workload
+    /// * `replicas`: The number of instances to keep running
+    ///
+    /// # Returns:
+    ///
+    /// The scaled workload
+    pub async fn scale_workload(
+        &mut self,
+        workload_name: &str,
+        namespace: &str,
+        replicas: u32,
+    ) -> Result<Workload, WorkloadError> {
+        let mut workload = self.get_workload(workload_name, namespace).await?;
+        workload.replicas = replicas;
+        self.put_workload(&workload).await?;
+        Ok(workload)
+    }
+
+    /// It returns the workloads of every namespace. Unlike `get_all_workloads`, it fails if
+    /// etcd can't be reached or if a workload can't be deserialized, as the workloads missing
+    /// from the list would be taken for deleted ones.
+    pub async fn get_workloads(&mut self) -> Result<Vec<Workload>, WorkloadError> {
+        self.etcd_service
+            .list_prefix("workload.")
+            .await
+            .map_err(|err| WorkloadError::Etcd(err.to_string()))?
+            .iter()
+            .map(|value| {
+                serde_json::from_str::<Workload>(value)
+                    .map_err(|err| WorkloadError::JsonToWorkload(err.to_string()))
+            })
+            .collect()
+    }
+
+    pub async fn delete_workload(&mut self, workload_name: &str, namespace: &str) {
+        let id = Self::id(workload_name, namespace);
+        _ = self.etcd_service.delete(&id).await;
+        let revisions_id = self.revisions_id(workload_name, namespace);
+        _ = self.etcd_service.delete(&revisions_id).await;
+        let rollout_id = self.rollout_id(workload_name, namespace);
+        _ = self.etcd_service.delete(&rollout_id).await;
+    }
+
+    /// It returns the progress of the rollout of the current revision of a workload
+    pub async fn get_workload_rollout(
+        &mut self,
+        workload_name: &str,
+        namespace: &str,
+    ) -> Result<Rollout, WorkloadError> {
+        let workload = self.get_workload(workload_name, namespace).await?;
+        self.get_rollout(&workload).await
+    }
+
+    /// It returns the rollout stored for the current revision of the workload, or a new
+    /// rollout if the revision changed since the last one.
+    pub async fn get_rollout(&mut self, workload: &Workload) -> Result<Rollout, WorkloadError> {
+        let id = self.rollout_id(&workload.name, &workload.namespace);
+        match self.etcd_service.get(&id).await {
+            Some(rollout) => {
+                let rollout: Rollout = serde_json::from_str(&rollout)
+                    .map_err(|err| WorkloadError::JsonToWorkload(err.to_string()))?;
+                if rollout.revision == workload.revision {
+                    Ok(rollout)
+                } else {
+                    Ok(Rollout::new(workload))
+                }
+            }
+            None => Ok(Rollout::new(workload)),
+        }
+    }
+
+    pub async fn put_rollout(
+        &mut self,
+        workload: &Workload,
+        rollout: &Rollout,
+    ) -> Result<(), WorkloadError> {
+        let json = serde_json::to_string(rollout)
             .map_err(|err| WorkloadError::WorkloadToJson(err.to_string()))?;
         self.etcd_service
-            .put(&new_id, &json)
+            .put(&self.rollout_id(&workload.name, &workload.namespace), &json)
             .await
             .map_err(|err| WorkloadError::Etcd(err.to_string()))?;
+        Ok(())
+    }
+
+    /// It returns the revisions kept for a workload, from the oldest to the newest
+    ///
+    /// # Arguments:
+    ///
+    /// * `workload_name`: The name of the workload
+    /// * `namespace`: The namespace of the workload
+    ///
+    /// # Returns:
+    ///
+    /// A Result<WorkloadRevisionVector, WorkloadError>
+    pub async fn get_revisions(
+        &mut self,
+        workload_name: &str,
+        namespace: &str,
+    ) -> Result<WorkloadRevisionVector, WorkloadError> {
+        self.get_workload(workload_name, namespace).await?;
+        self.revisions(workload_name, namespace).await
+    }
+
+    /// It returns the workload as it was at the given revision, if the revision is still kept
+    pub async fn get_revision(
+        &mut self,
+        workload: &Workload,
+        revision: u64,
+    ) -> Result<Option<Workload>, WorkloadError> {
+        let history = self.revisions(&workload.name, &workload.namespace).await?;
+        Ok(history
+            .revisions
+            .into_iter()
+            .find(|r| r.revision == revision)
+            .map(|r| r.workload))
+    }
+
+    /// It restores the specification of a previous revision of a workload, keeping the current
+    /// replica count, rollout strategy and priority. The restored specification is recorded as
+    /// a new revision.
+    ///
+    /// # Arguments:
+    ///
+    /// * `workload_name`: The name of the workload
+    /// * `namespace`: The namespace of the workload
+    /// * `revision`: The revision to restore, the one before the current revision if `None`
+    /// * `author`: The user requesting the rollback
+    ///
+    /// # Returns:
+    ///
+    /// The restored workload
+    pub async fn rollback_workload(
+        &mut self,
+        workload_name: &str,
+        namespace: &str,
+        revision: Option<u64>,
+        author: &str,
+    ) -> Result<Workload, WorkloadError> {
+        let current = self.get_workload(workload_name, namespace).await?;
+        let history = self.revisions(workload_name, namespace).await?;
+
+        let target = history.rollback_target(current.revision, revision)?;
+
+        let mut workload = current.with_spec_of(&target.workload);
+        if !current.same_spec(&workload) {
+            self.record_revision(&mut workload, author).await?;
+        }
+        self.put_workload(&workload).await?;
         Ok(workload)
     }
 
-    pub async fn delete_workload(&mut self, workload_name: &str, namespace: &str) {
-        let id = self.id(workload_name, namespace);
-        _ = self.etcd_service.delete(&id).await;
+    /// It appends a new revision of the workload to its history, drops the revisions over the
+    /// history limit and sets the revision number on the workload.
+    async fn record_revision(
+        &mut self,
+        workload: &mut Workload,
+        author: &str,
+    ) -> Result<(), WorkloadError> {
+        let mut history = self.revisions(&workload.name, &workload.namespace).await?;
+        let revision = history.revisions.last().map_or(workload.revision, |last| {
+            last.revision.max(workload.revision)
+        }) + 1;
+        workload.revision = revision;
+
+        history.push(
+            WorkloadRevision {
+                revision,
+                timestamp: SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map(|d| d.as_secs())
+                    .unwrap_or_default(),
+                author: author.to_string(),
+                workload: workload.clone(),
+            },
+            self.revision_history_limit,
+        );
+
+        let json = serde_json::to_string(&history)
+            .map_err(|err| WorkloadError::WorkloadToJson(err.to_string()))?;
+        self.etcd_service
+            .put(
+                &self.revisions_id(&workload.name, &workload.namespace),
+                &json,
+            )
+            .await
+            .map_err(|err| WorkloadError::Etcd(err.to_string()))?;
+        Ok(())
+    }
+
+    async fn revisions(
+        &mut self,
+        workload_name: &str,
+        namespace: &str,
+    ) -> Result<WorkloadRevisionVector, WorkloadError> {
+        let id = self.revisions_id(workload_name, namespace);
+        match self.etcd_service.get(&id).await {
+            Some(history) => serde_json::from_str(&history)
+                .map_err(|err| WorkloadError::JsonToWorkload(err.to_string())),
+            None => Ok(WorkloadRevisionVector::default()),
+        }
+    }
+
+    async fn put_workload(&mut self, workload: &Workload) -> Result<(), WorkloadError> {
+        let json = serde_json::to_string(workload)
+            .map_err(|err| WorkloadError::WorkloadToJson(err.to_string()))?;
+        self.etcd_service
+            .put(&Self::id(&workload.name, &workload.namespace), &json)
+            .await
+            .map_err(|err| WorkloadError::Etcd(err.to_string()))?;
+        Ok(())
     }
 
-    pub fn id(&mut self, name: &str, namespace: &str) -> String {
-        format!("{}.{}", namespace, name)
+    /// It moves the workloads stored under the keys of the previous versions, `{namespace}.{name}`,
+    /// to their current key, so they are listed and reconciled again. A workload already stored
+    /// under its current key is kept, and the old key is dropped.
+    ///
+    /// # Returns:
+    ///
+    /// The number of workloads moved
+    pub async fn migrate_legacy_keys(&mut self) -> Result<usize, WorkloadError> {
+        let entries = self
+            .etcd_service
+            .list_prefix_entries("")
+            .await
+            .map_err(|err| WorkloadError::Etcd(err.to_string()))?;
+
+        let legacy = legacy_workloads(&entries);
+        for (key, workload) in &legacy {
+            let id = Self::id(&workload.name, &workload.namespace);
+            if self.etcd_service.get(&id).await.is_none() {
+                self.put_workload(workload).await?;
+            }
+            self.etcd_service.delete(key).await;
+            info!(
+                "Workload {} moved from key {} to key {}",
+                workload.name, key, id
+            );
+        }
+        Ok(legacy.len())
+    }
+
+    pub fn id(name: &str, namespace: &str) -> String {
+        format!("workload.{}.{}", namespace, name)
+    }
+
+    fn revisions_id(&self, name: &str, namespace: &str) -> String {
+        format!("revisions.{}.{}", namespace, name)
+    }
+
+    fn rollout_id(&self, name: &str, namespace: &str) -> String {
+        format!("rollout.{}.{}", namespace, name)
+    }
+}
+
+/// It returns the workloads stored under the keys of the previous versions, `{namespace}.{name}`,
+/// along with their key. The values of the other keys aren't workloads, or don't match their key.
+fn legacy_workloads(entries: &[(String, String)]) -> Vec<(String, Workload)> {
+    entries
+        .iter()
+        .filter_map(|(key, value)| {
+            let workload = serde_json::from_str::<Workload>(value).ok()?;
+            let legacy = format!("{}.{}", workload.namespace, workload.name);
+            (*key == legacy).then(|| (key.clone(), workload))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn workload(name: &str, namespace: &str) -> Workload {
+        let dto: WorkloadDTO = serde_json::from_str(&format!(
+            r#"{{"name": "{}", "environment": [], "ports": [], "uri": "nginx"}}"#,
+            name
+        ))
+        .unwrap();
+        dto.into_workload(WorkloadService::id(name, namespace), namespace, 1)
+    }
+
+    fn entry(key: &str, workload: &Workload) -> (String, String) {
+        (key.to_string(), serde_json::to_string(workload).unwrap())
+    }
+
+    #[test]
+    fn test_legacy_workloads() {
+        let web = workload("web", "default");
+        let db = workload("db", "prod");
+        let entries = vec![
+            entry("default.web", &web),
+            entry("workload.prod.db", &db),
+            entry("revisions.default.web", &web),
+            ("node.a".to_string(), "{}".to_string()),
+            ("prod.db".to_string(), "not a workload".to_string()),
+        ];
+
+        let legacy = legacy_workloads(&entries);
+        assert_eq!(legacy.len(), 1);
+        assert_eq!(legacy[0].0, "default.web");
+        assert_eq!(legacy[0].1.name, "web");
+        assert_eq!(legacy[0].1.namespace, "default");
     }
 }
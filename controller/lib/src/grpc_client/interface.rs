@@ -1,7 +1,13 @@
 use log::{error, info};
 use proto::scheduler::instance_service_client::InstanceServiceClient;
-use proto::scheduler::{Instance, InstanceIdentifier, InstanceStatus};
-use tonic::transport::{Channel, Error};
+use proto::scheduler::node_service_client::NodeServiceClient;
+use proto::scheduler::{
+    DryRunRequest, DryRunResponse, Instance, InstanceIdentifier, InstanceListRequest,
+    InstanceListResponse, InstanceStatus, NodeCordonRequest, NodeCordonResponse, NodeDrainRequest,
+    NodeDrainStatus, NodeDrainStatusRequest, NodeIdentifier, NodeInfo, NodeListResponse,
+    NodeUpdateRequest, NodeUpdateResponse, PendingListResponse, PlacementExplanation,
+};
+use tonic::transport::{Channel, Endpoint, Error};
 use tonic::{Request, Response, Status, Streaming};
 
 #[derive(Debug)]
@@ -10,8 +16,10 @@ pub enum SchedulerClientInterfaceError {
     RequestFailed(Status),
 }
 
+#[derive(Clone)]
 pub struct SchedulerClientInterface {
     instance_client: InstanceServiceClient<Channel>,
+    node_client: NodeServiceClient<Channel>,
 }
 
 impl SchedulerClientInterface {
@@ -19,15 +27,20 @@ impl SchedulerClientInterface {
         instance_client_address: String,
     ) -> Result<Self, SchedulerClientInterfaceError> {
         info!(
-            "Starting gRPC client for scheduler Instance Service on {}",
+            "Starting gRPC client for scheduler Instance and Node Services on {}",
             instance_client_address,
         );
 
-        let instance_client = InstanceServiceClient::connect(instance_client_address)
+        let channel = Endpoint::new(instance_client_address)
+            .map_err(SchedulerClientInterfaceError::ConnectionError)?
+            .connect()
             .await
             .map_err(SchedulerClientInterfaceError::ConnectionError)?;
 
-        Ok(Self { instance_client })
+        Ok(Self {
+            instance_client: InstanceServiceClient::new(channel.clone()),
+            node_client: NodeServiceClient::new(channel),
+        })
     }
 
     pub async fn create_instance(
@@ -53,6 +66,29 @@ impl SchedulerClientInterface {
             .map_err(SchedulerClientInterfaceError::RequestFailed)
     }
 
+    pub async fn watch_instance(
+        &mut self,
+        request: Request<InstanceIdentifier>,
+    ) -> Result<Response<Streaming<InstanceStatus>>, SchedulerClientInterfaceError> {
+        let remote_address = match request.remote_addr() {
+            Some(addr) => addr.to_string(),
+            None => {
+                error!("\"watch_instance\" Failed to get remote address from request");
+                "Error getting address".to_string()
+            }
+        };
+
+        info!(
+            "Calling gRPC procedure \"watch_instance\" to {}",
+            remote_address
+        );
+
+        self.instance_client
+            .watch(request)
+            .await
+            .map_err(SchedulerClientInterfaceError::RequestFailed)
+    }
+
     pub async fn destroy_instance(
         &mut self,
         request: Request<InstanceIdentifier>,
@@ -121,4 +157,145 @@ impl SchedulerClientInterface {
             .await
             .map_err(SchedulerClientInterfaceError::RequestFailed)
     }
+
+    pub async fn update_node(
+        &mut self,
+        request: Request<NodeUpdateRequest>,
+    ) -> Result<Response<NodeUpdateResponse>, SchedulerClientInterfaceError> {
+        info!(
+            "Calling gRPC procedure \"update_node\" for node {}",
+            request.get_ref().id
+        );
+
+        self.node_client
+            .update(request)
+            .await
+            .map_err(SchedulerClientInterfaceError::RequestFailed)
+    }
+
+    pub async fn cordon_node(
+        &mut self,
+        request: Request<NodeCordonRequest>,
+    ) -> Result<Response<NodeCordonResponse>, SchedulerClientInterfaceError> {
+        info!(
+            "Calling gRPC procedure \"cordon_node\" for node {}",
+            request.get_ref().id
+        );
+
+        self.node_client
+            .cordon(request)
+            .await
+            .map_err(SchedulerClientInterfaceError::RequestFailed)
+    }
+
+    pub async fn drain_node(
+        &mut self,
+        request: Request<NodeDrainRequest>,
+    ) -> Result<Response<NodeDrainStatus>, SchedulerClientInterfaceError> {
+        info!(
+            "Calling gRPC procedure \"drain_node\" for node {}",
+            request.get_ref().id
+        );
+
+        self.node_client
+            .drain(request)
+            .await
+            .map_err(SchedulerClientInterfaceError::RequestFailed)
+    }
+
+    pub async fn drain_status(
+        &mut self,
+        request: Request<NodeDrainStatusRequest>,
+    ) -> Result<Response<NodeDrainStatus>, SchedulerClientInterfaceError> {
+        info!(
+            "Calling gRPC procedure \"drain_status\" for node {}",
+            request.get_ref().id
+        );
+
+        self.node_client
+            .drain_status(request)
+            .await
+            .map_err(SchedulerClientInterfaceError::RequestFailed)
+    }
+
+    pub async fn list_nodes(
+        &mut self,
+        request: Request<()>,
+    ) -> Result<Response<NodeListResponse>, SchedulerClientInterfaceError> {
+        info!("Calling gRPC procedure \"list_nodes\"");
+
+        self.node_client
+            .list(request)
+            .await
+            .map_err(SchedulerClientInterfaceError::RequestFailed)
+    }
+
+    pub async fn get_node(
+        &mut self,
+        request: Request<NodeIdentifier>,
+    ) -> Result<Response<NodeInfo>, SchedulerClientInterfaceError> {
+        info!(
+            "Calling gRPC procedure \"get_node\" for node {}",
+            request.get_ref().id
+        );
+
+        self.node_client
+            .get(request)
+            .await
+            .map_err(SchedulerClientInterfaceError::RequestFailed)
+    }
+
+    pub async fn list_instances(
+        &mut self,
+        request: Request<InstanceListRequest>,
+    ) -> Result<Response<InstanceListResponse>, SchedulerClientInterfaceError> {
+        info!("Calling gRPC procedure \"list_instances\"");
+
+        self.instance_client
+            .list(request)
+            .await
+            .map_err(SchedulerClientInterfaceError::RequestFailed)
+    }
+
+    pub async fn pending_instances(
+        &mut self,
+        request: Request<()>,
+    ) -> Result<Response<PendingListResponse>, SchedulerClientInterfaceError> {
+        info!("Calling gRPC procedure \"pending_instances\"");
+
+        self.instance_client
+            .pending(request)
+            .await
+            .map_err(SchedulerClientInterfaceError::RequestFailed)
+    }
+
+    pub async fn explain_instance(
+        &mut self,
+        request: Request<InstanceIdentifier>,
+    ) -> Result<Response<PlacementExplanation>, SchedulerClientInterfaceError> {
+        info!(
+            "Calling gRPC procedure \"explain_instance\" for instance {}",
+            request.get_ref().id
+        );
+
+        self.instance_client
+            .explain(request)
+            .await
+            .map_err(SchedulerClientInterfaceError::RequestFailed)
+    }
+
+    pub async fn dry_run(
+        &mut self,
+        request: Request<DryRunRequest>,
+    ) -> Result<Response<DryRunResponse>, SchedulerClientInterfaceError> {
+        info!(
+            "Calling gRPC procedure \"dry_run\" for {} instance(s)",
+            request.get_ref().instances.len()
+        );
+
+        self.instance_client
+            .dry_run(request)
+            .await
+            .map_err(SchedulerClientInterfaceError::RequestFailed)
+    }
 }
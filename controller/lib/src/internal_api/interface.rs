@@ -3,22 +3,42 @@ use std::net::SocketAddr;
 use super::node::controller::NodeController;
 use log::info;
 use proto::controller::node_service_server::NodeServiceServer;
-use tonic::transport::Server;
+use shutdown::Shutdown;
+use tokio::task::JoinHandle;
+use tonic::transport::{Error, Server};
 
-pub struct InternalAPIInterface {}
+pub struct InternalAPIInterface {
+    server: JoinHandle<Result<(), Error>>,
+}
 
 impl InternalAPIInterface {
-    pub async fn new(address: SocketAddr) -> Self {
+    /// It spawns the gRPC server, which stops accepting connections once `shutdown` is
+    /// triggered and then waits for the requests in flight. The node status streams are
+    /// closed by the node controller.
+    pub async fn new(
+        address: SocketAddr,
+        etcd_address: SocketAddr,
+        event_ttl: i64,
+        shutdown: Shutdown,
+    ) -> Self {
         info!("Starting gRPC server listening on {}", address);
 
-        tokio::spawn(async move {
+        let node_controller = NodeController::new(etcd_address, event_ttl, shutdown.clone());
+        let mut shutdown = shutdown;
+        let server = tokio::spawn(async move {
             Server::builder()
-                .add_service(NodeServiceServer::new(NodeController::default()))
-                .serve(address)
+                .add_service(NodeServiceServer::new(node_controller))
+                .serve_with_shutdown(address, shutdown.wait())
                 .await
-                .unwrap();
         });
 
-        Self {}
+        Self { server }
+    }
+
+    /// It waits for the gRPC server to stop
+    pub async fn wait(self) -> Result<(), Box<dyn std::error::Error>> {
+        self.server.await??;
+        info!("gRPC server stopped");
+        Ok(())
     }
 }
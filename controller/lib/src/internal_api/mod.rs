@@ -1,2 +1,2 @@
 pub mod interface;
-mod node;
+pub(crate) mod node;
@@ -1,14 +1,33 @@
-use log::info;
+use std::net::SocketAddr;
+
+use log::{error, info, warn};
 use tonic::{Request, Response, Status, Streaming};
 
 use proto::controller::NodeStatus;
 
 use proto::controller::node_service_server::NodeService;
 
-use super::service::update_node_status;
+use super::service::{update_node_status, NodeStatusTracker};
+use crate::etcd::EtcdClient;
+use crate::event::service::EventService;
+use shutdown::Shutdown;
+
+#[derive(Debug)]
+pub struct NodeController {
+    etcd_address: SocketAddr,
+    event_ttl: i64,
+    shutdown: Shutdown,
+}
 
-#[derive(Debug, Default)]
-pub struct NodeController {}
+impl NodeController {
+    pub fn new(etcd_address: SocketAddr, event_ttl: i64, shutdown: Shutdown) -> Self {
+        NodeController {
+            etcd_address,
+            event_ttl,
+            shutdown,
+        }
+    }
+}
 
 #[tonic::async_trait]
 impl NodeService for NodeController {
@@ -16,27 +35,66 @@ impl NodeService for NodeController {
         &self,
         request: Request<Streaming<NodeStatus>>,
     ) -> Result<Response<()>, Status> {
-        let remote_address = request.remote_addr().unwrap();
+        let remote_address = request
+            .remote_addr()
+            .map(|address| address.to_string())
+            .unwrap_or_else(|| "unknown".to_string());
 
         info!(
             "{} \"update_node_status\" streaming initiated",
-            remote_address.clone()
+            remote_address
         );
 
+        let mut event_service = EventService::new(&self.etcd_address, self.event_ttl)
+            .await
+            .map_err(|err| Status::internal(err.to_string()))?;
+        let mut etcd_service = EtcdClient::new(self.etcd_address.to_string())
+            .await
+            .map_err(|err| Status::internal(err.to_string()))?;
+        let mut tracker = NodeStatusTracker::default();
         let mut stream = request.into_inner();
+        let mut shutdown = self.shutdown.clone();
 
-        while let Some(node_status) = stream.message().await.unwrap() {
-            info!(
-                "{} \"update_node_status\" received chunk",
-                remote_address.clone()
-            );
-            update_node_status(node_status).unwrap();
+        loop {
+            // the stream is closed on shutdown, the node reconnects once the controller is back
+            let node_status = tokio::select! {
+                message = stream.message() => match message {
+                    Ok(Some(node_status)) => node_status,
+                    Ok(None) => break,
+                    // the node monitor notices if the node doesn't reconnect
+                    Err(err) => {
+                        warn!(
+                            "{} \"update_node_status\" stream failed : {}",
+                            remote_address, err
+                        );
+                        break;
+                    }
+                },
+                _ = shutdown.wait() => {
+                    info!(
+                        "{} \"update_node_status\" closing stream on shutdown",
+                        remote_address
+                    );
+                    break;
+                }
+            };
+            info!("{} \"update_node_status\" received chunk", remote_address);
+            if let Err(err) = update_node_status(
+                node_status,
+                &mut tracker,
+                &mut event_service,
+                &mut etcd_service,
+            )
+            .await
+            {
+                error!(
+                    "{} \"update_node_status\" failed to update node status : {}",
+                    remote_address, err
+                );
+            }
         }
 
-        info!(
-            "{} \"update_node_status\" streaming closed",
-            remote_address.clone()
-        );
+        info!("{} \"update_node_status\" streaming closed", remote_address);
 
         Ok(Response::new(()))
     }
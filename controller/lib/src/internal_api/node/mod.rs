@@ -1,2 +1,3 @@
 pub mod controller;
-mod service;
+pub(crate) mod model;
+pub(crate) mod service;
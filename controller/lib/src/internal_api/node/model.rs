@@ -0,0 +1,57 @@
+use proto::controller;
+use serde::{Deserialize, Serialize};
+
+use crate::event::model::EventError;
+use crate::external_api::instance::model::InstanceError;
+
+pub enum NodeError {
+    Etcd(String),
+    NodeToJson(String),
+    Event(EventError),
+    Instance(InstanceError),
+}
+
+impl std::fmt::Display for NodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NodeError::Etcd(err) => write!(f, "Etcd error: {}", err),
+            NodeError::NodeToJson(err) => write!(f, "Node to JSON error: {}", err),
+            NodeError::Event(err) => write!(f, "{}", err),
+            NodeError::Instance(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NodeState {
+    Registering,
+    Registered,
+    Unregistering,
+    Unregistered,
+    /// The node stopped sending its status, it is unregistered if it doesn't come back
+    Failing,
+}
+
+impl From<controller::NodeState> for NodeState {
+    fn from(state: controller::NodeState) -> Self {
+        match state {
+            controller::NodeState::Registering => NodeState::Registering,
+            controller::NodeState::Registered => NodeState::Registered,
+            controller::NodeState::Unregistering => NodeState::Unregistering,
+            controller::NodeState::Unregistered => NodeState::Unregistered,
+            controller::NodeState::Failing => NodeState::Failing,
+        }
+    }
+}
+
+/// A node of the cluster, as last seen by the controllers
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct Node {
+    pub id: String,
+    pub state: NodeState,
+    pub status_description: String,
+    /// Seconds since the UNIX epoch of the last status sent by the node
+    pub last_heartbeat: u64,
+    /// Ids of the instances the node last reported
+    pub instances: Vec<String>,
+}
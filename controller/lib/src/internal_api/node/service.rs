@@ -1,7 +1,129 @@
-use std::fmt::Error;
+use std::collections::HashMap;
+use std::time::{SystemTime, UNIX_EPOCH};
 
+use log::{debug, error};
 use proto::controller::NodeStatus;
 
-pub fn update_node_status(_node_status: NodeStatus) -> Result<(), Error> {
+use super::model::{Node, NodeError, NodeState};
+use crate::etcd::EtcdClient;
+use crate::event::model::InvolvedKind;
+use crate::event::service::{EventService, CLUSTER_EVENTS_NAMESPACE};
+use crate::external_api::instance::model::{InstanceError, InstanceStatus};
+use crate::external_api::instance::service::{update_instance_statuses, InstanceReport};
+
+/// Last state of the instances reported by a node over its status stream, used to update the
+/// instances only when their state changes.
+#[derive(Debug, Default)]
+pub struct NodeStatusTracker {
+    instances: HashMap<String, InstanceReport>,
+}
+
+/// It records a heartbeat of the node, along with the state of its instances. An event is
+/// recorded each time the node changes state, including when it comes back after failing.
+pub async fn update_node_status(
+    node_status: NodeStatus,
+    tracker: &mut NodeStatusTracker,
+    event_service: &mut EventService,
+    etcd_service: &mut EtcdClient,
+) -> Result<(), NodeError> {
+    let reports: Vec<(String, InstanceReport)> = node_status
+        .instances
+        .iter()
+        .map(|instance| {
+            let status = InstanceStatus::from(instance.state());
+            (
+                instance.id.clone(),
+                (status, instance.restart_count, instance.ready),
+            )
+        })
+        .filter(|(id, report)| tracker.instances.get(id) != Some(report))
+        .collect();
+
+    match update_instance_statuses(etcd_service, &reports).await {
+        Ok(results) => {
+            for ((id, result), (_, report)) in results.into_iter().zip(reports) {
+                match result {
+                    Ok(()) => {}
+                    // the instance wasn't created by the controller
+                    Err(InstanceError::InstanceNotFound) => {
+                        debug!("Node {} runs unknown instance {}", node_status.id, id)
+                    }
+                    Err(err) => {
+                        error!("Failed to update instance {} status : {}", id, err);
+                        continue;
+                    }
+                }
+                tracker.instances.insert(id, report);
+            }
+        }
+        Err(err) => error!(
+            "Failed to update the instances of node {} : {}",
+            node_status.id, err
+        ),
+    }
+
+    let previous = get_node(etcd_service, &node_status.id).await;
+    let node = Node {
+        id: node_status.id.clone(),
+        state: NodeState::from(node_status.state()),
+        status_description: node_status.status_description.clone(),
+        last_heartbeat: now(),
+        instances: node_status
+            .instances
+            .iter()
+            .map(|instance| instance.id.clone())
+            .collect(),
+    };
+    put_node(etcd_service, &node).await?;
+
+    if previous.map(|previous| previous.state) != Some(node.state) {
+        event_service
+            .record(
+                CLUSTER_EVENTS_NAMESPACE,
+                InvolvedKind::Node,
+                &node.id,
+                &format!("{:?}", node.state),
+                &node.status_description,
+            )
+            .await
+            .map_err(NodeError::Event)?;
+    }
+
+    Ok(())
+}
+
+fn node_key(id: &str) -> String {
+    format!("node.{}", id)
+}
+
+/// Seconds since the UNIX epoch
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+pub async fn get_node(etcd_service: &mut EtcdClient, id: &str) -> Option<Node> {
+    let node = etcd_service.get(&node_key(id)).await?;
+    serde_json::from_str(&node).ok()
+}
+
+pub async fn get_nodes(etcd_service: &mut EtcdClient) -> Vec<Node> {
+    match etcd_service.get_prefix(&node_key("")).await {
+        Some(nodes) => nodes
+            .iter()
+            .filter_map(|node| serde_json::from_str::<Node>(node).ok())
+            .collect(),
+        None => vec![],
+    }
+}
+
+pub async fn put_node(etcd_service: &mut EtcdClient, node: &Node) -> Result<(), NodeError> {
+    let json = serde_json::to_string(node).map_err(|err| NodeError::NodeToJson(err.to_string()))?;
+    etcd_service
+        .put(&node_key(&node.id), &json)
+        .await
+        .map_err(|err| NodeError::Etcd(err.to_string()))?;
     Ok(())
 }
@@ -0,0 +1,373 @@
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use etcd_client::{ElectionClient, LeaseKeepAliveStream, LeaseKeeper, ResignOptions};
+use log::{debug, error, info, warn};
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+use tokio::time::Instant;
+
+use crate::etcd::EtcdClient;
+use shutdown::Shutdown;
+
+/// Name of the etcd election the controllers campaign in
+const ELECTION_NAME: &str = "leader.controller";
+
+pub enum ElectionError {
+    Etcd(etcd_client::Error),
+    LeaseExpired,
+}
+
+impl std::fmt::Display for ElectionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ElectionError::Etcd(err) => write!(f, "Etcd error: {}", err),
+            ElectionError::LeaseExpired => write!(f, "Leader lease expired"),
+        }
+    }
+}
+
+impl From<etcd_client::Error> for ElectionError {
+    fn from(err: etcd_client::Error) -> Self {
+        ElectionError::Etcd(err)
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+struct LeaderState {
+    /// Identity of the current leader, if known
+    leader: Option<String>,
+    is_leader: bool,
+}
+
+/// `Leadership` tells whether this controller is the leader of the controllers. The background
+/// tasks which must run on a single controller, such as the reconciliation loop, wait for the
+/// leadership before doing any work.
+/// It can be cloned to be handed to each task.
+#[derive(Clone, Debug)]
+pub struct Leadership {
+    identity: String,
+    receiver: watch::Receiver<LeaderState>,
+}
+
+impl Leadership {
+    /// It returns the identity this controller campaigns with
+    pub fn identity(&self) -> &str {
+        &self.identity
+    }
+
+    pub fn is_leader(&self) -> bool {
+        self.receiver.borrow().is_leader
+    }
+
+    /// It returns the identity of the current leader, if known
+    pub fn leader(&self) -> Option<String> {
+        self.receiver.borrow().leader.clone()
+    }
+
+    /// It waits until this controller is the leader. It never returns once the election is
+    /// stopped without this controller being the leader.
+    pub async fn acquired(&mut self) {
+        while !self.receiver.borrow_and_update().is_leader {
+            if self.receiver.changed().await.is_err() {
+                std::future::pending::<()>().await;
+            }
+        }
+    }
+}
+
+/// `LeaderElection` elects a single leader among the controllers sharing the same etcd.
+/// Each controller campaigns with a lease kept alive while it runs, the leader being the
+/// first candidate whose lease is still alive. When the leader stops, it resigns; when it
+/// crashes or loses etcd, its lease expires and another controller takes over within `ttl`.
+/// A leader which can't renew its lease before it expires steps down.
+/// Properties:
+///
+/// * `etcd_address`: The address of etcd.
+/// * `identity`: The identity of this controller, as shown to the other controllers.
+/// * `ttl`: Time to live of the lease of the leader, in seconds.
+pub struct LeaderElection {
+    etcd_address: SocketAddr,
+    identity: String,
+    ttl: i64,
+}
+
+impl LeaderElection {
+    pub fn new(etcd_address: SocketAddr, identity: String, ttl: i64) -> Self {
+        LeaderElection {
+            etcd_address,
+            identity,
+            ttl: ttl.max(1),
+        }
+    }
+
+    /// It spawns a task campaigning for the leadership until `shutdown` is triggered, and
+    /// returns the `Leadership` it updates.
+    pub fn start(self, mut shutdown: Shutdown) -> (Leadership, JoinHandle<()>) {
+        info!(
+            "Campaigning for leadership as {} with a {} second(s) lease",
+            self.identity, self.ttl
+        );
+
+        let (sender, receiver) = watch::channel(LeaderState::default());
+        let leadership = Leadership {
+            identity: self.identity.clone(),
+            receiver,
+        };
+
+        let handle = tokio::spawn(async move {
+            loop {
+                match self.campaign(&sender, &mut shutdown).await {
+                    Ok(()) => break,
+                    Err(err) => error!("Leader election failed : {}", err),
+                }
+                if sender.borrow().is_leader {
+                    warn!("Leadership lost");
+                }
+                sender.send_replace(LeaderState::default());
+
+                tokio::select! {
+                    _ = tokio::time::sleep(self.renewal_period()) => {},
+                    _ = shutdown.wait() => break,
+                }
+            }
+            sender.send_replace(LeaderState::default());
+            info!("Leader election stopped");
+        });
+
+        (leadership, handle)
+    }
+
+    /// The lease is renewed three times per `ttl`, so a renewal can fail without losing it
+    fn renewal_period(&self) -> Duration {
+        Duration::from_millis(self.ttl as u64 * 1000 / 3)
+    }
+
+    /// It campaigns with a new lease and then keeps the leadership, until the shutdown, which
+    /// returns `Ok`, or until the lease is lost.
+    async fn campaign(
+        &self,
+        sender: &watch::Sender<LeaderState>,
+        shutdown: &mut Shutdown,
+    ) -> Result<(), ElectionError> {
+        let etcd_client = EtcdClient::new(self.etcd_address.to_string()).await?;
+        let mut lease_client = etcd_client.lease_client();
+        let mut election_client = etcd_client.election_client();
+
+        let lease = lease_client.grant(self.ttl, None).await?.id();
+        let (mut keeper, mut responses) = lease_client.keep_alive(lease).await?;
+        let mut expiry = Instant::now() + Duration::from_secs(self.ttl as u64);
+        let mut renewal = tokio::time::interval(self.renewal_period());
+
+        let mut campaign_client = election_client.clone();
+        let campaign = campaign_client.campaign(ELECTION_NAME, self.identity.clone(), lease);
+        tokio::pin!(campaign);
+
+        // the lease is kept alive while waiting for the current leader to go away
+        let leader_key = loop {
+            tokio::select! {
+                response = &mut campaign => break response?.take_leader(),
+                _ = renewal.tick() => {
+                    self.keep_alive(&mut keeper, &mut responses, &mut expiry).await?;
+                    let leader = current_leader(&mut election_client).await;
+                    sender.send_if_modified(|state| {
+                        let changed = state.leader != leader;
+                        state.leader = leader;
+                        changed
+                    });
+                },
+                _ = shutdown.wait() => {
+                    let _ = lease_client.revoke(lease).await;
+                    return Ok(());
+                }
+            }
+        };
+
+        info!("Elected leader as {}", self.identity);
+        sender.send_replace(LeaderState {
+            leader: Some(self.identity.clone()),
+            is_leader: true,
+        });
+
+        loop {
+            tokio::select! {
+                _ = renewal.tick() => {
+                    self.keep_alive(&mut keeper, &mut responses, &mut expiry).await?;
+                },
+                _ = shutdown.wait() => {
+                    // the other controllers don't wait for the lease to expire
+                    info!("Resigning leadership");
+                    if let Err(err) = election_client
+                        .resign(leader_key.map(|key| ResignOptions::new().with_leader(key)))
+                        .await
+                    {
+                        warn!("Failed to resign leadership : {}", err);
+                    }
+                    let _ = lease_client.revoke(lease).await;
+                    return Ok(());
+                }
+            }
+        }
+    }
+
+    /// It renews the lease, a failed renewal being tolerated until the lease expires
+    async fn keep_alive(
+        &self,
+        keeper: &mut LeaseKeeper,
+        responses: &mut LeaseKeepAliveStream,
+        expiry: &mut Instant,
+    ) -> Result<(), ElectionError> {
+        let renewed = tokio::time::timeout_at(*expiry, async {
+            keeper.keep_alive().await?;
+            responses.message().await
+        })
+        .await;
+
+        let ttl = match renewed {
+            Ok(Ok(response)) => Some(response.map_or(0, |response| response.ttl())),
+            Ok(Err(err)) => {
+                debug!("Failed to renew leader lease : {}", err);
+                None
+            }
+            Err(_) => None,
+        };
+        *expiry = lease_expiry(Instant::now(), *expiry, ttl).ok_or(ElectionError::LeaseExpired)?;
+        Ok(())
+    }
+}
+
+/// It returns when the lease expires after a renewal attempted at `now`, given the time to live
+/// etcd answered with, or `None` if the renewal failed. A failed renewal keeps the previous
+/// `expiry`, and the lease is lost, which returns `None`, once it is over or once etcd drops it.
+fn lease_expiry(now: Instant, expiry: Instant, ttl: Option<i64>) -> Option<Instant> {
+    match ttl {
+        Some(ttl) if ttl > 0 => Some(now + Duration::from_secs(ttl as u64)),
+        // etcd dropped the lease
+        Some(_) => None,
+        None if now < expiry => Some(expiry),
+        None => None,
+    }
+}
+
+/// It returns the identity of the current leader, if there is one
+async fn current_leader(election_client: &mut ElectionClient) -> Option<String> {
+    let response = election_client.leader(ELECTION_NAME).await.ok()?;
+    response
+        .kv()
+        .and_then(|kv| kv.value_str().ok())
+        .map(String::from)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn leadership() -> (watch::Sender<LeaderState>, Leadership) {
+        let (sender, receiver) = watch::channel(LeaderState::default());
+        let leadership = Leadership {
+            identity: "controller-1".to_string(),
+            receiver,
+        };
+        (sender, leadership)
+    }
+
+    fn state(leader: Option<&str>, is_leader: bool) -> LeaderState {
+        LeaderState {
+            leader: leader.map(String::from),
+            is_leader,
+        }
+    }
+
+    #[test]
+    fn test_leadership_follows_the_election() {
+        let (sender, leadership) = leadership();
+        assert!(!leadership.is_leader());
+        assert_eq!(leadership.leader(), None);
+
+        sender.send_replace(state(Some("controller-2"), false));
+        assert!(!leadership.is_leader());
+        assert_eq!(leadership.leader(), Some("controller-2".to_string()));
+
+        sender.send_replace(state(Some("controller-1"), true));
+        assert!(leadership.is_leader());
+        assert_eq!(leadership.leader(), Some("controller-1".to_string()));
+
+        // the leadership is lost
+        sender.send_replace(LeaderState::default());
+        assert!(!leadership.is_leader());
+        assert_eq!(leadership.leader(), None);
+    }
+
+    #[tokio::test]
+    async fn test_acquired_waits_for_the_leadership() {
+        let (sender, leadership) = leadership();
+        let mut waiting = leadership.clone();
+        let acquired = tokio::spawn(async move { waiting.acquired().await });
+
+        sender.send_replace(state(Some("controller-2"), false));
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(!acquired.is_finished());
+
+        sender.send_replace(state(Some("controller-1"), true));
+        tokio::time::timeout(Duration::from_secs(1), acquired)
+            .await
+            .expect("the leadership is acquired")
+            .unwrap();
+
+        // a leader doesn't wait
+        let mut leadership = leadership;
+        tokio::time::timeout(Duration::from_secs(1), leadership.acquired())
+            .await
+            .expect("already the leader");
+    }
+
+    #[tokio::test]
+    async fn test_acquired_never_returns_once_the_election_stopped() {
+        let (sender, mut leadership) = leadership();
+        drop(sender);
+
+        let acquired = tokio::time::timeout(Duration::from_millis(50), leadership.acquired()).await;
+        assert!(acquired.is_err());
+    }
+
+    #[test]
+    fn test_renewed_lease_expires_after_its_ttl() {
+        let now = Instant::now();
+        let expiry = now + Duration::from_secs(2);
+
+        let renewed = lease_expiry(now, expiry, Some(10));
+        assert_eq!(renewed, Some(now + Duration::from_secs(10)));
+    }
+
+    #[test]
+    fn test_failed_renewal_keeps_the_lease_until_it_expires() {
+        let now = Instant::now();
+        let expiry = now + Duration::from_secs(2);
+
+        assert_eq!(lease_expiry(now, expiry, None), Some(expiry));
+        assert_eq!(lease_expiry(expiry, expiry, None), None);
+        assert_eq!(
+            lease_expiry(expiry + Duration::from_secs(1), expiry, None),
+            None
+        );
+    }
+
+    #[test]
+    fn test_dropped_lease_is_lost() {
+        let now = Instant::now();
+        let expiry = now + Duration::from_secs(2);
+
+        assert_eq!(lease_expiry(now, expiry, Some(0)), None);
+        assert_eq!(lease_expiry(now, expiry, Some(-1)), None);
+    }
+
+    #[test]
+    fn test_lease_is_renewed_three_times_per_ttl() {
+        let election = LeaderElection::new("127.0.0.1:2379".parse().unwrap(), String::new(), 3);
+        assert_eq!(election.renewal_period(), Duration::from_secs(1));
+
+        // the time to live is at least a second
+        let election = LeaderElection::new("127.0.0.1:2379".parse().unwrap(), String::new(), 0);
+        assert_eq!(election.renewal_period(), Duration::from_millis(333));
+    }
+}
@@ -1,4 +1,8 @@
 pub mod etcd;
+pub mod event;
 pub mod external_api;
 pub mod grpc_client;
 pub mod internal_api;
+pub mod leader;
+pub mod node_monitor;
+pub mod reconciler;
@@ -0,0 +1,231 @@
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use log::{error, info, warn};
+use tokio::task::JoinHandle;
+
+use crate::etcd::EtcdClient;
+use crate::event::model::InvolvedKind;
+use crate::event::service::{EventService, CLUSTER_EVENTS_NAMESPACE};
+use crate::external_api::instance::service::mark_instances_lost;
+use crate::internal_api::node::model::{Node, NodeError, NodeState};
+use crate::internal_api::node::service::{get_nodes, now, put_node};
+use crate::leader::Leadership;
+use shutdown::Shutdown;
+
+/// `NodeMonitor` periodically checks the last heartbeat of each node. A node which stops
+/// sending its status is marked as failing after `failing_timeout`, then unregistered after
+/// `unregister_timeout`, its instances being marked as lost until the scheduler moves them to
+/// another node.
+/// A node which sends its status again is registered again by the node controller.
+/// Properties:
+///
+/// * `etcd_address`: The address of etcd.
+/// * `event_ttl`: Time to live of the recorded events, in seconds.
+/// * `failing_timeout`: Time without heartbeat after which a node is failing.
+/// * `unregister_timeout`: Time without heartbeat after which a node is unregistered.
+/// * `etcd_service`: The connection to etcd, opened by the first check and then reused.
+pub struct NodeMonitor {
+    etcd_address: SocketAddr,
+    event_ttl: i64,
+    failing_timeout: Duration,
+    unregister_timeout: Duration,
+    etcd_service: Option<EtcdClient>,
+}
+
+impl NodeMonitor {
+    pub fn new(
+        etcd_address: SocketAddr,
+        event_ttl: i64,
+        failing_timeout: Duration,
+        unregister_timeout: Duration,
+    ) -> Self {
+        NodeMonitor {
+            etcd_address,
+            event_ttl,
+            failing_timeout,
+            unregister_timeout: unregister_timeout.max(failing_timeout),
+            etcd_service: None,
+        }
+    }
+
+    /// It spawns a task checking the nodes three times per `failing_timeout` while this
+    /// controller is the leader, until `shutdown` is triggered.
+    pub fn start(mut self, mut leadership: Leadership, mut shutdown: Shutdown) -> JoinHandle<()> {
+        let period = (self.failing_timeout / 3).max(Duration::from_secs(1));
+        info!(
+            "Starting node monitor, nodes fail after {} second(s) without heartbeat",
+            self.failing_timeout.as_secs()
+        );
+
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(period);
+            loop {
+                if !leadership.is_leader() {
+                    tokio::select! {
+                        _ = leadership.acquired() => {},
+                        _ = shutdown.wait() => break,
+                    }
+                }
+                tokio::select! {
+                    _ = interval.tick() => {},
+                    _ = shutdown.wait() => break,
+                }
+                if !leadership.is_leader() {
+                    continue;
+                }
+                if let Err(err) = self.check().await {
+                    error!("Node check failed : {}", err);
+                }
+            }
+            info!("Node monitor stopped");
+        })
+    }
+
+    /// It moves the nodes whose last heartbeat is too old to their next state
+    pub async fn check(&mut self) -> Result<(), NodeError> {
+        let mut etcd_service = match &self.etcd_service {
+            Some(etcd_service) => etcd_service.clone(),
+            None => {
+                let etcd_service = EtcdClient::new(self.etcd_address.to_string())
+                    .await
+                    .map_err(|err| NodeError::Etcd(err.to_string()))?;
+                self.etcd_service.insert(etcd_service).clone()
+            }
+        };
+        let mut event_service = EventService::from_client(etcd_service.clone(), self.event_ttl);
+
+        for node in get_nodes(&mut etcd_service).await {
+            let silence = Duration::from_secs(now().saturating_sub(node.last_heartbeat));
+            let Some(state) = next_state(
+                node.state,
+                silence,
+                self.failing_timeout,
+                self.unregister_timeout,
+            ) else {
+                continue;
+            };
+
+            if let Err(err) = self
+                .transition(&mut etcd_service, &mut event_service, node, state, silence)
+                .await
+            {
+                error!("Failed to update node state : {}", err);
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn transition(
+        &self,
+        etcd_service: &mut EtcdClient,
+        event_service: &mut EventService,
+        mut node: Node,
+        state: NodeState,
+        silence: Duration,
+    ) -> Result<(), NodeError> {
+        warn!(
+            "Node {} is {:?}, no heartbeat for {} second(s)",
+            node.id,
+            state,
+            silence.as_secs()
+        );
+        node.state = state;
+        node.status_description = format!("No heartbeat for {} second(s)", silence.as_secs());
+        put_node(etcd_service, &node).await?;
+        event_service
+            .record(
+                CLUSTER_EVENTS_NAMESPACE,
+                InvolvedKind::Node,
+                &node.id,
+                &format!("{:?}", node.state),
+                &node.status_description,
+            )
+            .await
+            .map_err(NodeError::Event)?;
+
+        if state != NodeState::Unregistered {
+            return Ok(());
+        }
+
+        // the instances which have already been removed are skipped
+        let lost = mark_instances_lost(etcd_service, &node.instances)
+            .await
+            .map_err(NodeError::Instance)?;
+        for instance in lost {
+            info!("Instance {} lost with node {}", instance.id, node.id);
+            event_service
+                .record(
+                    &instance.namespace,
+                    InvolvedKind::Instance,
+                    &instance.id,
+                    "Lost",
+                    &format!("Node {} failed", node.id),
+                )
+                .await
+                .map_err(NodeError::Event)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// It returns the state a node moves to after `silence` without heartbeat, if it changes. A
+/// node is failing after `failing_timeout`, then unregistered after `unregister_timeout`.
+fn next_state(
+    state: NodeState,
+    silence: Duration,
+    failing_timeout: Duration,
+    unregister_timeout: Duration,
+) -> Option<NodeState> {
+    match state {
+        NodeState::Registering | NodeState::Registered if silence >= failing_timeout => {
+            Some(NodeState::Failing)
+        }
+        NodeState::Failing if silence >= unregister_timeout => Some(NodeState::Unregistered),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FAILING: Duration = Duration::from_secs(30);
+    const UNREGISTER: Duration = Duration::from_secs(300);
+
+    fn next(state: NodeState, silence: u64) -> Option<NodeState> {
+        next_state(state, Duration::from_secs(silence), FAILING, UNREGISTER)
+    }
+
+    #[test]
+    fn test_silent_node_fails_then_is_unregistered() {
+        assert_eq!(next(NodeState::Registered, 29), None);
+        assert_eq!(next(NodeState::Registered, 30), Some(NodeState::Failing));
+        assert_eq!(next(NodeState::Registered, 400), Some(NodeState::Failing));
+
+        assert_eq!(next(NodeState::Failing, 30), None);
+        assert_eq!(next(NodeState::Failing, 299), None);
+        assert_eq!(next(NodeState::Failing, 300), Some(NodeState::Unregistered));
+    }
+
+    #[test]
+    fn test_registering_node_fails() {
+        assert_eq!(next(NodeState::Registering, 10), None);
+        assert_eq!(next(NodeState::Registering, 30), Some(NodeState::Failing));
+    }
+
+    #[test]
+    fn test_unregistered_nodes_are_left_alone() {
+        assert_eq!(next(NodeState::Unregistered, 1000), None);
+        assert_eq!(next(NodeState::Unregistering, 1000), None);
+    }
+
+    #[test]
+    fn test_nodes_are_unregistered_after_failing() {
+        let address = "127.0.0.1:2379".parse().unwrap();
+        let monitor = NodeMonitor::new(address, 60, FAILING, Duration::from_secs(10));
+        assert_eq!(monitor.unregister_timeout, FAILING);
+    }
+}
@@ -0,0 +1,733 @@
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use log::{error, info, warn};
+use tokio::task::JoinHandle;
+
+use crate::external_api::instance::model::{Instance, InstanceError, InstanceStatus};
+use crate::external_api::instance::service::InstanceService;
+use crate::external_api::workload::model::{
+    RolloutState, RolloutStrategy, Workload, WorkloadError,
+};
+use crate::external_api::workload::service::WorkloadService;
+use crate::leader::Leadership;
+use shutdown::Shutdown;
+
+pub enum ReconcilerError {
+    Workload(WorkloadError),
+    Instance(InstanceError),
+}
+
+impl std::fmt::Display for ReconcilerError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ReconcilerError::Workload(err) => write!(f, "{}", err),
+            ReconcilerError::Instance(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+/// `Reconciler` periodically brings the number of active instances of each workload back to
+/// its number of replicas, by creating or destroying instances through the scheduler. When
+/// the specification of a workload changes, its instances are replaced following its rollout
+/// strategy.
+/// The workloads and their instances are read from etcd, the status of the instances being
+/// kept up to date by the scheduler and the nodes, so a restarted controller picks up where
+/// it stopped.
+/// Properties:
+///
+/// * `etcd_address`: The address of etcd.
+/// * `scheduler_address`: The address of the scheduler.
+/// * `revision_history_limit`: The number of revisions kept for each workload.
+/// * `event_ttl`: Time to live of the events recorded about the instances, in seconds.
+/// * `interval`: Time between two reconciliations.
+pub struct Reconciler {
+    etcd_address: SocketAddr,
+    scheduler_address: SocketAddr,
+    revision_history_limit: usize,
+    event_ttl: i64,
+    interval: Duration,
+}
+
+impl Reconciler {
+    pub fn new(
+        etcd_address: SocketAddr,
+        scheduler_address: SocketAddr,
+        revision_history_limit: usize,
+        event_ttl: i64,
+        interval: Duration,
+    ) -> Self {
+        Reconciler {
+            etcd_address,
+            scheduler_address,
+            revision_history_limit,
+            event_ttl,
+            interval,
+        }
+    }
+
+    /// It spawns a task reconciling the workloads every `interval` while this controller is
+    /// the leader, until `shutdown` is triggered. A reconciliation in progress is always
+    /// completed.
+    pub fn start(self, mut leadership: Leadership, mut shutdown: Shutdown) -> JoinHandle<()> {
+        info!(
+            "Starting reconciliation loop every {} second(s)",
+            self.interval.as_secs()
+        );
+
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(self.interval);
+            loop {
+                if !leadership.is_leader() {
+                    tokio::select! {
+                        // a new leader reconciles right away
+                        _ = leadership.acquired() => {
+                            interval = tokio::time::interval(self.interval);
+                        }
+                        _ = shutdown.wait() => break,
+                    }
+                }
+                tokio::select! {
+                    _ = interval.tick() => {},
+                    _ = shutdown.wait() => break,
+                }
+                // the leadership may have been lost while waiting
+                if !leadership.is_leader() {
+                    continue;
+                }
+                if let Err(err) = self.reconcile().await {
+                    error!("Reconciliation failed : {}", err);
+                }
+            }
+            info!("Reconciliation loop stopped");
+        })
+    }
+
+    /// It reconciles every workload with its instances, and destroys the instances of the
+    /// workloads which have been deleted.
+    pub async fn reconcile(&self) -> Result<(), ReconcilerError> {
+        let mut instance_service =
+            InstanceService::new(&self.scheduler_address, &self.etcd_address, self.event_ttl)
+                .await
+                .map_err(ReconcilerError::Instance)?;
+        let mut workload_service =
+            WorkloadService::new(&self.etcd_address, self.revision_history_limit)
+                .await
+                .map_err(ReconcilerError::Workload)?;
+
+        // instances are listed first, so that an instance can't be created for a workload
+        // missing from the list of workloads. A pass is aborted if either list is incomplete,
+        // so the instances of a workload which couldn't be read aren't taken for orphans.
+        let instances = instance_service
+            .get_instances()
+            .await
+            .map_err(ReconcilerError::Instance)?;
+        let workloads = workload_service
+            .get_workloads()
+            .await
+            .map_err(ReconcilerError::Workload)?;
+        let (owned, orphans) = assign_instances(&workloads, instances);
+
+        for (workload, owned) in workloads.iter().zip(owned) {
+            if let Err(err) = reconcile_workload(
+                &mut instance_service,
+                &mut workload_service,
+                workload,
+                owned,
+            )
+            .await
+            {
+                error!("Failed to reconcile workload {} : {}", workload.id, err);
+            }
+        }
+
+        // the remaining instances belong to deleted workloads
+        for instance in orphans {
+            info!(
+                "Removing instance {} of deleted workload {}",
+                instance.id, instance.name
+            );
+            if let Err(err) = remove_instance(&mut instance_service, &instance).await {
+                error!("Failed to remove instance {} : {}", instance.id, err);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// It forgets the instances of a workload which aren't running anymore, then creates or
+/// destroys instances until the workload has as many active instances of its current revision
+/// as replicas, or of its previous revision while the rollout is paused. The progress of the
+/// rollout of the current revision is stored along the workload.
+async fn reconcile_workload(
+    instance_service: &mut InstanceService,
+    workload_service: &mut WorkloadService,
+    workload: &Workload,
+    instances: Vec<Instance>,
+) -> Result<(), ReconcilerError> {
+    let (active, inactive): (Vec<Instance>, Vec<Instance>) = instances
+        .into_iter()
+        .partition(|instance| instance.status.is_active());
+    let (updated, outdated): (Vec<Instance>, Vec<Instance>) = active
+        .into_iter()
+        .partition(|instance| instance.workload_revision == workload.revision);
+
+    let mut rollout = workload_service
+        .get_rollout(workload)
+        .await
+        .map_err(ReconcilerError::Workload)?;
+    let previous = rollout.clone();
+
+    // an instance of the current revision which failed or keeps crashing while the previous
+    // revisions still run means the new specification doesn't work, so the previous instances
+    // are kept
+    if let Some(failed) = inactive.iter().chain(updated.iter()).find(|instance| {
+        instance.workload_revision == workload.revision
+            && matches!(
+                instance.status,
+                InstanceStatus::Failed | InstanceStatus::CrashLoopBackOff
+            )
+    }) {
+        if !outdated.is_empty() && rollout.state != RolloutState::Paused {
+            warn!(
+                "Pausing rollout of revision {} of workload {} : instance {} failed",
+                workload.revision, workload.id, failed.id
+            );
+            rollout.state = RolloutState::Paused;
+            rollout.message = format!(
+                "Instance {} of revision {} is {:?}",
+                failed.id, workload.revision, failed.status
+            );
+        }
+    }
+
+    for instance in inactive {
+        info!(
+            "Forgetting instance {} of workload {} with status {:?}",
+            instance.id, workload.id, instance.status
+        );
+        instance_service.forget_instance(&instance).await;
+    }
+
+    let ready = updated
+        .iter()
+        .filter(|instance| is_running(instance))
+        .count();
+    rollout.replicas = workload.replicas;
+    rollout.updated = updated.len() as u32;
+    rollout.ready = ready as u32;
+    rollout.outdated = outdated.len() as u32;
+    if outdated.is_empty() {
+        if ready >= workload.replicas as usize {
+            rollout.state = RolloutState::Complete;
+            rollout.message = String::new();
+        } else if rollout.state != RolloutState::Paused {
+            rollout.state = RolloutState::Progressing;
+        }
+    }
+
+    let result = if outdated.is_empty() {
+        scale(instance_service, workload, updated)
+            .await
+            .map_err(ReconcilerError::Instance)
+    } else if rollout.state == RolloutState::Paused {
+        keep_outdated(
+            instance_service,
+            workload_service,
+            workload,
+            &updated,
+            outdated,
+        )
+        .await
+    } else {
+        roll(instance_service, workload, updated, outdated)
+            .await
+            .map_err(ReconcilerError::Instance)
+    };
+
+    if rollout != previous {
+        workload_service
+            .put_rollout(workload, &rollout)
+            .await
+            .map_err(ReconcilerError::Workload)?;
+    }
+    result
+}
+
+/// It sorts the instances by workload, in the order of `workloads`. The instances whose
+/// workload isn't listed, i.e. has been deleted, are returned apart.
+fn assign_instances(
+    workloads: &[Workload],
+    instances: Vec<Instance>,
+) -> (Vec<Vec<Instance>>, Vec<Instance>) {
+    let index: HashMap<(&str, &str), usize> = workloads
+        .iter()
+        .enumerate()
+        .map(|(i, workload)| ((workload.namespace.as_str(), workload.name.as_str()), i))
+        .collect();
+    let mut owned: Vec<Vec<Instance>> = vec![vec![]; workloads.len()];
+    let mut orphans = vec![];
+    for instance in instances {
+        match index.get(&(instance.namespace.as_str(), instance.name.as_str())) {
+            Some(&i) => owned[i].push(instance),
+            None => orphans.push(instance),
+        }
+    }
+    (owned, orphans)
+}
+
+/// The changes bringing the instances of a workload closer to its specification
+#[derive(Default)]
+struct Plan {
+    /// Number of instances of the current revision to create
+    create: usize,
+    destroy: Vec<Instance>,
+}
+
+/// It destroys then creates the instances planned for a workload
+async fn apply(
+    instance_service: &mut InstanceService,
+    workload: &Workload,
+    plan: Plan,
+) -> Result<(), InstanceError> {
+    for instance in &plan.destroy {
+        instance_service
+            .delete_instance(&instance.id, &instance.namespace)
+            .await?;
+    }
+    for _ in 0..plan.create {
+        instance_service.create_instance(workload).await?;
+    }
+    Ok(())
+}
+
+/// It creates or destroys instances of the current revision of a workload until there are as
+/// many as replicas.
+async fn scale(
+    instance_service: &mut InstanceService,
+    workload: &Workload,
+    instances: Vec<Instance>,
+) -> Result<(), InstanceError> {
+    let active = instances.len();
+    let plan = scale_plan(workload.replicas as usize, instances);
+    if plan.create > 0 {
+        info!(
+            "Workload {} has {} active instance(s) out of {}, creating {}",
+            workload.id, active, workload.replicas, plan.create
+        );
+    } else if !plan.destroy.is_empty() {
+        info!(
+            "Workload {} has {} active instance(s) out of {}, destroying {}",
+            workload.id,
+            active,
+            workload.replicas,
+            plan.destroy.len()
+        );
+    }
+    apply(instance_service, workload, plan).await
+}
+
+/// It plans the instances to create or destroy so that there are as many active instances as
+/// `replicas`. The instances which are not running yet are destroyed first.
+fn scale_plan(replicas: usize, mut instances: Vec<Instance>) -> Plan {
+    if instances.len() < replicas {
+        Plan {
+            create: replicas - instances.len(),
+            destroy: vec![],
+        }
+    } else {
+        instances.sort_by_key(is_running);
+        instances.truncate(instances.len() - replicas);
+        Plan {
+            create: 0,
+            destroy: instances,
+        }
+    }
+}
+
+/// It keeps the instances of the previous revision of a workload running while its rollout is
+/// paused. They are scaled so that, along with the instances of the current revision which
+/// run, there are as many running instances as replicas. The instances are created from the
+/// newest previous revision still running, if it is still kept in the history.
+async fn keep_outdated(
+    instance_service: &mut InstanceService,
+    workload_service: &mut WorkloadService,
+    workload: &Workload,
+    updated: &[Instance],
+    outdated: Vec<Instance>,
+) -> Result<(), ReconcilerError> {
+    let revision = outdated
+        .iter()
+        .map(|instance| instance.workload_revision)
+        .max()
+        .unwrap_or_default();
+    let active = outdated.len();
+    let mut plan = paused_plan(workload.replicas as usize, updated, outdated);
+    if plan.create == 0 && plan.destroy.is_empty() {
+        return Ok(());
+    }
+
+    info!(
+        "Rollout of workload {} is paused, scaling revision {} from {} to {} instance(s)",
+        workload.id,
+        revision,
+        active,
+        active + plan.create - plan.destroy.len()
+    );
+    let mut previous = workload.clone();
+    if plan.create > 0 {
+        match workload_service
+            .get_revision(workload, revision)
+            .await
+            .map_err(ReconcilerError::Workload)?
+        {
+            Some(workload) => previous = workload,
+            None => {
+                warn!(
+                    "Revision {} of workload {} is not kept anymore, its instances can't be created",
+                    revision, workload.id
+                );
+                plan.create = 0;
+            }
+        }
+    }
+    apply(instance_service, &previous, plan)
+        .await
+        .map_err(ReconcilerError::Instance)
+}
+
+/// It plans the instances of the previous revisions to create or destroy while a rollout is
+/// paused, so that they make up for the instances of the current revision which don't run.
+fn paused_plan(replicas: usize, updated: &[Instance], outdated: Vec<Instance>) -> Plan {
+    let running = updated
+        .iter()
+        .filter(|instance| is_running(instance))
+        .count();
+    scale_plan(replicas.saturating_sub(running), outdated)
+}
+
+/// It makes one step of the rolling update of a workload.
+async fn roll(
+    instance_service: &mut InstanceService,
+    workload: &Workload,
+    updated: Vec<Instance>,
+    outdated: Vec<Instance>,
+) -> Result<(), InstanceError> {
+    let plan = roll_plan(
+        workload.replicas as usize,
+        &workload.strategy,
+        &updated,
+        outdated,
+    );
+    for instance in &plan.destroy {
+        info!(
+            "Replacing instance {} of workload {} with revision {}",
+            instance.id, workload.id, workload.revision
+        );
+    }
+    apply(instance_service, workload, plan).await
+}
+
+/// It plans one step of a rolling update. Outdated instances are destroyed as long as at least
+/// `replicas - max_unavailable` instances keep running, and instances of the current revision
+/// are created as long as there are at most `replicas + max_surge` instances.
+fn roll_plan(
+    replicas: usize,
+    strategy: &RolloutStrategy,
+    updated: &[Instance],
+    mut outdated: Vec<Instance>,
+) -> Plan {
+    // the rollout couldn't progress without any surge nor unavailability
+    let max_surge = if strategy.max_surge == 0 && strategy.max_unavailable == 0 {
+        1
+    } else {
+        strategy.max_surge as usize
+    };
+    let min_available = replicas.saturating_sub(strategy.max_unavailable as usize);
+
+    let mut available = updated
+        .iter()
+        .filter(|instance| is_running(instance))
+        .count()
+        + outdated
+            .iter()
+            .filter(|instance| is_running(instance))
+            .count();
+    let mut total = updated.len() + outdated.len();
+
+    // the outdated instances which are not running yet don't count as available
+    outdated.sort_by_key(is_running);
+    let mut destroy = vec![];
+    for instance in outdated {
+        if is_running(&instance) {
+            if available <= min_available {
+                break;
+            }
+            available -= 1;
+        }
+        destroy.push(instance);
+        total -= 1;
+    }
+
+    let missing = replicas.saturating_sub(updated.len());
+    let room = (replicas + max_surge).saturating_sub(total);
+    Plan {
+        create: missing.min(room),
+        destroy,
+    }
+}
+
+fn is_running(instance: &Instance) -> bool {
+    instance.status == InstanceStatus::Running
+}
+
+async fn remove_instance(
+    instance_service: &mut InstanceService,
+    instance: &Instance,
+) -> Result<(), InstanceError> {
+    if instance.status.is_active() {
+        instance_service
+            .delete_instance(&instance.id, &instance.namespace)
+            .await
+    } else {
+        instance_service.forget_instance(instance).await;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::external_api::workload::model::WorkloadDTO;
+
+    fn workload(name: &str, replicas: u32) -> Workload {
+        let mut dto: WorkloadDTO =
+            serde_json::from_str(r#"{"name": "", "environment": [], "ports": [], "uri": "nginx"}"#)
+                .unwrap();
+        dto.name = name.to_string();
+        dto.replicas = replicas;
+        dto.into_workload(WorkloadService::id(name, "default"), "default", 1)
+    }
+
+    fn instance(id: &str, workload: &Workload, status: InstanceStatus) -> Instance {
+        let mut instance = Instance::from_workload(id.to_string(), workload);
+        instance.status = status;
+        instance
+    }
+
+    fn ids(instances: &[Instance]) -> Vec<&str> {
+        instances
+            .iter()
+            .map(|instance| instance.id.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn test_scale_up() {
+        let web = workload("web", 3);
+        let instances = vec![instance("web-1", &web, InstanceStatus::Running)];
+
+        let plan = scale_plan(3, instances);
+        assert_eq!(plan.create, 2);
+        assert!(plan.destroy.is_empty());
+    }
+
+    #[test]
+    fn test_scale_down_destroys_instances_not_running_first() {
+        let web = workload("web", 1);
+        let instances = vec![
+            instance("web-1", &web, InstanceStatus::Running),
+            instance("web-2", &web, InstanceStatus::Scheduling),
+            instance("web-3", &web, InstanceStatus::Running),
+        ];
+
+        let plan = scale_plan(1, instances);
+        assert_eq!(plan.create, 0);
+        assert_eq!(ids(&plan.destroy), vec!["web-2", "web-1"]);
+    }
+
+    #[test]
+    fn test_scaled_workload_is_left_alone() {
+        let web = workload("web", 2);
+        let instances = vec![
+            instance("web-1", &web, InstanceStatus::Running),
+            instance("web-2", &web, InstanceStatus::Starting),
+        ];
+
+        let plan = scale_plan(2, instances);
+        assert_eq!(plan.create, 0);
+        assert!(plan.destroy.is_empty());
+
+        let plan = scale_plan(0, vec![]);
+        assert_eq!(plan.create, 0);
+        assert!(plan.destroy.is_empty());
+    }
+
+    #[test]
+    fn test_lost_instances_are_left_to_the_scheduler() {
+        let web = workload("web", 2);
+        let lost = instance("web-2", &web, InstanceStatus::Lost);
+        // the scheduler moves the instance to another node under the same id
+        assert!(lost.status.is_active());
+
+        let instances = vec![instance("web-1", &web, InstanceStatus::Running), lost];
+        let plan = scale_plan(2, instances.clone());
+        assert_eq!(plan.create, 0);
+        assert!(plan.destroy.is_empty());
+
+        // it is destroyed through the scheduler first when scaling down
+        let plan = scale_plan(1, instances);
+        assert_eq!(ids(&plan.destroy), vec!["web-2"]);
+    }
+
+    fn strategy(max_surge: u32, max_unavailable: u32) -> RolloutStrategy {
+        RolloutStrategy {
+            max_surge,
+            max_unavailable,
+        }
+    }
+
+    fn revision(id: &str, workload: &Workload, revision: u64, status: InstanceStatus) -> Instance {
+        let mut instance = instance(id, workload, status);
+        instance.workload_revision = revision;
+        instance
+    }
+
+    #[test]
+    fn test_roll_surges_before_destroying_running_instances() {
+        let web = workload("web", 3);
+        let outdated = vec![
+            revision("web-1", &web, 0, InstanceStatus::Running),
+            revision("web-2", &web, 0, InstanceStatus::Running),
+            revision("web-3", &web, 0, InstanceStatus::Running),
+        ];
+
+        // no instance can be unavailable, so one is created over the replicas first
+        let plan = roll_plan(3, &strategy(1, 0), &[], outdated.clone());
+        assert_eq!(plan.create, 1);
+        assert!(plan.destroy.is_empty());
+
+        // once it runs, an outdated instance can be destroyed, and its replacement created
+        let updated = vec![instance("web-4", &web, InstanceStatus::Running)];
+        let plan = roll_plan(3, &strategy(1, 0), &updated, outdated);
+        assert_eq!(ids(&plan.destroy), vec!["web-1"]);
+        assert_eq!(plan.create, 1);
+    }
+
+    #[test]
+    fn test_roll_waits_for_new_instances_to_run() {
+        let web = workload("web", 3);
+        let updated = vec![instance("web-4", &web, InstanceStatus::Starting)];
+        let outdated = vec![
+            revision("web-1", &web, 0, InstanceStatus::Running),
+            revision("web-2", &web, 0, InstanceStatus::Running),
+            revision("web-3", &web, 0, InstanceStatus::Running),
+        ];
+
+        let plan = roll_plan(3, &strategy(1, 0), &updated, outdated);
+        assert_eq!(plan.create, 0);
+        assert!(plan.destroy.is_empty());
+    }
+
+    #[test]
+    fn test_roll_with_max_unavailable() {
+        let web = workload("web", 4);
+        let outdated = vec![
+            revision("web-1", &web, 0, InstanceStatus::Running),
+            revision("web-2", &web, 0, InstanceStatus::Running),
+            revision("web-3", &web, 0, InstanceStatus::Running),
+            revision("web-4", &web, 0, InstanceStatus::Running),
+        ];
+
+        // two instances are replaced at once, without any surge
+        let plan = roll_plan(4, &strategy(0, 2), &[], outdated);
+        assert_eq!(ids(&plan.destroy), vec!["web-1", "web-2"]);
+        assert_eq!(plan.create, 2);
+    }
+
+    #[test]
+    fn test_roll_destroys_outdated_instances_not_running_first() {
+        let web = workload("web", 2);
+        let outdated = vec![
+            revision("web-1", &web, 0, InstanceStatus::Running),
+            revision("web-2", &web, 0, InstanceStatus::Scheduling),
+        ];
+
+        // an instance which doesn't run isn't available, so it is always replaced
+        let plan = roll_plan(2, &strategy(0, 0), &[], outdated);
+        assert_eq!(ids(&plan.destroy), vec!["web-2"]);
+        // without surge nor unavailability, one instance is surged
+        assert_eq!(plan.create, 2);
+    }
+
+    #[test]
+    fn test_roll_creates_missing_instances_within_surge() {
+        let web = workload("web", 3);
+        let outdated = vec![revision("web-1", &web, 0, InstanceStatus::Running)];
+
+        // the workload was scaled up along with its update
+        let plan = roll_plan(3, &strategy(1, 0), &[], outdated);
+        assert!(plan.destroy.is_empty());
+        assert_eq!(plan.create, 3);
+    }
+
+    #[test]
+    fn test_paused_rollout_keeps_outdated_replicas() {
+        let web = workload("web", 3);
+        let updated = vec![instance("web-4", &web, InstanceStatus::CrashLoopBackOff)];
+        let outdated = vec![
+            revision("web-1", &web, 0, InstanceStatus::Running),
+            revision("web-2", &web, 0, InstanceStatus::Running),
+        ];
+
+        // an outdated instance was lost while the rollout is paused
+        let plan = paused_plan(3, &updated, outdated.clone());
+        assert_eq!(plan.create, 1);
+        assert!(plan.destroy.is_empty());
+
+        // the instances of the current revision which run count as replicas
+        let updated = vec![instance("web-4", &web, InstanceStatus::Running)];
+        let plan = paused_plan(3, &updated, outdated.clone());
+        assert_eq!(plan.create, 0);
+        assert!(plan.destroy.is_empty());
+
+        // the workload was scaled down while the rollout is paused
+        let plan = paused_plan(1, &[], outdated);
+        assert_eq!(plan.create, 0);
+        assert_eq!(plan.destroy.len(), 1);
+    }
+
+    #[test]
+    fn test_instances_of_deleted_workloads_are_orphans() {
+        let web = workload("web", 1);
+        let db = workload("db", 1);
+        let deleted = workload("cache", 1);
+        let mut other_namespace = instance("web-3", &web, InstanceStatus::Running);
+        other_namespace.namespace = "staging".to_string();
+        let instances = vec![
+            instance("db-1", &db, InstanceStatus::Running),
+            instance("cache-1", &deleted, InstanceStatus::Running),
+            instance("web-1", &web, InstanceStatus::Running),
+            other_namespace,
+            instance("web-2", &web, InstanceStatus::Failed),
+        ];
+
+        let (owned, orphans) = assign_instances(&[web, db], instances);
+        assert_eq!(owned.len(), 2);
+        assert_eq!(ids(&owned[0]), vec!["web-1", "web-2"]);
+        assert_eq!(ids(&owned[1]), vec!["db-1"]);
+        assert_eq!(ids(&orphans), vec!["cache-1", "web-3"]);
+    }
+
+    #[test]
+    fn test_workload_without_instances() {
+        let web = workload("web", 1);
+
+        let (owned, orphans) = assign_instances(&[web], vec![]);
+        assert_eq!(owned.len(), 1);
+        assert!(owned[0].is_empty());
+        assert!(orphans.is_empty());
+    }
+}
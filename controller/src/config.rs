@@ -3,6 +3,30 @@ use std::net::{Ipv4Addr, SocketAddr};
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct KudoControllerConfig {
+    /// Time to live of the cluster events, in seconds
+    #[serde(default = "default_event_ttl")]
+    pub event_ttl: i64,
+    /// Time between two reconciliations of the workloads with their instances, in seconds
+    #[serde(default = "default_reconcile_interval")]
+    pub reconcile_interval: u64,
+    /// Time given to the requests in flight to complete on shutdown, in seconds
+    #[serde(default = "default_shutdown_timeout")]
+    pub shutdown_timeout: u64,
+    /// Identity of this controller in the leader election, its hostname and HTTP port if unset
+    #[serde(default)]
+    pub identity: Option<String>,
+    /// Time to live of the lease of the leader, in seconds. Another controller takes over
+    /// within this time when the leader fails.
+    #[serde(default = "default_leader_lease_ttl")]
+    pub leader_lease_ttl: i64,
+    /// Time without heartbeat after which a node is failing, in seconds
+    #[serde(default = "default_node_failing_timeout")]
+    pub node_failing_timeout: u64,
+    /// Time without heartbeat after which a node is unregistered and its instances are lost,
+    /// in seconds
+    #[serde(default = "default_node_unregister_timeout")]
+    pub node_unregister_timeout: u64,
+    // TOML tables must come after the plain values
     pub internal_api: InternalAPIConfig,
     pub external_api: ExternalAPIConfig,
 }
@@ -17,6 +41,58 @@ pub struct ExternalAPIConfig {
     pub http_server_addr: SocketAddr,
     pub http_server_num_workers: usize,
     pub etcd_address: SocketAddr,
+    #[serde(default = "default_scheduler_address")]
+    pub scheduler_address: SocketAddr,
+    /// Number of revisions kept for each workload
+    #[serde(default = "default_revision_history_limit")]
+    pub revision_history_limit: usize,
+}
+
+fn default_scheduler_address() -> SocketAddr {
+    SocketAddr::new(std::net::IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 50052)
+}
+
+fn default_revision_history_limit() -> usize {
+    10
+}
+
+fn default_event_ttl() -> i64 {
+    3600
+}
+
+fn default_reconcile_interval() -> u64 {
+    10
+}
+
+fn default_shutdown_timeout() -> u64 {
+    30
+}
+
+fn default_leader_lease_ttl() -> i64 {
+    15
+}
+
+fn default_node_failing_timeout() -> u64 {
+    15
+}
+
+fn default_node_unregister_timeout() -> u64 {
+    60
+}
+
+impl KudoControllerConfig {
+    /// It returns the identity of this controller in the leader election
+    pub fn identity(&self) -> String {
+        self.identity.clone().unwrap_or_else(|| {
+            let hostname = std::env::var("HOSTNAME")
+                .ok()
+                .or_else(|| std::fs::read_to_string("/etc/hostname").ok())
+                .map(|hostname| hostname.trim().to_string())
+                .filter(|hostname| !hostname.is_empty())
+                .unwrap_or_else(|| "controller".to_string());
+            format!("{}:{}", hostname, self.external_api.http_server_addr.port())
+        })
+    }
 }
 
 impl Default for KudoControllerConfig {
@@ -38,7 +114,16 @@ impl Default for KudoControllerConfig {
                     std::net::IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)),
                     2379,
                 ),
+                scheduler_address: default_scheduler_address(),
+                revision_history_limit: default_revision_history_limit(),
             },
+            event_ttl: default_event_ttl(),
+            reconcile_interval: default_reconcile_interval(),
+            shutdown_timeout: default_shutdown_timeout(),
+            identity: None,
+            leader_lease_ttl: default_leader_lease_ttl(),
+            node_failing_timeout: default_node_failing_timeout(),
+            node_unregister_timeout: default_node_unregister_timeout(),
         }
     }
 }
@@ -1,7 +1,13 @@
 use controller_lib::external_api;
+use controller_lib::external_api::workload::service::WorkloadService;
 use controller_lib::internal_api;
+use controller_lib::leader::LeaderElection;
+use controller_lib::node_monitor::NodeMonitor;
+use controller_lib::reconciler::Reconciler;
+use log::{error, info};
 
 use std::error::Error;
+use std::time::Duration;
 
 mod config;
 
@@ -11,17 +17,117 @@ async fn main() -> Result<(), Box<dyn Error>> {
     env_logger::init();
 
     let config: config::KudoControllerConfig = confy::load_path("controller.conf")?;
+    let shutdown_timeout = Duration::from_secs(config.shutdown_timeout);
+    let (shutdown_trigger, shutdown) = shutdown::channel();
+
+    // The workloads stored by the previous versions are moved to their current key
+    let moved = WorkloadService::new(
+        &config.external_api.etcd_address,
+        config.external_api.revision_history_limit,
+    )
+    .await
+    .map_err(|err| err.to_string())?
+    .migrate_legacy_keys()
+    .await
+    .map_err(|err| err.to_string())?;
+    if moved > 0 {
+        info!("{} workload(s) moved to their current key", moved);
+    }
 
     // gRPC Server
-    internal_api::interface::InternalAPIInterface::new(config.internal_api.grpc_server_addr).await;
+    let internal_api = internal_api::interface::InternalAPIInterface::new(
+        config.internal_api.grpc_server_addr,
+        config.external_api.etcd_address,
+        config.event_ttl,
+        shutdown.clone(),
+    )
+    .await;
+
+    // Leader election, the background loops only run on the leader. It is stopped after them,
+    // so that another controller can't lead while they still run
+    let (election_trigger, election_shutdown) = shutdown::channel();
+    let (leadership, election) = LeaderElection::new(
+        config.external_api.etcd_address,
+        config.identity(),
+        config.leader_lease_ttl,
+    )
+    .start(election_shutdown);
+
+    // Reconciliation loop
+    let reconciler = Reconciler::new(
+        config.external_api.etcd_address,
+        config.external_api.scheduler_address,
+        config.external_api.revision_history_limit,
+        config.event_ttl,
+        Duration::from_secs(config.reconcile_interval),
+    )
+    .start(leadership.clone(), shutdown.clone());
+
+    // Node failure detection
+    let node_monitor = NodeMonitor::new(
+        config.external_api.etcd_address,
+        config.event_ttl,
+        Duration::from_secs(config.node_failing_timeout),
+        Duration::from_secs(config.node_unregister_timeout),
+    )
+    .start(leadership.clone(), shutdown.clone());
 
     // HTTP Server
-    external_api::interface::ExternalAPIInterface::new(
+    let external_api = external_api::interface::ExternalAPIInterface::new(
         config.external_api.http_server_addr,
         config.external_api.http_server_num_workers,
         config.external_api.etcd_address,
+        config.external_api.scheduler_address,
+        config.external_api.revision_history_limit,
+        config.event_ttl,
+        shutdown_timeout,
+        leadership,
+        shutdown,
     )
+    .await?;
+
+    shutdown::signal().await;
+    info!(
+        "Shutting down, waiting up to {} second(s) for the requests in flight",
+        shutdown_timeout.as_secs()
+    );
+    shutdown_trigger.trigger();
+
+    let drained = tokio::time::timeout(shutdown_timeout, async {
+        let (internal_api, external_api, reconciler, node_monitor) = tokio::join!(
+            internal_api.wait(),
+            external_api.wait(),
+            reconciler,
+            node_monitor
+        );
+        internal_api?;
+        external_api?;
+        reconciler?;
+        node_monitor?;
+        election_trigger.trigger();
+        election.await?;
+        Ok::<(), Box<dyn Error>>(())
+    })
     .await;
 
-    Ok(())
+    let result = match drained {
+        Ok(Ok(())) => {
+            info!("Shutdown complete");
+            Ok(())
+        }
+        Ok(Err(err)) => {
+            error!("Shutdown failed : {}", err);
+            Err(err)
+        }
+        Err(_) => {
+            error!(
+                "Requests still in flight after {} second(s), exiting",
+                shutdown_timeout.as_secs()
+            );
+            Err("shutdown timed out".into())
+        }
+    };
+
+    log::logger().flush();
+    result
 }
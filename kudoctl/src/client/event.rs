@@ -0,0 +1,57 @@
+use anyhow::{Context, Result};
+use log::debug;
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+
+use super::request::Client;
+
+/// Something which happened to an instance or a node of the cluster.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Event {
+    /// Seconds since the UNIX epoch
+    pub timestamp: i64,
+    pub namespace: String,
+    pub involved_kind: String,
+    pub involved: String,
+    pub reason: String,
+    pub message: String,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct GetEventsResponse {
+    pub count: u64,
+    pub events: Vec<Event>,
+    #[serde(skip)]
+    pub show_header: bool,
+}
+
+/// Get the events of the cluster, optionally only the ones of a namespace
+/// or about a given instance or node.
+pub async fn list(
+    client: &Client,
+    namespace: Option<&str>,
+    involved: Option<&str>,
+) -> Result<GetEventsResponse> {
+    let mut query = url::form_urlencoded::Serializer::new(String::new());
+    if let Some(namespace) = namespace {
+        query.append_pair("namespace", namespace);
+    }
+    if let Some(involved) = involved {
+        query.append_pair("involved", involved);
+    }
+
+    let response = (*client)
+        .send_json_request::<GetEventsResponse, ()>(
+            &format!("/events?{}", query.finish()),
+            Method::GET,
+            None,
+        )
+        .await
+        .context("Error getting events")?;
+    debug!(
+        "{} total events, {} events received ",
+        response.count,
+        response.events.len()
+    );
+    Ok(response)
+}
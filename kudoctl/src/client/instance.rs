@@ -3,43 +3,38 @@ use log::debug;
 use reqwest::Method;
 use serde::{Deserialize, Serialize};
 
-use crate::{client::types::IdResponse, resource::workload};
+use crate::{client::event::Event, resource::workload};
 
 use super::request::Client;
 
-#[derive(Debug, Serialize)]
-struct CreateRequestBody {
-    pub workload_name: String,
-}
-
-/// Starts an instance on the cluster.
-///
-/// Returns the id of the instance.
-pub async fn create(client: &Client, namespace: &str, workload_id: &str) -> anyhow::Result<String> {
-    let response: IdResponse = (*client)
-        .send_json_request(
-            &format!("/instance/{}", namespace),
-            Method::PUT,
-            Some(&CreateRequestBody {
-                workload_name: workload_id.to_owned(),
-            }),
-        )
-        .await
-        .context("Error creating instance")?;
-    debug!("Instance {} created", response.id);
-    Ok(response.id)
-}
-
 #[derive(Debug, Deserialize, Serialize)]
 pub struct Instance {
     pub id: String,
     pub name: String,
     pub r#type: String,
     pub uri: String,
-    pub ports: Vec<String>,
+    pub ports: Vec<Port>,
+    #[serde(rename = "environment")]
     pub env: Vec<String>,
     pub resources: workload::Resources,
     pub status: String,
+    /// number of times the node restarted the instance
+    #[serde(default)]
+    pub restart_count: u32,
+    /// the instance is running and its readiness probe succeeds
+    #[serde(default)]
+    pub ready: bool,
+
+    /// events about the instance, not sent by the controller with the instance
+    #[serde(default)]
+    pub events: Vec<Event>,
+}
+
+/// a port of the node forwarded to a port of the instance
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Port {
+    pub source: i32,
+    pub destination: i32,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -84,16 +79,45 @@ pub async fn get(client: &Client, namespace: &str, instance_id: &str) -> anyhow:
     Ok(response)
 }
 
-/// Delete an instance with the given id.
-pub async fn delete(client: &Client, namespace: &str, instance_id: &str) -> anyhow::Result<()> {
-    (*client)
-        .send_json_request::<(), ()>(
-            &format!("/instance/{}/{}", namespace, instance_id),
-            Method::DELETE,
-            None,
-        )
-        .await
-        .context("Error deleting instance")?;
-    debug!("Instance {} deleted", instance_id);
-    Ok(())
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_instance_from_controller() {
+        // an instance as served by the controller on `/instance/{namespace}/{id}`
+        let json = r#"{
+            "id": "9f86d081884c7d65",
+            "name": "web",
+            "namespace": "default",
+            "type": "Container",
+            "uri": "nginx",
+            "environment": ["A=1"],
+            "resources": {"cpu": 500, "memory": 256, "disk": 10, "requests": null},
+            "ports": [{"source": 8080, "destination": 80}],
+            "status": "Running",
+            "ip": "10.0.1.2",
+            "workload_revision": 3,
+            "restart_policy": "always",
+            "restart_count": 1,
+            "liveness_probe": null,
+            "readiness_probe": null,
+            "ready": true,
+            "node_selector": {},
+            "affinity": null,
+            "tolerations": [],
+            "priority": 0
+        }"#;
+
+        let instance: Instance = serde_json::from_str(json).unwrap();
+        assert_eq!(instance.env, vec!["A=1"]);
+        assert_eq!(instance.ports[0].source, 8080);
+        assert_eq!(instance.ports[0].destination, 80);
+        assert_eq!(instance.restart_count, 1);
+        assert!(instance.events.is_empty());
+
+        let list: GetInstancesResponse =
+            serde_json::from_str(&format!(r#"{{"count": 1, "instances": [{}]}}"#, json)).unwrap();
+        assert_eq!(list.instances[0].id, "9f86d081884c7d65");
+    }
 }
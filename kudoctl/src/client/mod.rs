@@ -1,3 +1,4 @@
+pub mod event;
 pub mod instance;
 pub mod namespace;
 pub mod node;
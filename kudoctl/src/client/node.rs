@@ -46,3 +46,71 @@ pub async fn get(client: &Client, node_id: &str) -> Result<Node> {
     debug!("Node {} received", response.id);
     Ok(response)
 }
+
+// Body of the cordon route, telling whether new instances are kept off a node.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct NodeCordon {
+    pub id: String,
+    pub cordoned: bool,
+}
+
+/// Stop placing new instances on a node, or place them there again.
+pub async fn cordon(client: &Client, node_id: &str, cordoned: bool) -> Result<NodeCordon> {
+    let method = match cordoned {
+        true => Method::PUT,
+        false => Method::DELETE,
+    };
+    let response = (*client)
+        .send_json_request::<NodeCordon, ()>(&format!("/node/{}/cordon", node_id), method, None)
+        .await
+        .context("Error cordoning node")?;
+    debug!("Node {} cordoned: {}", response.id, response.cordoned);
+    Ok(response)
+}
+
+// Body of the drain request.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct DrainRequestBody {
+    pub max_unavailable: Option<u32>,
+}
+
+/// Progress of the drain of a node.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct NodeDrain {
+    pub id: String,
+    /// NotDrained, Draining or Drained
+    pub state: String,
+    pub remaining: u32,
+    pub moving: u32,
+    pub moved: u32,
+    pub evicted: u32,
+    pub message: String,
+}
+
+/// Cordon a node and start moving its instances to the other nodes.
+pub async fn drain(
+    client: &Client,
+    node_id: &str,
+    max_unavailable: Option<u32>,
+) -> Result<NodeDrain> {
+    let response: NodeDrain = (*client)
+        .send_json_request(
+            &format!("/node/{}/drain", node_id),
+            Method::POST,
+            Some(&DrainRequestBody { max_unavailable }),
+        )
+        .await
+        .context("Error draining node")?;
+    debug!("Drain of node {} is {}", response.id, response.state);
+    Ok(response)
+}
+
+/// Get the progress of the drain of a node.
+pub async fn drain_status(client: &Client, node_id: &str) -> Result<NodeDrain> {
+    let response = (*client)
+        .send_json_request::<NodeDrain, ()>(&format!("/node/{}/drain", node_id), Method::GET, None)
+        .await
+        .context("Error getting node drain")?;
+    debug!("Drain of node {} is {}", response.id, response.state);
+    Ok(response)
+}
@@ -64,6 +64,11 @@ impl Client {
             header::HeaderValue::from_static("application/json"),
         );
 
+        // Identify the user in the workload revisions history.
+        if let Ok(author) = header::HeaderValue::from_str(&current_user()) {
+            headers.insert("X-Kudo-Author", author);
+        }
+
         Ok(Client {
             client: reqwest::Client::builder()
                 .default_headers(headers)
@@ -83,7 +88,7 @@ impl Client {
             .base_url
             .join(endpoint)
             .map_err(RequestError::ParseError)?;
-        let mut request = (*self).client.request(method, url);
+        let mut request = self.client.request(method, url);
 
         if let Some(body) = body {
             request = request.json(body);
@@ -123,3 +128,10 @@ impl Client {
             .map_err(RequestError::ReqwestError)
     }
 }
+
+// Returns the name of the user running the CLI.
+fn current_user() -> String {
+    std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .unwrap_or_else(|_| "unknown".to_string())
+}
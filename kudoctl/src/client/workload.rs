@@ -108,3 +108,173 @@ pub async fn delete(client: &Client, id: &str) -> Result<()> {
     debug!("Workload {} deleted", response.id);
     Ok(())
 }
+
+/// A recorded revision of a workload.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Revision {
+    pub revision: u64,
+    /// Seconds since the UNIX epoch
+    pub timestamp: i64,
+    pub author: String,
+    pub workload: RevisionWorkload,
+}
+
+/// Specification of the workload at a given revision.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct RevisionWorkload {
+    pub uri: String,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct GetRevisionsResponse {
+    pub revisions: Vec<Revision>,
+    #[serde(skip)]
+    pub show_header: bool,
+}
+
+/// Get the revisions kept for a workload, from the oldest to the newest.
+pub async fn revisions(
+    client: &Client,
+    namespace: &str,
+    workload_name: &str,
+) -> Result<GetRevisionsResponse> {
+    let response: GetRevisionsResponse = (*client)
+        .send_json_request::<GetRevisionsResponse, ()>(
+            &format!("/workload/{}/{}/revisions", namespace, workload_name),
+            Method::GET,
+            None,
+        )
+        .await
+        .context("Error getting workload revisions")?;
+    debug!(
+        "{} revisions received for workload {}",
+        response.revisions.len(),
+        workload_name
+    );
+    Ok(response)
+}
+
+#[derive(Debug, Serialize)]
+struct RollbackRequestBody {
+    pub revision: Option<u64>,
+}
+
+/// Restore a previous revision of a workload, the one before the current revision if
+/// `revision` is not set.
+///
+/// Returns the id of the workload.
+pub async fn rollback(
+    client: &Client,
+    namespace: &str,
+    workload_name: &str,
+    revision: Option<u64>,
+) -> Result<String> {
+    let response: IdResponse = (*client)
+        .send_json_request(
+            &format!("/workload/{}/{}/rollback", namespace, workload_name),
+            Method::POST,
+            Some(&RollbackRequestBody { revision }),
+        )
+        .await
+        .context("Error rolling back workload")?;
+    debug!("Workload {} rolled back", response.id);
+    Ok(response.id)
+}
+
+#[derive(Debug, Serialize)]
+struct ScaleRequestBody {
+    pub replicas: u32,
+}
+
+/// Change the number of instances to keep running for a workload.
+///
+/// Returns the id of the workload.
+pub async fn scale(
+    client: &Client,
+    namespace: &str,
+    workload_name: &str,
+    replicas: u32,
+) -> Result<String> {
+    let response: IdResponse = (*client)
+        .send_json_request(
+            &format!("/workload/{}/{}/scale", namespace, workload_name),
+            Method::POST,
+            Some(&ScaleRequestBody { replicas }),
+        )
+        .await
+        .context("Error scaling workload")?;
+    debug!("Workload {} scaled to {} replicas", response.id, replicas);
+    Ok(response.id)
+}
+
+/// Progress of the rollout of the current revision of a workload.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Rollout {
+    pub revision: u64,
+    /// Progressing, Paused or Complete
+    pub state: String,
+    pub replicas: u32,
+    pub updated: u32,
+    pub ready: u32,
+    pub outdated: u32,
+    pub message: String,
+}
+
+/// Get the progress of the rollout of the current revision of a workload.
+pub async fn rollout(client: &Client, namespace: &str, workload_name: &str) -> Result<Rollout> {
+    let response: Rollout = (*client)
+        .send_json_request::<Rollout, ()>(
+            &format!("/workload/{}/{}/rollout", namespace, workload_name),
+            Method::GET,
+            None,
+        )
+        .await
+        .context("Error getting workload rollout")?;
+    debug!(
+        "Rollout of revision {} of workload {} is {}",
+        response.revision, workload_name, response.state
+    );
+    Ok(response)
+}
+
+/// Where an instance of a workload would be placed.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct DryRunPlacement {
+    pub instance: String,
+    /// None if no node can run the instance
+    pub node: Option<String>,
+    pub reason: String,
+    /// Instances of a lower priority evicted to make room for the instance
+    pub preempted: Vec<String>,
+}
+
+/// Outcome of the simulation of the placement of the instances of a workload.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct DryRun {
+    pub fits: bool,
+    pub placements: Vec<DryRunPlacement>,
+}
+
+/// Ask the scheduler where the instances of a workload would be placed, without creating or
+/// updating the workload.
+pub async fn dry_run(
+    client: &Client,
+    namespace: &str,
+    workload: &workload::Workload,
+) -> Result<DryRun> {
+    let response: DryRun = (*client)
+        .send_json_request(
+            &format!("/workload/{}/{}/dry-run", namespace, workload.name),
+            Method::POST,
+            Some(workload),
+        )
+        .await
+        .context("Error running workload dry run")?;
+    debug!(
+        "Dry run of workload {}: {} placements, fits: {}",
+        workload.name,
+        response.placements.len(),
+        response.fits
+    );
+    Ok(response)
+}
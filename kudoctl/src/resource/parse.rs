@@ -35,6 +35,31 @@ resources:
         }
     }
 
+    #[test]
+    fn test_parse_yaml_resource_with_requests() {
+        let content = r#"
+kind: workload
+name: my-workload
+uri: my-workload-uri
+resources:
+  cpu: 1000
+  memory: 512
+  disk: 3
+  requests:
+    cpu: 250
+"#;
+        let resource = parse_yaml_resource(content).unwrap();
+
+        match resource {
+            Resource::Workload(workload) => {
+                let requests = workload.resources.requests.unwrap();
+                assert_eq!(requests.cpu, Some(250));
+                assert_eq!(requests.memory, None);
+                assert_eq!(requests.disk, None);
+            }
+        }
+    }
+
     #[test]
     fn test_parse_yaml_resource_with_ports() {
         let content = r#"
@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 use serde::{Deserialize, Serialize};
 
 // Workload definition (serialized to YAML)
@@ -10,9 +12,145 @@ pub struct Workload {
     pub ports: Option<Vec<String>>,
     /// environment variables to set on the workload
     pub env: Option<Vec<String>>,
+    /// number of instances to keep running, 1 if not set
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub replicas: Option<u32>,
+    /// when the node restarts an exited instance: always (default), on-failure or never
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub restart_policy: Option<String>,
+    /// how instances are replaced when the workload changes
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub strategy: Option<Strategy>,
+    /// the node restarts an instance when this probe fails
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub liveness_probe: Option<Probe>,
+    /// the node stops routing traffic to an instance while this probe fails
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub readiness_probe: Option<Probe>,
+    /// labels the nodes of the instances must have
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub node_selector: Option<HashMap<String, String>>,
+    /// constraints on the nodes the instances are placed on
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub affinity: Option<Affinity>,
+    /// taints of the nodes the instances can be placed on anyway
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tolerations: Option<Vec<Toleration>>,
+    /// instances of a lower priority are preempted to make room for the instances, 0 if not set
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub priority: Option<i32>,
+}
+
+// Node affinity and anti-affinity of the instances of a workload
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Affinity {
+    // the node must match one of these terms
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub required: Option<Vec<NodeSelectorTerm>>,
+    // the nodes matching these terms are preferred
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub preferred: Option<Vec<PreferredTerm>>,
+    // spreads the instances of the workload over the nodes
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub anti_affinity: Option<AntiAffinity>,
+}
+
+// Requirements on the labels of a node, all of them must match
+#[derive(Serialize, Deserialize, Debug)]
+pub struct NodeSelectorTerm {
+    pub requirements: Vec<NodeSelectorRequirement>,
+}
+
+// Operator is one of in, not-in, exists and does-not-exist, values are only set with in and not-in
+#[derive(Serialize, Deserialize, Debug)]
+pub struct NodeSelectorRequirement {
+    pub key: String,
+    pub operator: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub values: Option<Vec<String>>,
+}
+
+// A term whose nodes are preferred, by a weight between 1 and 100
+#[derive(Serialize, Deserialize, Debug)]
+pub struct PreferredTerm {
+    pub weight: i32,
+    pub term: NodeSelectorTerm,
+}
+
+// Instances of the same workload are never placed on the same node if required, otherwise
+// such nodes are avoided by a weight between 1 and 100
+#[derive(Serialize, Deserialize, Debug)]
+pub struct AntiAffinity {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub required: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub weight: Option<i32>,
+}
+
+// Allows the instances on the nodes with a matching taint, any value and effect (no-schedule or
+// prefer-no-schedule) if not set
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Toleration {
+    pub key: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub effect: Option<String>,
 }
 
-// Resources assigned to a workload
+// Health check of the instances, exactly one of http_get, tcp_socket and exec must be set.
+// The node defaults are used for unset values
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Probe {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub http_get: Option<HttpGet>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tcp_socket: Option<TcpSocket>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exec: Option<Exec>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub initial_delay_seconds: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub period_seconds: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timeout_seconds: Option<u32>,
+    // consecutive failures before the probe is considered failed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub failure_threshold: Option<u32>,
+}
+
+// GET request on a port of the instance, successful if the status is 2xx or 3xx
+#[derive(Serialize, Deserialize, Debug)]
+pub struct HttpGet {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    pub port: i32,
+}
+
+// TCP connection on a port of the instance
+#[derive(Serialize, Deserialize, Debug)]
+pub struct TcpSocket {
+    pub port: i32,
+}
+
+// Command run inside the instance, successful if it exits with 0
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Exec {
+    pub command: Vec<String>,
+}
+
+// Rollout strategy of a workload, the controller defaults are used for unset values
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Strategy {
+    // number of replicas which can be unavailable during a rollout
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_unavailable: Option<u32>,
+    // number of instances which can be created over the replicas during a rollout
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_surge: Option<u32>,
+}
+
+// Resources assigned to a workload, the limits of each instance
 #[derive(Serialize, Deserialize, Debug)]
 pub struct Resources {
     // CPU in milliCPU
@@ -21,4 +159,19 @@ pub struct Resources {
     pub memory: u64,
     // Storage in GB
     pub disk: u64,
+    /// resources reserved for each instance on its node, the limits above if not set
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub requests: Option<ResourceRequests>,
+}
+
+// Resources the instances are placed by, while the ones of Resources are the limits enforced
+// by the node. The limit is used for each resource which isn't set
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ResourceRequests {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cpu: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub memory: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub disk: Option<u64>,
 }
@@ -6,10 +6,10 @@ use crate::{
         request::{Client, RequestError},
     },
     config,
-    resource::Resource,
+    resource::{workload::Workload, Resource},
 };
 use anyhow::{bail, Context, Result};
-use clap::Args;
+use clap::{Args, ValueEnum};
 use log::{debug, info};
 
 #[derive(Debug, Args)]
@@ -21,6 +21,20 @@ pub struct Apply {
     /// If the resource already exists, don’t update it   
     #[clap(long)]
     no_update: bool,
+
+    /// Only check the resource, without applying it: `client` parses the file, `server` also
+    /// asks the scheduler where the instances would be placed
+    #[clap(long, arg_enum, value_parser)]
+    dry_run: Option<DryRun>,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum, Debug)]
+enum DryRun {
+    /// parse the resource file
+    Client,
+
+    /// simulate the placement of the instances
+    Server,
 }
 
 pub async fn execute(args: Apply, conf: &config::Config) -> Result<String> {
@@ -52,34 +66,35 @@ pub async fn execute(args: Apply, conf: &config::Config) -> Result<String> {
     let resource_data: Resource =
         serde_yaml::from_str(&yaml).context("Error parsing file resource")?;
 
-    let mut needs_instance_create = true;
+    match (args.dry_run, &resource_data) {
+        (Some(DryRun::Client), Resource::Workload(workload)) => {
+            info!("Workload {} is valid (dry run)", workload.name);
+            return Ok("".to_string());
+        }
+        (Some(DryRun::Server), Resource::Workload(workload)) => {
+            return dry_run(&client, &conf.namespace, workload).await;
+        }
+        (None, _) => {}
+    }
 
     match resource_data {
         Resource::Workload(ref workload) => {
             debug!("Pushing workload {}", workload.name);
 
-            let workload_id = match client::workload::create(&client, &conf.namespace, workload)
-                .await
-            {
-                Ok(id) => {
-                    info!("Workload {} created", id);
-                    id
-                }
+            // the instances of the workload are then created by the controller
+            match client::workload::create(&client, &conf.namespace, workload).await {
+                Ok(id) => info!("Workload {} created", id),
 
                 Err(e) => match e {
                     RequestError::ErrStatusCode(ref status) => {
                         if status.status == 409 {
                             info!("Workload {} already exists", workload.name);
-                            needs_instance_create = false;
 
                             let res = client::workload::update(&client, &conf.namespace, workload)
                                 .await
                                 .context("Error updating workload");
                             match res {
-                                Ok(id) => {
-                                    info!("Workload {} updated", id);
-                                    id
-                                }
+                                Ok(id) => info!("Workload {} updated", id),
                                 Err(e) => {
                                     bail!("Error updating workload: {}", e);
                                 }
@@ -91,18 +106,41 @@ pub async fn execute(args: Apply, conf: &config::Config) -> Result<String> {
                     _ => bail!("Error creating workload {}: {}", workload.name, e),
                 },
             };
+        }
+    }
 
-            if needs_instance_create {
-                let instance_id =
-                    client::instance::create(&client, &conf.namespace, &workload.name).await?;
+    Ok("".to_string())
+}
+
+/// It prints where the instances of a workload would be placed, and fails if one of them can't
+/// be placed
+async fn dry_run(client: &Client, namespace: &str, workload: &Workload) -> Result<String> {
+    let dry_run = client::workload::dry_run(client, namespace, workload).await?;
 
+    for placement in &dry_run.placements {
+        match &placement.node {
+            Some(node) if placement.preempted.is_empty() => {
                 info!(
-                    "Workload {} created with id {} and started with instance {}",
-                    workload.name, workload_id, instance_id
-                );
+                    "Instance {} would be placed on node {}",
+                    placement.instance, node
+                )
             }
+            Some(node) => info!(
+                "Instance {} would be placed on node {}, preempting {}",
+                placement.instance,
+                node,
+                placement.preempted.join(", ")
+            ),
+            None => info!(
+                "Instance {} can't be placed: {}",
+                placement.instance, placement.reason
+            ),
         }
     }
 
+    if !dry_run.fits {
+        bail!("Workload {} doesn't fit in the cluster", workload.name);
+    }
+    info!("Workload {} fits in the cluster (dry run)", workload.name);
     Ok("".to_string())
 }
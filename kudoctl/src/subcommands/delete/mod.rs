@@ -1,7 +1,6 @@
 use crate::config;
 use anyhow::Result;
 use clap::{Args, ValueEnum};
-mod instance;
 mod namespace;
 mod resource;
 
@@ -22,9 +21,6 @@ enum Subjects {
     /// resources
     Resource,
 
-    /// instances
-    Instance,
-
     /// namespaces
     Namespace,
 }
@@ -33,7 +29,6 @@ enum Subjects {
 pub async fn execute(args: Subcommand, conf: &config::Config) -> Result<String> {
     match args.subject {
         Subjects::Resource => resource::execute(conf, args.id.as_str()).await,
-        Subjects::Instance => instance::execute(conf, args.id.as_str()).await,
         Subjects::Namespace => namespace::execute(conf, args.id.as_str()).await,
     }?;
 
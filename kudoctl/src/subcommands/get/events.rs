@@ -0,0 +1,59 @@
+use super::output::{self, format_timestamp, OutputFormat};
+use crate::{
+    client::{
+        self,
+        event::{Event, GetEventsResponse},
+        request::Client,
+    },
+    config,
+};
+use anyhow::{Context, Result};
+use std::fmt::Display;
+
+/// get events [id] subcommand execution
+/// Does the request, then formats the output.
+pub async fn execute(
+    conf: &config::Config,
+    format: OutputFormat,
+    show_header: bool,
+    involved: Option<String>,
+) -> Result<String> {
+    let client = Client::new(conf).context("Error creating client")?;
+    let mut result =
+        client::event::list(&client, Some(&conf.namespace), involved.as_deref()).await?;
+    result.show_header = show_header;
+    output::format_output(result, format)
+}
+
+impl Display for GetEventsResponse {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.show_header {
+            writeln!(f, "TIME\tKIND\tINVOLVED\tREASON\tMESSAGE")?;
+        }
+
+        for event in &self.events {
+            writeln!(
+                f,
+                "{}\t{}\t{}\t{}\t{}",
+                format_timestamp(event.timestamp),
+                event.involved_kind,
+                event.involved,
+                event.reason,
+                event.message
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for Event {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} {} {}",
+            format_timestamp(self.timestamp),
+            self.reason,
+            self.message
+        )
+    }
+}
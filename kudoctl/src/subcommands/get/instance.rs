@@ -19,7 +19,10 @@ pub async fn execute(
     let search = search.unwrap();
 
     let client = Client::new(conf).context("Error creating client")?;
-    let result = client::instance::get(&client, &conf.namespace, search.as_str()).await?;
+    let mut result = client::instance::get(&client, &conf.namespace, search.as_str()).await?;
+    result.events = client::event::list(&client, Some(&conf.namespace), Some(&result.id))
+        .await?
+        .events;
 
     output::format_output(result, format)
 }
@@ -29,6 +32,8 @@ impl Display for Instance {
         writeln!(f, "id : {}\n", self.id)?;
         writeln!(f, "workload name : {}\n", self.name)?;
         writeln!(f, "status : {}\n", self.status)?;
+        writeln!(f, "ready : {}\n", self.ready)?;
+        writeln!(f, "restarts : {}\n", self.restart_count)?;
         writeln!(f, "type : {}\n", self.r#type)?;
         writeln!(f, "uri : {}\n", self.uri)?;
 
@@ -36,10 +41,9 @@ impl Display for Instance {
         let ports_str = self
             .ports
             .iter()
-            .fold(String::new(), |acc, port| acc + &format!("{} ", port))
-            .trim()
-            .replace(' ', ",")
-            .replace(':', "->");
+            .map(|port| format!("{}->{}", port.source, port.destination))
+            .collect::<Vec<_>>()
+            .join(",");
         writeln!(f, "ports : {} ", ports_str)?;
 
         // display environment variables
@@ -58,6 +62,12 @@ impl Display for Instance {
             "resources : {}milliCPU, {}mB memory, {}GB disk ",
             self.resources.cpu, self.resources.memory, self.resources.disk
         )?;
+
+        // display events
+        writeln!(f, "\nevents :")?;
+        for event in &self.events {
+            writeln!(f, "  {}", event)?;
+        }
         Ok(())
     }
 }
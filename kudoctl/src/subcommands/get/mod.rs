@@ -1,9 +1,10 @@
+mod events;
 mod instance;
 mod instances;
 mod namespaces;
 mod node;
 mod nodes;
-mod output;
+pub mod output;
 mod resource;
 mod resources;
 use self::output::OutputFormat;
@@ -50,6 +51,9 @@ enum GetSubjects {
 
     /// namespaces
     Namespaces,
+
+    /// events, of an instance or a node if an ID is given
+    Events,
 }
 
 /// match the subcommand to get the correct info
@@ -65,5 +69,6 @@ pub async fn execute(args: GetSubcommand, conf: &config::Config) -> Result<Strin
         GetSubjects::Nodes => nodes::execute(conf, format, show_header).await,
         GetSubjects::Node => node::execute(conf, format, args.id).await,
         GetSubjects::Namespaces => namespaces::execute(conf, format, show_header).await,
+        GetSubjects::Events => events::execute(conf, format, show_header, args.id).await,
     }
 }
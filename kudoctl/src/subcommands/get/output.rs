@@ -1,6 +1,7 @@
 use std::fmt::Display;
 
 use anyhow::{Context, Result};
+use chrono::{TimeZone, Utc};
 use clap::ValueEnum;
 use serde::Serialize;
 
@@ -23,3 +24,11 @@ pub fn format_output<T: Serialize + Display>(output: T, format: OutputFormat) ->
     }
     .context("Error formatting output")
 }
+
+/// Formats seconds since the UNIX epoch as an UTC date.
+pub fn format_timestamp(timestamp: i64) -> String {
+    Utc.timestamp_opt(timestamp, 0)
+        .single()
+        .map(|date| date.format("%F %T").to_string())
+        .unwrap_or_default()
+}
@@ -30,6 +30,10 @@ impl Display for Workload {
         writeln!(f, "name : {}\n", self.name)?;
         writeln!(f, "uri : {}\n", self.uri)?;
 
+        if let Some(replicas) = self.replicas {
+            writeln!(f, "replicas : {}\n", replicas)?;
+        }
+
         if let Some(ports) = &self.ports {
             // display ports
             let ports_str = ports
@@ -58,6 +62,16 @@ impl Display for Workload {
             "resources : {}milliCPU, {}mB memory, {}GB disk ",
             self.resources.cpu, self.resources.memory, self.resources.disk
         )?;
+        if let Some(requests) = &self.resources.requests {
+            let resources = &self.resources;
+            writeln!(
+                f,
+                "requests : {}milliCPU, {}mB memory, {}GB disk ",
+                requests.cpu.unwrap_or(resources.cpu),
+                requests.memory.unwrap_or(resources.memory),
+                requests.disk.unwrap_or(resources.disk)
+            )?;
+        }
         Ok(())
     }
 }
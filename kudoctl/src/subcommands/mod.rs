@@ -4,12 +4,18 @@ use log::error;
 mod apply;
 mod delete;
 mod get;
+mod node;
+mod rollout;
+mod scale;
 
 #[derive(Subcommand)]
 pub enum Subcommands {
     Apply(apply::Apply),
     Get(get::GetSubcommand),
     Delete(delete::Subcommand),
+    Node(node::Subcommand),
+    Rollout(rollout::Subcommand),
+    Scale(scale::Scale),
 }
 
 /// Match the subcommand to execute
@@ -22,6 +28,9 @@ pub async fn match_subcommand(command: Subcommands, conf: &config::Config) {
         Subcommands::Apply(args) => apply::execute(args, conf).await,
         Subcommands::Get(args) => get::execute(args, conf).await,
         Subcommands::Delete(args) => delete::execute(args, conf).await,
+        Subcommands::Node(args) => node::execute(args, conf).await,
+        Subcommands::Rollout(args) => rollout::execute(args, conf).await,
+        Subcommands::Scale(args) => scale::execute(args, conf).await,
     };
 
     // Print the result or the error
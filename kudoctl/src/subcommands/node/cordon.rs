@@ -0,0 +1,23 @@
+use crate::{
+    client::{self, request::Client},
+    config,
+};
+use anyhow::{Context, Result};
+use clap::Args;
+use log::info;
+
+#[derive(Debug, Args)]
+pub struct Cordon {
+    /// Id of the node
+    #[clap(value_name = "NODE")]
+    node: String,
+}
+
+/// node cordon <node> subcommand execution
+pub async fn execute(args: Cordon, conf: &config::Config) -> Result<String> {
+    let client = Client::new(conf).context("Error creating client")?;
+    let node = client::node::cordon(&client, &args.node, true).await?;
+
+    info!("Node {} cordoned", node.id);
+    Ok(String::new())
+}
@@ -0,0 +1,62 @@
+use std::time::Duration;
+
+use crate::{
+    client::{self, node::NodeDrain, request::Client},
+    config,
+};
+use anyhow::{bail, Context, Result};
+use clap::Args;
+
+/// Time between two checks of the drain when watching it
+const WATCH_INTERVAL: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Args)]
+pub struct Drain {
+    /// Number of instances of a workload moved at the same time
+    #[clap(long)]
+    max_unavailable: Option<u32>,
+
+    /// Start the drain and exit instead of waiting for the node to be drained
+    #[clap(long)]
+    no_watch: bool,
+
+    /// Id of the node
+    #[clap(value_name = "NODE")]
+    node: String,
+}
+
+/// node drain <node> subcommand execution
+/// Prints the progress of the drain until no instance is left on the node.
+pub async fn execute(args: Drain, conf: &config::Config) -> Result<String> {
+    let client = Client::new(conf).context("Error creating client")?;
+    let mut drain = client::node::drain(&client, &args.node, args.max_unavailable).await?;
+
+    loop {
+        match drain.state.as_str() {
+            "Drained" => return Ok(format!("Node {} drained", args.node)),
+            // the node was uncordoned in the meantime
+            "NotDrained" => bail!("Drain of node {} stopped: {}", args.node, drain.message),
+            _ => println!("{}", progress(&args.node, &drain)),
+        }
+
+        if args.no_watch {
+            return Ok(String::new());
+        }
+        tokio::time::sleep(WATCH_INTERVAL).await;
+        drain = client::node::drain_status(&client, &args.node).await?;
+    }
+}
+
+fn progress(node: &str, drain: &NodeDrain) -> String {
+    let mut progress = format!(
+        "Waiting for node {} to be drained: {} instances left, {} moving, {} moved",
+        node, drain.remaining, drain.moving, drain.moved
+    );
+    if drain.evicted > 0 {
+        progress.push_str(&format!(
+            ", {} waiting for a node with enough capacity",
+            drain.evicted
+        ));
+    }
+    progress
+}
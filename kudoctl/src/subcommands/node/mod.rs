@@ -0,0 +1,34 @@
+use crate::config;
+use anyhow::Result;
+use clap::Args;
+mod cordon;
+mod drain;
+mod uncordon;
+
+#[derive(Debug, Args)]
+/// Manage the nodes of the cluster
+pub struct Subcommand {
+    #[clap(subcommand)]
+    action: Actions,
+}
+
+#[derive(Debug, clap::Subcommand)]
+enum Actions {
+    /// Stop placing new instances on a node, the instances already there keep running
+    Cordon(cordon::Cordon),
+
+    /// Place new instances on a node again, stopping its drain if any
+    Uncordon(uncordon::Uncordon),
+
+    /// Cordon a node then move its instances to the other nodes
+    Drain(drain::Drain),
+}
+
+/// match the subcommand to execute the correct action
+pub async fn execute(args: Subcommand, conf: &config::Config) -> Result<String> {
+    match args.action {
+        Actions::Cordon(args) => cordon::execute(args, conf).await,
+        Actions::Uncordon(args) => uncordon::execute(args, conf).await,
+        Actions::Drain(args) => drain::execute(args, conf).await,
+    }
+}
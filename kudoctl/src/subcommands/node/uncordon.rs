@@ -0,0 +1,23 @@
+use crate::{
+    client::{self, request::Client},
+    config,
+};
+use anyhow::{Context, Result};
+use clap::Args;
+use log::info;
+
+#[derive(Debug, Args)]
+pub struct Uncordon {
+    /// Id of the node
+    #[clap(value_name = "NODE")]
+    node: String,
+}
+
+/// node uncordon <node> subcommand execution
+pub async fn execute(args: Uncordon, conf: &config::Config) -> Result<String> {
+    let client = Client::new(conf).context("Error creating client")?;
+    let node = client::node::cordon(&client, &args.node, false).await?;
+
+    info!("Node {} uncordoned", node.id);
+    Ok(String::new())
+}
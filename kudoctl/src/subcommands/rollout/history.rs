@@ -0,0 +1,52 @@
+use super::super::get::output::{self, format_timestamp, OutputFormat};
+use crate::{
+    client::{self, request::Client, workload::GetRevisionsResponse},
+    config,
+};
+use anyhow::{Context, Result};
+use clap::Args;
+use std::fmt::Display;
+
+#[derive(Debug, Args)]
+pub struct History {
+    /// Change the output format
+    #[clap(short = 'F', long, arg_enum, value_parser)]
+    format: Option<OutputFormat>,
+
+    /// Don’t show header (human readable only)
+    #[clap(long)]
+    no_header: bool,
+
+    /// Name of the workload
+    #[clap(value_name = "WORKLOAD")]
+    workload: String,
+}
+
+/// rollout history <workload> subcommand execution
+/// Does the request, then formats the output.
+pub async fn execute(args: History, conf: &config::Config) -> Result<String> {
+    let client = Client::new(conf).context("Error creating client")?;
+    let mut result = client::workload::revisions(&client, &conf.namespace, &args.workload).await?;
+    result.show_header = !args.no_header;
+    output::format_output(result, args.format.unwrap_or(OutputFormat::HumanReadable))
+}
+
+impl Display for GetRevisionsResponse {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.show_header {
+            writeln!(f, "REVISION\tDATE\tAUTHOR\tURI")?;
+        }
+
+        for revision in &self.revisions {
+            writeln!(
+                f,
+                "{}\t{}\t{}\t{}",
+                revision.revision,
+                format_timestamp(revision.timestamp),
+                revision.author,
+                revision.workload.uri
+            )?;
+        }
+        Ok(())
+    }
+}
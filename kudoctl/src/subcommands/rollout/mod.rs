@@ -0,0 +1,34 @@
+use crate::config;
+use anyhow::Result;
+use clap::Args;
+mod history;
+mod status;
+mod undo;
+
+#[derive(Debug, Args)]
+/// Manage the rollout of a workload
+pub struct Subcommand {
+    #[clap(subcommand)]
+    action: Actions,
+}
+
+#[derive(Debug, clap::Subcommand)]
+enum Actions {
+    /// Show the revisions kept for a workload
+    History(history::History),
+
+    /// Show the progress of the rollout of the current revision of a workload
+    Status(status::Status),
+
+    /// Restore a previous revision of a workload and replace its running instances
+    Undo(undo::Undo),
+}
+
+/// match the subcommand to execute the correct action
+pub async fn execute(args: Subcommand, conf: &config::Config) -> Result<String> {
+    match args.action {
+        Actions::History(args) => history::execute(args, conf).await,
+        Actions::Status(args) => status::execute(args, conf).await,
+        Actions::Undo(args) => undo::execute(args, conf).await,
+    }
+}
@@ -0,0 +1,67 @@
+use std::time::Duration;
+
+use crate::{
+    client::{self, request::Client, workload::Rollout},
+    config,
+};
+use anyhow::{bail, Context, Result};
+use clap::Args;
+
+/// Time between two checks of the rollout when watching it
+const WATCH_INTERVAL: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Args)]
+pub struct Status {
+    /// Print the current progress and exit instead of waiting for the rollout to end
+    #[clap(long)]
+    no_watch: bool,
+
+    /// Name of the workload
+    #[clap(value_name = "WORKLOAD")]
+    workload: String,
+}
+
+/// rollout status <workload> subcommand execution
+/// Prints the progress of the rollout until it is complete or paused.
+pub async fn execute(args: Status, conf: &config::Config) -> Result<String> {
+    let client = Client::new(conf).context("Error creating client")?;
+
+    loop {
+        let rollout = client::workload::rollout(&client, &conf.namespace, &args.workload).await?;
+
+        match rollout.state.as_str() {
+            "Complete" => {
+                return Ok(format!(
+                    "Workload {} successfully rolled out to revision {}",
+                    args.workload, rollout.revision
+                ))
+            }
+            "Paused" => bail!(
+                "Rollout of workload {} to revision {} paused: {}",
+                args.workload,
+                rollout.revision,
+                rollout.message
+            ),
+            _ => println!("{}", progress(&args.workload, &rollout)),
+        }
+
+        if args.no_watch {
+            return Ok(String::new());
+        }
+        tokio::time::sleep(WATCH_INTERVAL).await;
+    }
+}
+
+fn progress(workload: &str, rollout: &Rollout) -> String {
+    if rollout.outdated > 0 {
+        format!(
+            "Waiting for rollout of workload {} to finish: {} out of {} new instances running, {} old instances pending termination",
+            workload, rollout.ready, rollout.replicas, rollout.outdated
+        )
+    } else {
+        format!(
+            "Waiting for rollout of workload {} to finish: {} out of {} instances running",
+            workload, rollout.ready, rollout.replicas
+        )
+    }
+}
@@ -0,0 +1,31 @@
+use crate::{
+    client::{self, request::Client},
+    config,
+};
+use anyhow::{Context, Result};
+use clap::Args;
+use log::info;
+
+#[derive(Debug, Args)]
+pub struct Undo {
+    /// Revision to restore, the previous revision if not set
+    #[clap(long)]
+    to_revision: Option<u64>,
+
+    /// Name of the workload
+    #[clap(value_name = "WORKLOAD")]
+    workload: String,
+}
+
+/// rollout undo <workload> subcommand execution
+pub async fn execute(args: Undo, conf: &config::Config) -> Result<String> {
+    let client = Client::new(conf).context("Error creating client")?;
+    let id = client::workload::rollback(&client, &conf.namespace, &args.workload, args.to_revision)
+        .await?;
+
+    match args.to_revision {
+        Some(revision) => info!("Workload {} rolled back to revision {}", id, revision),
+        None => info!("Workload {} rolled back to its previous revision", id),
+    }
+    Ok(String::new())
+}
@@ -0,0 +1,29 @@
+use crate::{
+    client::{self, request::Client},
+    config,
+};
+use anyhow::{Context, Result};
+use clap::Args;
+use log::info;
+
+#[derive(Debug, Args)]
+/// Change the number of instances to keep running for a workload
+pub struct Scale {
+    /// Number of instances to keep running
+    #[clap(long)]
+    replicas: u32,
+
+    /// Name of the workload
+    #[clap(value_name = "WORKLOAD")]
+    workload: String,
+}
+
+/// scale <workload> subcommand execution
+pub async fn execute(args: Scale, conf: &config::Config) -> Result<String> {
+    let client = Client::new(conf).context("Error creating client")?;
+    let id =
+        client::workload::scale(&client, &conf.namespace, &args.workload, args.replicas).await?;
+
+    info!("Workload {} scaled to {} replicas", id, args.replicas);
+    Ok(String::new())
+}
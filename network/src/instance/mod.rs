@@ -1,12 +1,17 @@
 pub mod request;
 pub mod response;
 
+use std::net::Ipv4Addr;
+
+use log::info;
+
 use crate::error::KudoNetworkError;
+use crate::port::Port;
 use crate::utils::{
     bridge_name, default_interface_name, namespace_name, run_command, veth_in_name, veth_out_name,
 };
 
-use request::{CleanInstanceRequest, SetupInstanceRequest};
+use request::{CleanInstanceRequest, RouteInstanceRequest, SetupInstanceRequest};
 use response::SetupInstanceResponse;
 
 /// Create a network namespace and interfaces, and configure routes to isolate instances
@@ -84,106 +89,17 @@ pub fn setup_instance(
     )?;
 
     // Iptables rules to expose ports
-    for port in request.ports.iter() {
-        run_command(
-            "iptables",
-            &[
-                "-t",
-                "nat",
-                "-A",
-                "PREROUTING",
-                "-i",
-                &default_interface,
-                "-p",
-                "tcp",
-                "--dport",
-                &port.source.to_string(),
-                "-j",
-                "DNAT",
-                "--to",
-                &format!(
-                    "{}:{}",
-                    request.instance_ip_cidr.address(),
-                    port.destination
-                ),
-            ],
-        )?;
-        run_command(
-            "iptables",
-            &[
-                "-t",
-                "nat",
-                "-A",
-                "PREROUTING",
-                "-i",
-                &default_interface,
-                "-p",
-                "udp",
-                "--dport",
-                &port.source.to_string(),
-                "-j",
-                "DNAT",
-                "--to",
-                &format!(
-                    "{}:{}",
-                    request.instance_ip_cidr.address(),
-                    port.destination
-                ),
-            ],
-        )?;
-        run_command(
-            "iptables",
-            &[
-                "-t",
-                "nat",
-                "-A",
-                "OUTPUT",
-                "-o",
-                "lo",
-                "-p",
-                "tcp",
-                "-m",
-                "tcp",
-                "--dport",
-                &port.source.to_string(),
-                "-j",
-                "DNAT",
-                "--to-destination",
-                &format!(
-                    "{}:{}",
-                    request.instance_ip_cidr.address(),
-                    port.destination
-                ),
-            ],
-        )?;
-        run_command(
-            "iptables",
-            &[
-                "-t",
-                "nat",
-                "-A",
-                "OUTPUT",
-                "-o",
-                "lo",
-                "-p",
-                "udp",
-                "-m",
-                "udp",
-                "--dport",
-                &port.source.to_string(),
-                "-j",
-                "DNAT",
-                "--to-destination",
-                &format!(
-                    "{}:{}",
-                    request.instance_ip_cidr.address(),
-                    port.destination
-                ),
-            ],
-        )?;
-    }
+    add_port_rules(
+        &default_interface,
+        request.instance_ip_cidr.address(),
+        &request.ports,
+    )?;
 
-    Ok(SetupInstanceResponse::new(veth_out, namespace))
+    Ok(SetupInstanceResponse::new(
+        veth_out,
+        namespace,
+        request.instance_ip_cidr.address(),
+    ))
 }
 
 /// Remove instance network namespace and interfaces created for it
@@ -195,106 +111,121 @@ pub fn clean_instance(request: CleanInstanceRequest) -> Result<(), KudoNetworkEr
     run_command("ip", &["netns", "del", &namespace])?;
     run_command("ip", &["link", "del", &veth_out])?;
 
-    for port in request.ports.iter() {
-        run_command(
-            "iptables",
-            &[
-                "-t",
-                "nat",
-                "-D",
-                "PREROUTING",
-                "-i",
-                &default_interface,
-                "-p",
-                "tcp",
-                "--dport",
-                &port.source.to_string(),
-                "-j",
-                "DNAT",
-                "--to",
-                &format!(
-                    "{}:{}",
-                    request.instance_ip_cidr.address(),
-                    port.destination
-                ),
-            ],
-        )?;
+    delete_port_rules(
+        &default_interface,
+        request.instance_ip_cidr.address(),
+        &request.ports,
+    )?;
+    Ok(())
+}
+
+/// Expose the ports of an instance again, after `unroute_instance`
+pub fn route_instance(request: RouteInstanceRequest) -> Result<(), KudoNetworkError> {
+    let default_interface = default_interface_name()?;
+    add_port_rules(
+        &default_interface,
+        request.instance_ip_cidr.address(),
+        &request.ports,
+    )
+}
+
+/// Stop exposing the ports of an instance, so that it doesn't receive traffic anymore while
+/// keeping its network namespace and interfaces
+pub fn unroute_instance(request: RouteInstanceRequest) -> Result<(), KudoNetworkError> {
+    let default_interface = default_interface_name()?;
+    delete_port_rules(
+        &default_interface,
+        request.instance_ip_cidr.address(),
+        &request.ports,
+    )
+}
+
+fn add_port_rules(
+    default_interface: &str,
+    instance_ip: Ipv4Addr,
+    ports: &[Port],
+) -> Result<(), KudoNetworkError> {
+    for port in ports {
+        for rule in port_rules(default_interface, instance_ip, port) {
+            let mut args = vec!["-t", "nat", "-A"];
+            args.extend(rule.iter().map(String::as_str));
+            run_command("iptables", &args)?;
+        }
+    }
+    Ok(())
+}
 
-        run_command(
-            "iptables",
-            &[
-                "-t",
-                "nat",
-                "-D",
+/// Rules which don't exist, because the instance was unrouted, are skipped and logged
+fn delete_port_rules(
+    default_interface: &str,
+    instance_ip: Ipv4Addr,
+    ports: &[Port],
+) -> Result<(), KudoNetworkError> {
+    for port in ports {
+        for rule in port_rules(default_interface, instance_ip, port) {
+            let mut check = vec!["-t", "nat", "-C"];
+            check.extend(rule.iter().map(String::as_str));
+            if run_command("iptables", &check).is_err() {
+                info!("Skipping missing iptables rule {}", rule.join(" "));
+                continue;
+            }
+
+            let mut args = vec!["-t", "nat", "-D"];
+            args.extend(rule.iter().map(String::as_str));
+            run_command("iptables", &args)?;
+        }
+    }
+    Ok(())
+}
+
+/// Iptables rules, without table nor action, forwarding the tcp and udp traffic of a node port
+/// to an instance port
+fn port_rules(default_interface: &str, instance_ip: Ipv4Addr, port: &Port) -> Vec<Vec<String>> {
+    let destination = format!("{}:{}", instance_ip, port.destination);
+    let source = port.source.to_string();
+
+    let mut rules = vec![];
+    for protocol in ["tcp", "udp"] {
+        rules.push(
+            [
                 "PREROUTING",
                 "-i",
-                &default_interface,
+                default_interface,
                 "-p",
-                "udp",
+                protocol,
                 "--dport",
-                &port.source.to_string(),
+                &source,
                 "-j",
                 "DNAT",
                 "--to",
-                &format!(
-                    "{}:{}",
-                    request.instance_ip_cidr.address(),
-                    port.destination
-                ),
-            ],
-        )?;
-
-        run_command(
-            "iptables",
-            &[
-                "-t",
-                "nat",
-                "-D",
-                "OUTPUT",
-                "-o",
-                "lo",
-                "-p",
-                "tcp",
-                "-m",
-                "tcp",
-                "--dport",
-                &port.source.to_string(),
-                "-j",
-                "DNAT",
-                "--to-destination",
-                &format!(
-                    "{}:{}",
-                    request.instance_ip_cidr.address(),
-                    port.destination
-                ),
-            ],
-        )?;
-
-        run_command(
-            "iptables",
-            &[
-                "-t",
-                "nat",
-                "-D",
+                &destination,
+            ]
+            .iter()
+            .map(|arg| arg.to_string())
+            .collect(),
+        );
+    }
+    for protocol in ["tcp", "udp"] {
+        rules.push(
+            [
                 "OUTPUT",
                 "-o",
                 "lo",
                 "-p",
-                "udp",
+                protocol,
                 "-m",
-                "udp",
+                protocol,
                 "--dport",
-                &port.source.to_string(),
+                &source,
                 "-j",
                 "DNAT",
                 "--to-destination",
-                &format!(
-                    "{}:{}",
-                    request.instance_ip_cidr.address(),
-                    port.destination
-                ),
-            ],
-        )?;
+                &destination,
+            ]
+            .iter()
+            .map(|arg| arg.to_string())
+            .collect(),
+        );
     }
-    Ok(())
+    rules
 }
@@ -56,3 +56,20 @@ impl CleanInstanceRequest {
         }
     }
 }
+
+// Routing
+pub struct RouteInstanceRequest {
+    /// Composed of the instance ip and a mask
+    pub instance_ip_cidr: Ipv4Inet,
+    /// Exposed ports to be routed to the instance
+    pub ports: Vec<Port>,
+}
+
+impl RouteInstanceRequest {
+    pub fn new(instance_ip_cidr: Ipv4Inet, ports: Vec<Port>) -> Self {
+        Self {
+            instance_ip_cidr,
+            ports,
+        }
+    }
+}
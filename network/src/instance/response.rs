@@ -1,13 +1,18 @@
+use std::net::Ipv4Addr;
+
 pub struct SetupInstanceResponse {
     pub interface_name: String,
     pub namespace_name: String,
+    /// Address the instance can be reached at from the node
+    pub instance_ip: Ipv4Addr,
 }
 
 impl SetupInstanceResponse {
-    pub fn new(interface_name: String, namespace_name: String) -> Self {
+    pub fn new(interface_name: String, namespace_name: String, instance_ip: Ipv4Addr) -> Self {
         Self {
             interface_name,
             namespace_name,
+            instance_ip,
         }
     }
 }
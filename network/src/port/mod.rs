@@ -1,3 +1,4 @@
+#[derive(Clone, Debug)]
 pub struct Port {
     pub source: i32,
     pub destination: i32,
@@ -1,16 +1,18 @@
 use bollard::container::{
     Config, KillContainerOptions, RemoveContainerOptions, RenameContainerOptions,
-    StopContainerOptions,
+    StopContainerOptions, WaitContainerOptions,
 };
+use bollard::exec::{CreateExecOptions, StartExecResults};
 use bollard::Docker;
 
-use anyhow::{Context, Error, Result};
+use anyhow::{anyhow, Context, Error, Result};
 
 use bollard::image::CreateImageOptions;
 use futures_util::TryStreamExt;
 
 use super::workload_trait::Workload;
-use proto::agent::Instance;
+use bollard::models::HostConfig;
+use proto::agent::{Instance, Resource};
 
 pub struct Container {
     id: String,
@@ -40,6 +42,7 @@ impl Container {
         let container_config: Config<&str> = Config {
             image: Some(instance.uri.as_str()),
             tty: Some(true),
+            host_config: Some(host_config(instance.resource.as_ref())?),
             ..Default::default()
         };
 
@@ -88,6 +91,31 @@ impl Container {
     }
 }
 
+//
+// Limits the cpu (in milliCPU) and memory (in MB) of a container to the limits of its
+// instance, a limit of 0 leaving the resource unlimited. The disk limit isn't enforced.
+// A limit too large for docker is rejected rather than wrapped around.
+//
+fn host_config(resource: Option<&Resource>) -> Result<HostConfig> {
+    let limit = resource
+        .and_then(|resource| resource.limit.clone())
+        .unwrap_or_default();
+    let enforced = |amount: u64, unit: i64, name: &str| match amount {
+        0 => Ok(None),
+        amount => i64::try_from(amount)
+            .ok()
+            .and_then(|amount| amount.checked_mul(unit))
+            .map(Some)
+            .ok_or_else(|| anyhow!("{} limit of {} is too large", name, amount)),
+    };
+
+    Ok(HostConfig {
+        nano_cpus: enforced(limit.cpu, 1_000_000, "cpu")?,
+        memory: enforced(limit.memory, 1024 * 1024, "memory")?,
+        ..Default::default()
+    })
+}
+
 #[tonic::async_trait]
 impl Workload for Container {
     fn id(&self) -> String {
@@ -136,6 +164,83 @@ impl Workload for Container {
 
         Ok(())
     }
+
+    //
+    // Waits for the container to exit
+    // and returns its exit code
+    //
+    async fn wait(&self) -> Result<i64, Error> {
+        let docker =
+            Docker::connect_with_socket_defaults().context("Can't connect to docker socket. ")?;
+
+        let responses = docker
+            .wait_container(self.id().as_str(), None::<WaitContainerOptions<String>>)
+            .try_collect::<Vec<_>>()
+            .await
+            .context("Can't wait for docker container. ")?;
+
+        responses
+            .last()
+            .map(|response| response.status_code)
+            .context("Docker container exited without exit code. ")
+    }
+
+    //
+    // Restarts a container,
+    // starting it again if it exited
+    //
+    async fn restart(&self) -> Result<(), Error> {
+        let docker =
+            Docker::connect_with_socket_defaults().context("Can't connect to docker socket. ")?;
+
+        docker
+            .restart_container(self.id().as_str(), None)
+            .await
+            .context("Can't restart docker container. ")?;
+
+        Ok(())
+    }
+
+    //
+    // Executes a command inside the container
+    // and returns its exit code
+    //
+    async fn exec(&self, command: Vec<String>) -> Result<i64, Error> {
+        let docker =
+            Docker::connect_with_socket_defaults().context("Can't connect to docker socket. ")?;
+
+        let exec = docker
+            .create_exec(
+                self.id().as_str(),
+                CreateExecOptions {
+                    cmd: Some(command),
+                    attach_stdout: Some(true),
+                    attach_stderr: Some(true),
+                    ..Default::default()
+                },
+            )
+            .await
+            .context("Can't create docker exec. ")?;
+
+        // the command is done once its output is closed
+        if let StartExecResults::Attached { output, .. } = docker
+            .start_exec(&exec.id, None)
+            .await
+            .context("Can't start docker exec. ")?
+        {
+            output
+                .try_collect::<Vec<_>>()
+                .await
+                .context("Can't read docker exec output. ")?;
+        }
+
+        docker
+            .inspect_exec(&exec.id)
+            .await
+            .context("Can't inspect docker exec. ")?
+            .exit_code
+            .context("Docker exec exited without exit code. ")
+    }
 }
 
 #[cfg(test)]
@@ -148,7 +253,7 @@ mod tests {
         container::{ListContainersOptions, RemoveContainerOptions},
         Docker,
     };
-    use proto::agent::{Instance, Resource, ResourceSummary, Type};
+    use proto::agent::{Instance, Resource, ResourceSummary, RestartPolicy, Type};
 
     const IMAGE: &str = "alpine:3";
 
@@ -164,6 +269,7 @@ mod tests {
                 memory: 0,
                 disk: 0,
             }),
+            request: None,
         };
 
         let instance = Instance {
@@ -176,9 +282,12 @@ mod tests {
             resource: Some(resource),
             status: 1,
             r#type: Type::Container.into(),
+            restart_policy: RestartPolicy::Always.into(),
+            liveness_probe: None,
+            readiness_probe: None,
         };
 
-        Ok(Container::new(instance).await?)
+        Container::new(instance).await
     }
 
     async fn create_container_test() -> Result<(), Error> {
@@ -225,12 +334,9 @@ mod tests {
             }))
             .await?;
 
-        assert_eq!(
-            result
-                .iter()
-                .any(|cont| cont.id.as_ref().unwrap() == &container.id),
-            false
-        );
+        assert!(!result
+            .iter()
+            .any(|cont| cont.id.as_ref().unwrap() == &container.id));
 
         Ok(())
     }
@@ -244,4 +350,43 @@ mod tests {
     fn test_stop_container() {
         tokio_test::block_on(stop_container_test()).unwrap();
     }
+
+    #[test]
+    fn test_host_config_enforces_limits() {
+        let resource = Resource {
+            limit: Some(ResourceSummary {
+                cpu: 500,
+                memory: 256,
+                disk: 10,
+            }),
+            usage: None,
+            request: Some(ResourceSummary {
+                cpu: 100,
+                memory: 128,
+                disk: 10,
+            }),
+        };
+        let config = super::host_config(Some(&resource)).unwrap();
+        assert_eq!(Some(500_000_000), config.nano_cpus);
+        assert_eq!(Some(256 * 1024 * 1024), config.memory);
+
+        let unlimited = super::host_config(None).unwrap();
+        assert_eq!(None, unlimited.nano_cpus);
+        assert_eq!(None, unlimited.memory);
+    }
+
+    #[test]
+    fn test_host_config_rejects_overflowing_limits() {
+        for (cpu, memory) in [(u64::MAX, 256), (500, 1 << 44), (500, u64::MAX)] {
+            let resource = Resource {
+                limit: Some(ResourceSummary {
+                    cpu,
+                    memory,
+                    disk: 10,
+                }),
+                ..Default::default()
+            };
+            assert!(super::host_config(Some(&resource)).is_err());
+        }
+    }
 }
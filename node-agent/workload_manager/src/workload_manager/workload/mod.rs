@@ -3,6 +3,8 @@ use proto::agent::{Instance, Type};
 use workload_trait::Workload;
 
 mod container;
+pub mod probe;
+pub mod restart;
 pub mod workload_trait;
 
 pub async fn create(instance: Instance) -> Result<impl Workload> {
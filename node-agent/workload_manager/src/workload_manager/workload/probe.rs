@@ -0,0 +1,250 @@
+use std::net::Ipv4Addr;
+use std::time::Duration;
+
+use anyhow::{anyhow, bail, Context, Result};
+use proto::agent::{probe::Action, Probe, ProbeResult};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+
+use super::workload_trait::Workload;
+
+// Values used for the unset fields of a probe
+const DEFAULT_PERIOD: Duration = Duration::from_secs(10);
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(1);
+const DEFAULT_FAILURE_THRESHOLD: u32 = 3;
+
+//
+// Periodically checks the health of a workload
+// and keeps the result of the last checks
+//
+pub struct Prober {
+    probe: Probe,
+    ip: Option<Ipv4Addr>,
+    initial_success: bool,
+    result: ProbeResult,
+}
+
+impl Prober {
+    //
+    // `initial_success` is the state of the probe
+    // until it fails or succeeds for the first time
+    //
+    pub fn new(probe: Probe, initial_success: bool) -> Self {
+        Prober {
+            probe,
+            ip: None,
+            initial_success,
+            result: ProbeResult {
+                success: initial_success,
+                message: String::new(),
+                consecutive_failures: 0,
+            },
+        }
+    }
+
+    //
+    // Sets the address the network probes are sent to
+    //
+    pub fn set_ip(&mut self, ip: Ipv4Addr) {
+        self.ip = Some(ip);
+    }
+
+    pub fn initial_delay(&self) -> Duration {
+        Duration::from_secs(self.probe.initial_delay_seconds.into())
+    }
+
+    pub fn period(&self) -> Duration {
+        match self.probe.period_seconds {
+            0 => DEFAULT_PERIOD,
+            period => Duration::from_secs(period.into()),
+        }
+    }
+
+    fn timeout(&self) -> Duration {
+        match self.probe.timeout_seconds {
+            0 => DEFAULT_TIMEOUT,
+            timeout => Duration::from_secs(timeout.into()),
+        }
+    }
+
+    fn failure_threshold(&self) -> u32 {
+        match self.probe.failure_threshold {
+            0 => DEFAULT_FAILURE_THRESHOLD,
+            threshold => threshold,
+        }
+    }
+
+    pub fn result(&self) -> &ProbeResult {
+        &self.result
+    }
+
+    pub fn succeeded(&self) -> bool {
+        self.result.success
+    }
+
+    //
+    // Forgets the previous checks,
+    // once the workload has been restarted
+    //
+    pub fn reset(&mut self) {
+        self.result = ProbeResult {
+            success: self.initial_success,
+            message: String::new(),
+            consecutive_failures: 0,
+        };
+    }
+
+    //
+    // Checks the workload once,
+    // returns true if the state of the probe changed
+    //
+    pub async fn check<W: Workload + Sync>(&mut self, workload: &W) -> bool {
+        let outcome = match tokio::time::timeout(self.timeout(), self.execute(workload)).await {
+            Ok(outcome) => outcome,
+            Err(_) => Err(anyhow!("Timed out after {}s", self.timeout().as_secs())),
+        };
+        self.record(outcome)
+    }
+
+    fn record(&mut self, outcome: Result<()>) -> bool {
+        let success = self.result.success;
+
+        match outcome {
+            Ok(()) => {
+                self.result.success = true;
+                self.result.message = String::new();
+                self.result.consecutive_failures = 0;
+            }
+            Err(err) => {
+                self.result.message = format!("{:#}", err);
+                self.result.consecutive_failures += 1;
+                if self.result.consecutive_failures >= self.failure_threshold() {
+                    self.result.success = false;
+                }
+            }
+        }
+
+        success != self.result.success
+    }
+
+    async fn execute<W: Workload + Sync>(&self, workload: &W) -> Result<()> {
+        match &self.probe.action {
+            Some(Action::HttpGet(action)) => {
+                http_get(self.address(action.port)?, &action.path).await
+            }
+            Some(Action::TcpSocket(action)) => {
+                TcpStream::connect(self.address(action.port)?)
+                    .await
+                    .context("Can't connect to instance. ")?;
+                Ok(())
+            }
+            Some(Action::Exec(action)) => {
+                let exit_code = workload.exec(action.command.clone()).await?;
+                if exit_code != 0 {
+                    bail!("Command exited with code {}", exit_code);
+                }
+                Ok(())
+            }
+            None => Ok(()),
+        }
+    }
+
+    fn address(&self, port: i32) -> Result<(Ipv4Addr, u16)> {
+        let ip = self.ip.context("Instance has no ip address. ")?;
+        let port = u16::try_from(port).with_context(|| format!("Invalid port {}. ", port))?;
+        Ok((ip, port))
+    }
+}
+
+//
+// Sends a GET request and checks
+// that the status code is 2xx or 3xx
+//
+async fn http_get(address: (Ipv4Addr, u16), path: &str) -> Result<()> {
+    let mut stream = TcpStream::connect(address)
+        .await
+        .context("Can't connect to instance. ")?;
+
+    let path = if path.is_empty() { "/" } else { path };
+    stream
+        .write_all(
+            format!(
+                "GET {} HTTP/1.0\r\nHost: {}\r\nConnection: close\r\n\r\n",
+                path, address.0
+            )
+            .as_bytes(),
+        )
+        .await
+        .context("Can't send HTTP request. ")?;
+
+    let mut status_line = String::new();
+    BufReader::new(stream)
+        .read_line(&mut status_line)
+        .await
+        .context("Can't read HTTP response. ")?;
+
+    match status_code(&status_line) {
+        Some(status) if (200..400).contains(&status) => Ok(()),
+        Some(status) => bail!("HTTP request returned status {}", status),
+        None => bail!("Invalid HTTP response {:?}", status_line.trim_end()),
+    }
+}
+
+//
+// Parses the status code of a HTTP status line
+// (eg. `HTTP/1.1 200 OK`)
+//
+fn status_code(status_line: &str) -> Option<u16> {
+    let mut parts = status_line.split_whitespace();
+    if !parts.next()?.starts_with("HTTP/") {
+        return None;
+    }
+    parts.next()?.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{status_code, Prober};
+    use anyhow::anyhow;
+    use proto::agent::Probe;
+
+    fn probe(failure_threshold: u32) -> Probe {
+        Probe {
+            failure_threshold,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_prober_fails_after_threshold() {
+        let mut prober = Prober::new(probe(2), true);
+
+        assert!(!prober.record(Err(anyhow!("refused"))));
+        assert!(prober.succeeded());
+        assert!(prober.record(Err(anyhow!("refused"))));
+        assert!(!prober.succeeded());
+        assert_eq!(2, prober.result().consecutive_failures);
+        assert_eq!("refused", prober.result().message);
+
+        assert!(prober.record(Ok(())));
+        assert!(prober.succeeded());
+        assert_eq!(0, prober.result().consecutive_failures);
+    }
+
+    #[test]
+    fn test_prober_reset() {
+        let mut prober = Prober::new(probe(1), false);
+
+        assert!(prober.record(Ok(())));
+        prober.reset();
+        assert!(!prober.succeeded());
+    }
+
+    #[test]
+    fn test_status_code() {
+        assert_eq!(Some(200), status_code("HTTP/1.1 200 OK\r\n"));
+        assert_eq!(Some(503), status_code("HTTP/1.0 503 Service Unavailable"));
+        assert_eq!(None, status_code("SSH-2.0-OpenSSH_8.9"));
+        assert_eq!(None, status_code(""));
+    }
+}
@@ -0,0 +1,375 @@
+use std::time::Duration;
+
+use anyhow::Result;
+use network::instance::request::RouteInstanceRequest;
+use network::instance::{route_instance, unroute_instance};
+use proto::agent::{Instance, InstanceStatus, RestartPolicy, Status};
+use tokio::sync::{mpsc, oneshot};
+use tokio::time::{Instant, Interval};
+
+use super::probe::Prober;
+use super::workload_trait::Workload;
+
+// Delay before the first restart, doubled after each restart
+const INITIAL_BACKOFF: Duration = Duration::from_secs(10);
+const MAX_BACKOFF: Duration = Duration::from_secs(300);
+
+// The backoff of a workload which ran longer
+// than this before exiting starts over
+const BACKOFF_RESET: Duration = Duration::from_secs(600);
+
+// Number of restarts in a row after which
+// the workload is reported as crash looping
+const CRASH_LOOP_THRESHOLD: u32 = 5;
+
+//
+// Exponential delay between the restarts of a workload
+//
+#[derive(Debug)]
+pub struct Backoff {
+    delay: Duration,
+    consecutive: u32,
+}
+
+impl Backoff {
+    pub fn new() -> Self {
+        Backoff {
+            delay: INITIAL_BACKOFF,
+            consecutive: 0,
+        }
+    }
+
+    //
+    // Returns the delay to wait before the next restart
+    //
+    pub fn next_delay(&mut self) -> Duration {
+        let delay = self.delay;
+        self.delay = (self.delay * 2).min(MAX_BACKOFF);
+        self.consecutive += 1;
+        delay
+    }
+
+    pub fn reset(&mut self) {
+        *self = Backoff::new();
+    }
+
+    //
+    // Returns true if the workload has been restarted
+    // too many times in a row
+    //
+    pub fn is_crash_looping(&self) -> bool {
+        self.consecutive >= CRASH_LOOP_THRESHOLD
+    }
+}
+
+impl Default for Backoff {
+    fn default() -> Self {
+        Backoff::new()
+    }
+}
+
+//
+// Returns true if a workload which stopped running must be restarted,
+// `failed` being true if it exited with an error or was unhealthy
+//
+pub fn should_restart(policy: RestartPolicy, failed: bool) -> bool {
+    match policy {
+        RestartPolicy::Always => true,
+        RestartPolicy::OnFailure => failed,
+        RestartPolicy::Never => false,
+    }
+}
+
+//
+// Why a watched workload stopped running
+//
+enum Exit {
+    // The workload exited with this exit code
+    Exited(i64),
+    // The liveness probe failed with this message
+    Unhealthy(String),
+    // The workload has been asked to stop
+    Stopped,
+}
+
+//
+// Restarts a workload each time it exits or its liveness probe fails,
+// according to the restart policy of its instance
+//
+pub struct Supervisor<W: Workload> {
+    workload: W,
+    instance_id: String,
+    policy: RestartPolicy,
+    restart_count: u32,
+    backoff: Backoff,
+    liveness: Option<Prober>,
+    readiness: Option<Prober>,
+    ready: bool,
+    // Ports of the instance, routed to it only while it is ready
+    routing: Option<RouteInstanceRequest>,
+    routed: bool,
+}
+
+impl<W: Workload + Send + Sync> Supervisor<W> {
+    pub fn new(instance: &Instance, workload: W) -> Self {
+        Supervisor {
+            workload,
+            instance_id: instance.id.clone(),
+            policy: instance.restart_policy(),
+            restart_count: 0,
+            backoff: Backoff::new(),
+            // an instance is alive until proven otherwise,
+            // but ready only once its readiness probe succeeds
+            liveness: instance
+                .liveness_probe
+                .clone()
+                .map(|probe| Prober::new(probe, true)),
+            readiness: instance
+                .readiness_probe
+                .clone()
+                .map(|probe| Prober::new(probe, false)),
+            ready: false,
+            routing: None,
+            routed: false,
+        }
+    }
+
+    //
+    // Sets the network of the instance, as set up by `network::instance::setup_instance`.
+    // The probes are sent to its ip and its ports are unrouted while it isn't ready
+    //
+    pub fn with_network(mut self, routing: RouteInstanceRequest) -> Self {
+        let ip = routing.instance_ip_cidr.address();
+        for prober in self.liveness.iter_mut().chain(self.readiness.iter_mut()) {
+            prober.set_ip(ip);
+        }
+        self.routing = Some(routing);
+        // `setup_instance` routes the ports
+        self.routed = true;
+        self
+    }
+
+    //
+    // Watches the workload until it exits for good or `stop` is received,
+    // each status change is sent with the number of restarts and the probe results
+    //
+    pub async fn run(
+        mut self,
+        status: mpsc::Sender<InstanceStatus>,
+        mut stop: oneshot::Receiver<()>,
+    ) -> Result<()> {
+        loop {
+            let started = Instant::now();
+            for prober in self.liveness.iter_mut().chain(self.readiness.iter_mut()) {
+                prober.reset();
+            }
+            self.set_ready(self.readiness.is_none()).await;
+
+            let (failure, description) = match self.watch(&status, &mut stop).await? {
+                Exit::Exited(exit_code) => {
+                    (exit_code != 0, format!("Exited with code {}", exit_code))
+                }
+                Exit::Unhealthy(message) => (true, format!("Liveness probe failed: {}", message)),
+                Exit::Stopped => return self.stop(&status).await,
+            };
+            self.set_ready(false).await;
+
+            if started.elapsed() >= BACKOFF_RESET {
+                self.backoff.reset();
+            }
+
+            if !should_restart(self.policy, failure) {
+                // the workload may still run if its liveness probe failed
+                self.workload.stop().await?;
+                let state = if failure {
+                    Status::Crashed
+                } else {
+                    Status::Terminated
+                };
+                self.report(&status, state, description).await;
+                return Ok(());
+            }
+
+            let delay = self.backoff.next_delay();
+            let state = if self.backoff.is_crash_looping() {
+                Status::CrashLoopBackOff
+            } else {
+                Status::Crashed
+            };
+            self.report(
+                &status,
+                state,
+                format!("{}, restarting in {}s", description, delay.as_secs()),
+            )
+            .await;
+
+            tokio::select! {
+                _ = tokio::time::sleep(delay) => {},
+                _ = &mut stop => return self.stop(&status).await,
+            };
+
+            self.workload.restart().await?;
+            self.restart_count += 1;
+            self.report(&status, Status::Running, "Restarted".to_string())
+                .await;
+        }
+    }
+
+    //
+    // Runs the probes of the workload until it stops running
+    //
+    async fn watch(
+        &mut self,
+        status: &mpsc::Sender<InstanceStatus>,
+        stop: &mut oneshot::Receiver<()>,
+    ) -> Result<Exit> {
+        let mut liveness_ticks = self.liveness.as_ref().map(ticks);
+        let mut readiness_ticks = self.readiness.as_ref().map(ticks);
+
+        loop {
+            tokio::select! {
+                exit_code = self.workload.wait() => return Ok(Exit::Exited(exit_code?)),
+                _ = &mut *stop => return Ok(Exit::Stopped),
+                _ = tick(&mut liveness_ticks) => {
+                    if let Some(prober) = self.liveness.as_mut() {
+                        if prober.check(&self.workload).await && !prober.succeeded() {
+                            return Ok(Exit::Unhealthy(prober.result().message.clone()));
+                        }
+                    }
+                },
+                _ = tick(&mut readiness_ticks) => {
+                    if let Some(prober) = self.readiness.as_mut() {
+                        if prober.check(&self.workload).await {
+                            let (ready, message) = (prober.succeeded(), prober.result().message.clone());
+                            self.set_ready(ready).await;
+                            let description = if ready {
+                                "Ready".to_string()
+                            } else {
+                                format!("Readiness probe failed: {}", message)
+                            };
+                            self.report(status, Status::Running, description).await;
+                        }
+                    }
+                },
+            }
+        }
+    }
+
+    //
+    // Routes the ports of the instance only while it is ready.
+    // The iptables commands block, so they are run apart from the runtime
+    //
+    async fn set_ready(&mut self, ready: bool) {
+        self.ready = ready;
+
+        if let Some(routing) = &self.routing {
+            if ready == self.routed {
+                return;
+            }
+            let request =
+                RouteInstanceRequest::new(routing.instance_ip_cidr, routing.ports.clone());
+            let routed = tokio::task::spawn_blocking(move || {
+                if ready {
+                    route_instance(request).is_ok()
+                } else {
+                    unroute_instance(request).is_ok()
+                }
+            })
+            .await;
+            // the routing is tried again on the next readiness change
+            if routed.unwrap_or(false) {
+                self.routed = ready;
+            }
+        }
+    }
+
+    async fn stop(&self, status: &mpsc::Sender<InstanceStatus>) -> Result<()> {
+        self.workload.stop().await?;
+        self.report(status, Status::Terminated, "Stopped".to_string())
+            .await;
+        Ok(())
+    }
+
+    async fn report(
+        &self,
+        status: &mpsc::Sender<InstanceStatus>,
+        state: Status,
+        description: String,
+    ) {
+        // the receiver may be gone, the workload is still supervised
+        let _ = status
+            .send(InstanceStatus {
+                id: self.instance_id.clone(),
+                status: state.into(),
+                description,
+                resource: None,
+                restart_count: self.restart_count,
+                liveness: self.liveness.as_ref().map(|prober| prober.result().clone()),
+                readiness: self
+                    .readiness
+                    .as_ref()
+                    .map(|prober| prober.result().clone()),
+                ready: self.ready,
+            })
+            .await;
+    }
+}
+
+//
+// Ticks every period of the probe,
+// once its initial delay is elapsed
+//
+fn ticks(prober: &Prober) -> Interval {
+    tokio::time::interval_at(Instant::now() + prober.initial_delay(), prober.period())
+}
+
+async fn tick(ticks: &mut Option<Interval>) {
+    match ticks {
+        Some(ticks) => {
+            ticks.tick().await;
+        }
+        None => std::future::pending().await,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{should_restart, Backoff, CRASH_LOOP_THRESHOLD, INITIAL_BACKOFF, MAX_BACKOFF};
+    use proto::agent::RestartPolicy;
+
+    #[test]
+    fn test_backoff_doubles_until_max() {
+        let mut backoff = Backoff::new();
+
+        assert_eq!(INITIAL_BACKOFF, backoff.next_delay());
+        assert_eq!(INITIAL_BACKOFF * 2, backoff.next_delay());
+        for _ in 0..10 {
+            backoff.next_delay();
+        }
+        assert_eq!(MAX_BACKOFF, backoff.next_delay());
+
+        backoff.reset();
+        assert_eq!(INITIAL_BACKOFF, backoff.next_delay());
+    }
+
+    #[test]
+    fn test_backoff_crash_loop() {
+        let mut backoff = Backoff::new();
+
+        for _ in 1..CRASH_LOOP_THRESHOLD {
+            backoff.next_delay();
+            assert!(!backoff.is_crash_looping());
+        }
+        backoff.next_delay();
+        assert!(backoff.is_crash_looping());
+    }
+
+    #[test]
+    fn test_should_restart() {
+        assert!(should_restart(RestartPolicy::Always, false));
+        assert!(should_restart(RestartPolicy::Always, true));
+        assert!(!should_restart(RestartPolicy::OnFailure, false));
+        assert!(should_restart(RestartPolicy::OnFailure, true));
+        assert!(!should_restart(RestartPolicy::Never, true));
+    }
+}
@@ -14,4 +14,22 @@ pub trait Workload {
     // (equivalent to a `kill -9` on linux)
     //
     async fn kill(&self) -> Result<()>;
+
+    //
+    // Waits for the workload to exit
+    // and returns its exit code
+    //
+    async fn wait(&self) -> Result<i64>;
+
+    //
+    // Restarts a workload,
+    // starting it again if it exited
+    //
+    async fn restart(&self) -> Result<()>;
+
+    //
+    // Executes a command inside the workload
+    // and returns its exit code
+    //
+    async fn exec(&self, command: Vec<String>) -> Result<i64>;
 }
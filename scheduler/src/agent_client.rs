@@ -0,0 +1,254 @@
+use log::debug;
+use proto::agent::{self, instance_service_client::InstanceServiceClient, Signal};
+use proto::scheduler::{probe, Instance, InstanceStatus, Probe, Resource, ResourceSummary, Status};
+use tonic::transport::{Channel, Endpoint};
+use tonic::{Response, Streaming};
+
+use crate::{NodeIdentifier, SchedulerError};
+
+/// `AgentClient` is the client of the instance service of a node agent, created when the node
+/// registers. The connection is opened on the first request, and opened again if it is lost.
+///
+/// Properties:
+///
+/// * `node_id`: The id of the node the agent runs on.
+/// * `client`: The gRPC client of the agent.
+#[derive(Debug, Clone)]
+pub struct AgentClient {
+    node_id: NodeIdentifier,
+    client: InstanceServiceClient<Channel>,
+}
+
+impl AgentClient {
+    /// `new` creates the client of the agent of a node.
+    ///
+    /// Arguments:
+    ///
+    /// * `node_id`: The id of the node.
+    /// * `address`: The address of the gRPC server of the agent, e.g. `10.0.0.2:50053`.
+    ///
+    /// Returns:
+    ///
+    /// The client, or an error if the address is invalid.
+    pub fn new(node_id: &str, address: &str) -> Result<Self, SchedulerError> {
+        let uri = if address.contains("://") {
+            address.to_string()
+        } else {
+            format!("http://{}", address)
+        };
+        let endpoint = Endpoint::from_shared(uri)
+            .map_err(|_| SchedulerError::InvalidNodeAddress(address.to_string()))?;
+
+        Ok(AgentClient {
+            node_id: node_id.to_string(),
+            client: InstanceServiceClient::new(endpoint.connect_lazy()),
+        })
+    }
+
+    /// It returns the id of the node the agent runs on
+    pub fn node_id(&self) -> &str {
+        &self.node_id
+    }
+
+    /// It asks the agent to create an instance, and returns the stream of its statuses
+    pub async fn create(
+        &mut self,
+        instance: Instance,
+    ) -> Result<Streaming<agent::InstanceStatus>, SchedulerError> {
+        debug!("creating instance {} on node {}", instance.id, self.node_id);
+        self.client
+            .create(agent_instance(instance))
+            .await
+            .map(Response::into_inner)
+            .map_err(|status| {
+                SchedulerError::AgentRequest(self.node_id.clone(), status.to_string())
+            })
+    }
+
+    /// It sends a signal to an instance running on the node of the agent
+    pub async fn signal(
+        &mut self,
+        instance: Instance,
+        signal: Signal,
+    ) -> Result<(), SchedulerError> {
+        debug!(
+            "sending {:?} to instance {} on node {}",
+            signal, instance.id, self.node_id
+        );
+        self.client
+            .signal(agent::SignalInstruction {
+                instance: Some(agent_instance(instance)),
+                signal: signal.into(),
+            })
+            .await
+            .map(|_| ())
+            .map_err(|status| {
+                SchedulerError::AgentRequest(self.node_id.clone(), status.to_string())
+            })
+    }
+}
+
+/// It converts a status reported by an agent to the status sent to the controller
+pub fn instance_status(status: agent::InstanceStatus) -> InstanceStatus {
+    InstanceStatus {
+        id: status.id.clone(),
+        status: self::status(status.status()).into(),
+        status_description: status.description,
+        resource: status.resource.map(|resource| Resource {
+            limit: resource.limit.map(resource_summary),
+            usage: resource.usage.map(resource_summary),
+            request: resource.request.map(resource_summary),
+        }),
+        restart_count: status.restart_count,
+        ready: status.ready,
+        ..Default::default()
+    }
+}
+
+/// It converts the status of an instance on an agent to the status known by the controller
+pub fn status(status: agent::Status) -> Status {
+    match status {
+        agent::Status::Running => Status::Running,
+        agent::Status::Starting => Status::Starting,
+        agent::Status::Stopping => Status::Stopping,
+        agent::Status::Destroying => Status::Destroying,
+        agent::Status::Terminated => Status::Terminated,
+        // a crashed container may still be restarted by the agent, so it isn't failed yet
+        agent::Status::Crashed => Status::Stopped,
+        agent::Status::Failed => Status::Failed,
+        agent::Status::Scheduling => Status::Scheduling,
+        agent::Status::Scheduled => Status::Scheduled,
+        agent::Status::CrashLoopBackOff => Status::CrashLoopBackOff,
+    }
+}
+
+fn resource_summary(summary: agent::ResourceSummary) -> ResourceSummary {
+    ResourceSummary {
+        cpu: summary.cpu,
+        memory: summary.memory,
+        disk: summary.disk,
+    }
+}
+
+/// It converts an instance placed by the scheduler to the instance sent to its agent
+fn agent_instance(instance: Instance) -> agent::Instance {
+    let summary = |summary: ResourceSummary| agent::ResourceSummary {
+        cpu: summary.cpu,
+        memory: summary.memory,
+        disk: summary.disk,
+    };
+
+    agent::Instance {
+        id: instance.id,
+        name: instance.name,
+        r#type: instance.r#type,
+        // the instance is only sent to its agent once it is scheduled
+        status: agent::Status::Scheduled.into(),
+        uri: instance.uri,
+        environment: instance.environnement,
+        resource: instance.resource.map(|resource| agent::Resource {
+            limit: resource.limit.map(summary),
+            usage: resource.usage.map(summary),
+            request: resource.request.map(summary),
+        }),
+        ports: instance
+            .ports
+            .into_iter()
+            .map(|port| agent::Port {
+                source: port.source,
+                destination: port.destination,
+            })
+            .collect(),
+        ip: instance.ip,
+        restart_policy: instance.restart_policy,
+        liveness_probe: instance.liveness_probe.map(agent_probe),
+        readiness_probe: instance.readiness_probe.map(agent_probe),
+    }
+}
+
+fn agent_probe(probe: Probe) -> agent::Probe {
+    let action = probe.action.map(|action| match action {
+        probe::Action::HttpGet(action) => agent::probe::Action::HttpGet(agent::HttpGetAction {
+            path: action.path,
+            port: action.port,
+        }),
+        probe::Action::TcpSocket(action) => {
+            agent::probe::Action::TcpSocket(agent::TcpSocketAction { port: action.port })
+        }
+        probe::Action::Exec(action) => agent::probe::Action::Exec(agent::ExecAction {
+            command: action.command,
+        }),
+    });
+
+    agent::Probe {
+        action,
+        initial_delay_seconds: probe.initial_delay_seconds,
+        period_seconds: probe.period_seconds,
+        timeout_seconds: probe.timeout_seconds,
+        failure_threshold: probe.failure_threshold,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proto::scheduler::{HttpGetAction, Port, RestartPolicy};
+
+    #[test]
+    fn test_agent_instance() {
+        let instance = Instance {
+            id: "instance".to_string(),
+            uri: "docker.io/library/nginx:latest".to_string(),
+            environnement: vec!["A=1".to_string()],
+            ports: vec![Port {
+                source: 80,
+                destination: 8080,
+            }],
+            ip: "10.0.1.2".to_string(),
+            restart_policy: RestartPolicy::OnFailure.into(),
+            liveness_probe: Some(Probe {
+                action: Some(probe::Action::HttpGet(HttpGetAction {
+                    path: "/health".to_string(),
+                    port: 80,
+                })),
+                period_seconds: 10,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        let converted = agent_instance(instance);
+        assert_eq!(vec!["A=1".to_string()], converted.environment);
+        assert_eq!(8080, converted.ports[0].destination);
+        assert_eq!("10.0.1.2", converted.ip);
+        assert_eq!(agent::RestartPolicy::OnFailure, converted.restart_policy());
+        assert_eq!(agent::Status::Scheduled, converted.status());
+
+        let probe = converted.liveness_probe.unwrap();
+        assert_eq!(10, probe.period_seconds);
+        assert!(matches!(
+            probe.action,
+            Some(agent::probe::Action::HttpGet(agent::HttpGetAction {
+                port: 80,
+                ..
+            }))
+        ));
+    }
+
+    #[test]
+    fn test_instance_status() {
+        let reported = agent::InstanceStatus {
+            id: "instance".to_string(),
+            status: agent::Status::Crashed.into(),
+            description: "Exited with code 1".to_string(),
+            restart_count: 2,
+            ..Default::default()
+        };
+
+        let status = instance_status(reported);
+        assert_eq!(Status::Stopped, status.status());
+        assert_eq!("Exited with code 1", status.status_description);
+        assert_eq!(2, status.restart_count);
+        assert_eq!(Status::Failed, self::status(agent::Status::Failed));
+    }
+}
@@ -1,15 +1,98 @@
+use std::collections::HashMap;
+
 use serde_derive::{Deserialize, Serialize};
 
+use crate::persistence::StateBackendKind;
+use crate::placement::{self, Overcommit, PlacementStrategy};
+
 /// `Config` is a struct that contains the configuration of the scheduler.
 ///
 /// Properties:
 ///
 /// * `host`: The hostname or IP address of the gRPC server.
 /// * `port`: The port that the gRPC server will listen on.
+/// * `shutdown_timeout`: The seconds given to the requests in flight to complete on shutdown.
+/// * `placement_strategy`: How the capacity plugin scores the nodes, `bin-pack` or `spread`.
+/// * `cluster_cidr`: The CIDR the subnets of the nodes are taken from.
+/// * `node_subnet_length`: The network length of the subnet of each node.
+/// * `node_heartbeat_timeout`: The seconds without status after which a node is failed.
+/// * `reschedule_grace_period`: The seconds the instances of a failed node are given for the
+///   node to come back before they are rescheduled.
+/// * `pending_timeout`: The seconds an instance waits for a node with enough free capacity
+///   before it is failed, it waits as long as needed if not set.
+/// * `state_backend`: Where the nodes and the placements are saved to survive a restart, `file`
+///   or `memory`.
+/// * `state_path`: The directory the `file` backend saves the state to.
+/// * `state_snapshot_interval`: The number of changes logged after which a snapshot of the
+///   state is taken.
+/// * `event_timeout`: The seconds after which an event still being handled fails its request.
+/// * `plugin_weights`: The weights of the score plugins by name, `capacity`, `labels` and
+///   `spread`. A plugin with a weight of 0 doesn't score the nodes, one without weight has a
+///   weight of 1.
+/// * `overcommit`: The share of the capacity of each node the instances can request, by
+///   resource, `cpu`, `memory` and `disk`. A ratio of 2 allows requesting twice the capacity.
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Config {
     pub host: String,
     pub port: u16,
+    #[serde(default = "default_shutdown_timeout")]
+    pub shutdown_timeout: u64,
+    #[serde(default)]
+    pub placement_strategy: PlacementStrategy,
+    #[serde(default = "default_cluster_cidr")]
+    pub cluster_cidr: String,
+    #[serde(default = "default_node_subnet_length")]
+    pub node_subnet_length: u8,
+    #[serde(default = "default_node_heartbeat_timeout")]
+    pub node_heartbeat_timeout: u64,
+    #[serde(default = "default_reschedule_grace_period")]
+    pub reschedule_grace_period: u64,
+    #[serde(default)]
+    pub pending_timeout: Option<u64>,
+    #[serde(default)]
+    pub state_backend: StateBackendKind,
+    #[serde(default = "default_state_path")]
+    pub state_path: String,
+    #[serde(default = "default_state_snapshot_interval")]
+    pub state_snapshot_interval: u64,
+    #[serde(default = "default_event_timeout")]
+    pub event_timeout: u64,
+    #[serde(default = "placement::default_plugin_weights")]
+    pub plugin_weights: HashMap<String, u32>,
+    #[serde(default)]
+    pub overcommit: Overcommit,
+}
+
+fn default_cluster_cidr() -> String {
+    "10.0.0.0/16".to_string()
+}
+
+fn default_node_subnet_length() -> u8 {
+    24
+}
+
+fn default_state_path() -> String {
+    "state".to_string()
+}
+
+fn default_state_snapshot_interval() -> u64 {
+    1000
+}
+
+fn default_event_timeout() -> u64 {
+    10
+}
+
+fn default_node_heartbeat_timeout() -> u64 {
+    15
+}
+
+fn default_reschedule_grace_period() -> u64 {
+    30
+}
+
+fn default_shutdown_timeout() -> u64 {
+    30
 }
 
 impl Default for Config {
@@ -17,6 +100,19 @@ impl Default for Config {
         Config {
             host: "127.0.0.1".to_string(),
             port: 50052,
+            shutdown_timeout: default_shutdown_timeout(),
+            placement_strategy: PlacementStrategy::default(),
+            cluster_cidr: default_cluster_cidr(),
+            node_subnet_length: default_node_subnet_length(),
+            node_heartbeat_timeout: default_node_heartbeat_timeout(),
+            reschedule_grace_period: default_reschedule_grace_period(),
+            pending_timeout: None,
+            state_backend: StateBackendKind::default(),
+            state_path: default_state_path(),
+            state_snapshot_interval: default_state_snapshot_interval(),
+            event_timeout: default_event_timeout(),
+            plugin_weights: placement::default_plugin_weights(),
+            overcommit: Overcommit::default(),
         }
     }
 }
@@ -0,0 +1,105 @@
+use std::collections::HashMap;
+
+use proto::scheduler::{node_selector_requirement::Operator, Instance, NodeSelectorTerm, Taint};
+
+use crate::storage::Storage;
+use crate::PlacedInstance;
+
+/// It returns true if the labels match all the requirements of the term
+pub fn matches(labels: &HashMap<String, String>, term: &NodeSelectorTerm) -> bool {
+    term.requirements.iter().all(|requirement| {
+        let value = labels.get(&requirement.key);
+        match requirement.operator() {
+            Operator::In => value.is_some_and(|value| requirement.values.contains(value)),
+            Operator::NotIn => !value.is_some_and(|value| requirement.values.contains(value)),
+            Operator::Exists => value.is_some(),
+            Operator::DoesNotExist => value.is_none(),
+        }
+    })
+}
+
+/// It returns true if one of the tolerations of the instance tolerates the taint
+pub fn tolerates(instance: &Instance, taint: &Taint) -> bool {
+    instance.tolerations.iter().any(|toleration| {
+        toleration.key == taint.key
+            && (toleration.value.is_empty() || toleration.value == taint.value)
+            && (toleration.effects.is_empty()
+                || toleration.effects().any(|effect| effect == taint.effect()))
+    })
+}
+
+/// It returns the number of instances of the workload of the instance placed on a node
+pub fn siblings(instance: &Instance, node_id: &str, instances: &Storage<PlacedInstance>) -> usize {
+    if instance.workload.is_empty() {
+        return 0;
+    }
+
+    PlacedInstance::on_node(node_id, instances)
+        .into_iter()
+        .filter(|placed| {
+            placed.instance.id != instance.id && placed.instance.workload == instance.workload
+        })
+        .count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proto::scheduler::{NodeSelectorRequirement, TaintEffect, Toleration};
+
+    fn term(key: &str, operator: Operator, values: &[&str]) -> NodeSelectorTerm {
+        NodeSelectorTerm {
+            requirements: vec![NodeSelectorRequirement {
+                key: key.to_string(),
+                operator: operator.into(),
+                values: values.iter().map(|value| value.to_string()).collect(),
+            }],
+        }
+    }
+
+    #[test]
+    fn test_matches_operators() {
+        let labels = HashMap::from([("zone".to_string(), "eu-west".to_string())]);
+
+        assert!(matches(&labels, &term("zone", Operator::In, &["eu-west"])));
+        assert!(!matches(
+            &labels,
+            &term("zone", Operator::NotIn, &["eu-west"])
+        ));
+        assert!(matches(&labels, &term("disk", Operator::NotIn, &["ssd"])));
+        assert!(matches(&labels, &term("zone", Operator::Exists, &[])));
+        assert!(!matches(
+            &labels,
+            &term("zone", Operator::DoesNotExist, &[])
+        ));
+    }
+
+    #[test]
+    fn test_tolerates() {
+        let taint = Taint {
+            key: "gpu".to_string(),
+            value: "a100".to_string(),
+            effect: TaintEffect::NoSchedule.into(),
+        };
+        let instance = |value: &str, effects: Vec<TaintEffect>| Instance {
+            tolerations: vec![Toleration {
+                key: "gpu".to_string(),
+                value: value.to_string(),
+                effects: effects.into_iter().map(Into::into).collect(),
+            }],
+            ..Default::default()
+        };
+
+        assert!(tolerates(&instance("", vec![]), &taint));
+        assert!(tolerates(
+            &instance("a100", vec![TaintEffect::NoSchedule]),
+            &taint
+        ));
+        assert!(!tolerates(&instance("t4", vec![]), &taint));
+        assert!(!tolerates(
+            &instance("", vec![TaintEffect::PreferNoSchedule]),
+            &taint
+        ));
+        assert!(!tolerates(&Instance::default(), &taint));
+    }
+}
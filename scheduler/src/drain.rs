@@ -0,0 +1,232 @@
+use std::collections::{BTreeMap, HashMap};
+
+use proto::scheduler::{DrainState, Instance, NodeDrainStatus, Status};
+
+use crate::storage::{IStorage, Storage};
+use crate::PlacedInstance;
+
+/// `Drain` is the progress of moving the instances of a cordoned node to the other nodes.
+///
+/// Properties:
+///
+/// * `max_unavailable`: The instances of a workload moved at the same time.
+/// * `moving`: The workload of each instance moved which isn't running on another node yet,
+///   by instance id.
+/// * `moved`: The number of instances running on another node.
+/// * `evicted`: The number of instances no other node could run, put in the pending queue.
+#[derive(Debug, Clone, Default)]
+pub struct Drain {
+    pub max_unavailable: u32,
+    pub moving: BTreeMap<String, String>,
+    pub moved: u32,
+    pub evicted: u32,
+}
+
+/// It returns the workload an instance counts against in the budget, the instances without
+/// workload being their own workload
+fn workload_of(instance: &Instance) -> &str {
+    match instance.workload.is_empty() {
+        true => &instance.id,
+        false => &instance.workload,
+    }
+}
+
+impl Drain {
+    /// `new` creates a drain moving at most `max_unavailable` instances of a workload at the
+    /// same time, at least one
+    pub fn new(max_unavailable: u32) -> Self {
+        Drain {
+            max_unavailable: max_unavailable.max(1),
+            ..Default::default()
+        }
+    }
+
+    /// It settles the moves in flight: an instance running on another node is moved, an
+    /// instance which is neither placed nor pending anymore, e.g. because it was destroyed or
+    /// failed, no longer counts against the budget.
+    ///
+    /// Arguments:
+    ///
+    /// * `node_id`: The id of the drained node.
+    /// * `instances`: The instances placed on the nodes.
+    /// * `pending`: It returns true if an instance is waiting in the pending queue.
+    pub fn settle(
+        &mut self,
+        node_id: &str,
+        instances: &Storage<PlacedInstance>,
+        pending: impl Fn(&str) -> bool,
+    ) {
+        let mut moved = 0;
+        self.moving.retain(|id, _| match instances.get(id) {
+            Some(placed) => {
+                let running =
+                    placed.node_id != node_id && placed.instance.status() == Status::Running;
+                if running {
+                    moved += 1;
+                }
+                !running
+            }
+            None => pending(id),
+        });
+        self.moved += moved;
+    }
+
+    /// It chooses the instances of the drained node to move next, so that at most
+    /// `max_unavailable` instances of each workload are moving at the same time, and records
+    /// them as moving.
+    ///
+    /// Arguments:
+    ///
+    /// * `remaining`: The instances still placed on the drained node.
+    ///
+    /// Returns:
+    ///
+    /// The instances to move, by workload then id.
+    pub fn next(&mut self, remaining: &[&PlacedInstance]) -> Vec<PlacedInstance> {
+        let mut unavailable: HashMap<&str, u32> = HashMap::new();
+        for workload in self.moving.values() {
+            *unavailable.entry(workload).or_default() += 1;
+        }
+
+        let mut candidates: Vec<&PlacedInstance> = remaining
+            .iter()
+            .filter(|placed| !self.moving.contains_key(&placed.instance.id))
+            .copied()
+            .collect();
+        candidates.sort_by(|placed, other| {
+            workload_of(&placed.instance)
+                .cmp(workload_of(&other.instance))
+                .then_with(|| placed.instance.id.cmp(&other.instance.id))
+        });
+
+        let mut chosen = Vec::new();
+        for placed in candidates {
+            let count = unavailable
+                .entry(workload_of(&placed.instance))
+                .or_default();
+            if *count < self.max_unavailable {
+                *count += 1;
+                chosen.push(placed.clone());
+            }
+        }
+        for placed in &chosen {
+            self.moving.insert(
+                placed.instance.id.clone(),
+                workload_of(&placed.instance).to_string(),
+            );
+        }
+        chosen
+    }
+
+    /// It returns the status of the drain of a node
+    ///
+    /// Arguments:
+    ///
+    /// * `node_id`: The id of the drained node.
+    /// * `remaining`: The number of instances still placed on the node.
+    pub fn status(&self, node_id: &str, remaining: usize) -> NodeDrainStatus {
+        let drained = remaining == 0 && self.moving.is_empty();
+        let state = match drained {
+            true => DrainState::Drained,
+            false => DrainState::Draining,
+        };
+        let description = match drained {
+            true => format!("node {} drained", node_id),
+            false => format!(
+                "draining node {}: {} instance(s) left, {} moving",
+                node_id,
+                remaining,
+                self.moving.len()
+            ),
+        };
+
+        NodeDrainStatus {
+            id: node_id.to_string(),
+            state: state.into(),
+            remaining: remaining as u32,
+            moving: self.moving.len() as u32,
+            moved: self.moved,
+            evicted: self.evicted,
+            description,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support;
+
+    fn placed(id: &str, workload: &str, node_id: &str, status: Status) -> PlacedInstance {
+        let mut placed = test_support::placed(id, node_id);
+        placed.instance.workload = workload.to_string();
+        placed.instance.status = status.into();
+        placed
+    }
+
+    fn ids(chosen: &[PlacedInstance]) -> Vec<&str> {
+        chosen
+            .iter()
+            .map(|placed| placed.instance.id.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn test_next_respects_budget() {
+        let web_1 = placed("web-1", "default/web", "a", Status::Running);
+        let web_2 = placed("web-2", "default/web", "a", Status::Running);
+        let web_3 = placed("web-3", "default/web", "a", Status::Running);
+        let db = placed("db-1", "default/db", "a", Status::Running);
+        let single = placed("single", "", "a", Status::Running);
+        let remaining = vec![&web_1, &web_2, &web_3, &db, &single];
+
+        let mut drain = Drain::new(2);
+        assert_eq!(
+            vec!["db-1", "web-1", "web-2", "single"],
+            ids(&drain.next(&remaining))
+        );
+        // the moves in flight use up the budget
+        assert!(drain.next(&remaining).is_empty());
+
+        // web-1 runs on another node
+        drain.moving.remove("web-1");
+        let remaining = vec![&web_2, &web_3, &db, &single];
+        assert_eq!(vec!["web-3"], ids(&drain.next(&remaining)));
+    }
+
+    #[test]
+    fn test_next_moves_one_at_a_time_by_default() {
+        let web_1 = placed("web-1", "default/web", "a", Status::Running);
+        let web_2 = placed("web-2", "default/web", "a", Status::Running);
+
+        let mut drain = Drain::new(0);
+        assert_eq!(1, drain.max_unavailable);
+        assert_eq!(vec!["web-1"], ids(&drain.next(&[&web_1, &web_2])));
+    }
+
+    #[test]
+    fn test_settle() {
+        let mut instances = PlacedInstance::storage();
+        instances.update("web-1", placed("web-1", "web", "b", Status::Running));
+        instances.update("web-2", placed("web-2", "web", "b", Status::Scheduled));
+        instances.update("web-3", placed("web-3", "web", "a", Status::Running));
+
+        let mut drain = Drain::new(10);
+        for id in ["web-1", "web-2", "web-3", "web-4", "web-5"] {
+            drain.moving.insert(id.to_string(), "web".to_string());
+        }
+        // web-4 is pending and web-5 was destroyed
+        drain.settle("a", &instances, |id| id == "web-4");
+
+        assert_eq!(1, drain.moved);
+        assert_eq!(
+            vec!["web-2", "web-3", "web-4"],
+            drain.moving.keys().map(String::as_str).collect::<Vec<_>>()
+        );
+
+        let status = drain.status("a", 1);
+        assert_eq!(DrainState::Draining, status.state());
+        assert_eq!(3, status.moving);
+        assert_eq!(DrainState::Drained, Drain::new(1).status("a", 0).state());
+    }
+}
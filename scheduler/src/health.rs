@@ -0,0 +1,163 @@
+use std::time::{Duration, Instant};
+
+use log::warn;
+use proto::scheduler::Status;
+
+use crate::storage::{IStorage, Storage};
+use crate::{Node, PlacedInstance};
+
+/// `HealthCheck` is what changed since the last check of the nodes.
+///
+/// Properties:
+///
+/// * `lost`: The instances whose node just failed.
+/// * `recovered`: The lost instances whose node sends its status again.
+/// * `expired`: The instances lost for longer than the grace period, to reschedule.
+#[derive(Debug, Default)]
+pub struct HealthCheck {
+    pub lost: Vec<PlacedInstance>,
+    pub recovered: Vec<PlacedInstance>,
+    pub expired: Vec<PlacedInstance>,
+}
+
+/// It marks the nodes which haven't sent their status for `heartbeat_timeout` as failed, and
+/// their instances as lost. A node which sends its status again is no longer failed, so its
+/// lost instances are kept on it as long as they haven't been rescheduled.
+///
+/// Arguments:
+///
+/// * `nodes`: The nodes of the cluster.
+/// * `instances`: The instances placed on the nodes.
+/// * `now`: The time of the check.
+/// * `heartbeat_timeout`: Time without status after which a node is failed.
+/// * `grace_period`: Time after which a lost instance is rescheduled.
+///
+/// Returns:
+///
+/// The instances which were lost, recovered, or must be rescheduled.
+pub fn check(
+    nodes: &mut Storage<Node>,
+    instances: &mut Storage<PlacedInstance>,
+    now: Instant,
+    heartbeat_timeout: Duration,
+    grace_period: Duration,
+) -> HealthCheck {
+    let silent: Vec<String> = nodes
+        .get_all()
+        .values()
+        .filter(|node| {
+            node.status != Status::Failed
+                && now.saturating_duration_since(node.last_seen) >= heartbeat_timeout
+        })
+        .map(|node| node.id.clone())
+        .collect();
+    for id in silent {
+        if let Some(node) = nodes.get_mut(&id) {
+            warn!(
+                "node {} failed, no status for {} second(s)",
+                id,
+                now.saturating_duration_since(node.last_seen).as_secs()
+            );
+            node.status = Status::Failed;
+        }
+    }
+
+    let mut result = HealthCheck::default();
+    let ids: Vec<String> = instances.get_all().keys().cloned().collect();
+    for id in ids {
+        let placed = match instances.get_mut(&id) {
+            Some(placed) => placed,
+            None => continue,
+        };
+        // an unregistered node is gone for good
+        let healthy = nodes
+            .get(&placed.node_id)
+            .is_some_and(|node| node.status != Status::Failed);
+
+        match placed.lost_since {
+            None if !healthy => {
+                placed.lost_since = Some(now);
+                result.lost.push(placed.clone());
+            }
+            Some(_) if healthy => {
+                placed.lost_since = None;
+                result.recovered.push(placed.clone());
+            }
+            Some(since) if now.saturating_duration_since(since) >= grace_period => {
+                result.expired.push(placed.clone());
+            }
+            _ => {}
+        }
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{self, placed};
+
+    const TIMEOUT: Duration = Duration::from_secs(15);
+    const GRACE: Duration = Duration::from_secs(30);
+
+    fn node(id: &str, last_seen: Instant) -> Node {
+        Node {
+            last_seen,
+            ..test_support::node(id)
+        }
+    }
+
+    fn cluster(start: Instant) -> (Storage<Node>, Storage<PlacedInstance>) {
+        let mut nodes = Storage::new();
+        nodes.update("a", node("a", start));
+        nodes.update("b", node("b", start));
+
+        let mut instances = Storage::new();
+        instances.update("on-a", placed("on-a", "a"));
+        instances.update("on-b", placed("on-b", "b"));
+        (nodes, instances)
+    }
+
+    #[test]
+    fn test_silent_node_loses_its_instances() {
+        let start = Instant::now();
+        let (mut nodes, mut instances) = cluster(start);
+        nodes.get_mut("b").unwrap().last_seen = start + TIMEOUT + GRACE;
+
+        let now = start + TIMEOUT;
+        let result = check(&mut nodes, &mut instances, now, TIMEOUT, GRACE);
+        assert_eq!(Status::Failed, nodes.get("a").unwrap().status);
+        assert_eq!(Status::Running, nodes.get("b").unwrap().status);
+        assert_eq!(1, result.lost.len());
+        assert_eq!("on-a", result.lost[0].instance.id);
+        assert!(result.expired.is_empty());
+
+        // the instance is only rescheduled once the grace period is over
+        let result = check(&mut nodes, &mut instances, now + GRACE / 2, TIMEOUT, GRACE);
+        assert!(result.lost.is_empty() && result.expired.is_empty());
+        let result = check(&mut nodes, &mut instances, now + GRACE, TIMEOUT, GRACE);
+        assert_eq!(1, result.expired.len());
+        assert_eq!("on-a", result.expired[0].instance.id);
+    }
+
+    #[test]
+    fn test_node_back_within_grace_period_keeps_its_instances() {
+        let start = Instant::now();
+        let (mut nodes, mut instances) = cluster(start);
+
+        let now = start + TIMEOUT;
+        let result = check(&mut nodes, &mut instances, now, TIMEOUT, GRACE);
+        assert_eq!(2, result.lost.len());
+
+        let node = nodes.get_mut("a").unwrap();
+        node.status = Status::Running;
+        node.last_seen = now + GRACE;
+        let result = check(&mut nodes, &mut instances, now + GRACE, TIMEOUT, GRACE);
+        assert_eq!(1, result.recovered.len());
+        assert_eq!("on-a", result.recovered[0].instance.id);
+        assert_eq!(1, result.expired.len());
+        assert_eq!("on-b", result.expired[0].instance.id);
+        assert_eq!(None, instances.get("on-a").unwrap().lost_since);
+    }
+}
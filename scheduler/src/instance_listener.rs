@@ -4,7 +4,9 @@ use tokio_stream::wrappers::ReceiverStream;
 use tonic::{Request, Response, Status};
 
 use proto::scheduler::{
-    instance_service_server::InstanceService, Instance, InstanceIdentifier, InstanceStatus,
+    instance_service_server::InstanceService, DryRunRequest, DryRunResponse, Instance,
+    InstanceIdentifier, InstanceListRequest, InstanceListResponse, InstanceStatus,
+    PendingListResponse, PlacementExplanation,
 };
 
 use crate::{manager::Manager, Event};
@@ -31,7 +33,7 @@ impl InstanceService for InstanceListener {
 
         match self
             .sender
-            .send(Event::InstanceCreate(request.into_inner(), tx))
+            .send(Event::InstanceCreate(Box::new(request.into_inner()), tx))
             .await
         {
             Ok(_) => {
@@ -45,6 +47,29 @@ impl InstanceService for InstanceListener {
 
     type CreateStream = ReceiverStream<Result<InstanceStatus, Status>>;
 
+    async fn watch(
+        &self,
+        request: Request<InstanceIdentifier>,
+    ) -> Result<Response<Self::WatchStream>, Status> {
+        debug!("received request: {:?}", request);
+        let (tx, rx) = Manager::create_mpsc_channel();
+
+        match self
+            .sender
+            .send(Event::InstanceWatch(request.into_inner().id, tx))
+            .await
+        {
+            Ok(_) => {
+                return Ok(Response::new(ReceiverStream::new(rx)));
+            }
+            Err(_) => {
+                return Err(Status::internal("could not send event to manager"));
+            }
+        }
+    }
+
+    type WatchStream = ReceiverStream<Result<InstanceStatus, Status>>;
+
     async fn start(&self, request: Request<InstanceIdentifier>) -> Result<Response<()>, Status> {
         debug!("received request: {:?}", request);
         let (tx, rx) = Manager::create_oneshot_channel();
@@ -55,7 +80,7 @@ impl InstanceService for InstanceListener {
             .await
         {
             Ok(_) => {
-                return rx.await.unwrap();
+                return Manager::wait_reply(rx).await;
             }
             Err(_) => {
                 return Err(Status::internal("could not send event to manager"));
@@ -73,7 +98,7 @@ impl InstanceService for InstanceListener {
             .await
         {
             Ok(_) => {
-                return rx.await.unwrap();
+                return Manager::wait_reply(rx).await;
             }
             Err(_) => {
                 return Err(Status::internal("could not send event to manager"));
@@ -91,7 +116,84 @@ impl InstanceService for InstanceListener {
             .await
         {
             Ok(_) => {
-                return rx.await.unwrap();
+                return Manager::wait_reply(rx).await;
+            }
+            Err(_) => {
+                return Err(Status::internal("could not send event to manager"));
+            }
+        }
+    }
+
+    async fn list(
+        &self,
+        request: Request<InstanceListRequest>,
+    ) -> Result<Response<InstanceListResponse>, Status> {
+        debug!("received request: {:?}", request);
+        let (tx, rx) = Manager::create_oneshot_channel();
+
+        match self
+            .sender
+            .send(Event::InstanceList(request.into_inner(), tx))
+            .await
+        {
+            Ok(_) => {
+                return Manager::wait_reply(rx).await;
+            }
+            Err(_) => {
+                return Err(Status::internal("could not send event to manager"));
+            }
+        }
+    }
+
+    async fn pending(&self, request: Request<()>) -> Result<Response<PendingListResponse>, Status> {
+        debug!("received request: {:?}", request);
+        let (tx, rx) = Manager::create_oneshot_channel();
+
+        match self.sender.send(Event::InstancePending(tx)).await {
+            Ok(_) => {
+                return Manager::wait_reply(rx).await;
+            }
+            Err(_) => {
+                return Err(Status::internal("could not send event to manager"));
+            }
+        }
+    }
+
+    async fn explain(
+        &self,
+        request: Request<InstanceIdentifier>,
+    ) -> Result<Response<PlacementExplanation>, Status> {
+        debug!("received request: {:?}", request);
+        let (tx, rx) = Manager::create_oneshot_channel();
+
+        match self
+            .sender
+            .send(Event::InstanceExplain(request.into_inner().id, tx))
+            .await
+        {
+            Ok(_) => {
+                return Manager::wait_reply(rx).await;
+            }
+            Err(_) => {
+                return Err(Status::internal("could not send event to manager"));
+            }
+        }
+    }
+
+    async fn dry_run(
+        &self,
+        request: Request<DryRunRequest>,
+    ) -> Result<Response<DryRunResponse>, Status> {
+        debug!("received request: {:?}", request);
+        let (tx, rx) = Manager::create_oneshot_channel();
+
+        match self
+            .sender
+            .send(Event::InstanceDryRun(request.into_inner(), tx))
+            .await
+        {
+            Ok(_) => {
+                return Manager::wait_reply(rx).await;
             }
             Err(_) => {
                 return Err(Status::internal("could not send event to manager"));
@@ -0,0 +1,275 @@
+use std::collections::{BTreeMap, HashMap};
+use std::net::Ipv4Addr;
+
+use cidr::{Ipv4Cidr, Ipv4Inet};
+use serde_derive::{Deserialize, Serialize};
+
+use crate::{NodeIdentifier, SchedulerError};
+
+/// `Pool` holds the addresses given to the instances of a node.
+///
+/// Properties:
+///
+/// * `subnet`: The subnet of the node, the addresses are taken from.
+/// * `addresses`: The id of the instance each address is given to.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct Pool {
+    subnet: Ipv4Cidr,
+    addresses: BTreeMap<Ipv4Addr, String>,
+}
+
+impl Pool {
+    /// It returns true if the address can't be given to an instance: the network address,
+    /// the address of the bridge of the node, which is the first host, and the broadcast
+    /// address.
+    fn is_reserved(&self, address: Ipv4Addr) -> bool {
+        let first = u32::from(self.subnet.first_address());
+        let last = u32::from(self.subnet.last_address());
+        let address = u32::from(address);
+
+        address == first || address == first + 1 || address == last
+    }
+}
+
+/// `Ipam` hands out the addresses of the instances, from the subnet of the node each instance
+/// is placed on. An address is never given to two instances, and is released when its
+/// instance is destroyed. The allocations aren't saved on their own: the address of each
+/// instance is saved along with its placement, and restored from there after a restart.
+///
+/// Properties:
+///
+/// * `pools`: The addresses given on each node.
+#[derive(Debug, Default)]
+pub struct Ipam {
+    pools: HashMap<NodeIdentifier, Pool>,
+}
+
+impl Ipam {
+    pub fn new() -> Self {
+        Ipam::default()
+    }
+
+    /// It creates the pool of a node, keeping the allocations if it already exists
+    pub fn add_node(&mut self, node_id: &str, subnet: Ipv4Cidr) {
+        if self
+            .pools
+            .get(node_id)
+            .is_some_and(|pool| pool.subnet == subnet)
+        {
+            return;
+        }
+        self.pools.insert(
+            node_id.to_string(),
+            Pool {
+                subnet,
+                addresses: BTreeMap::new(),
+            },
+        );
+    }
+
+    /// It removes the pool of a node, along with its allocations
+    pub fn remove_node(&mut self, node_id: &str) {
+        self.pools.remove(node_id);
+    }
+
+    /// It gives back to an instance the address it was given before a restart.
+    ///
+    /// Arguments:
+    ///
+    /// * `node_id`: The node the instance is placed on.
+    /// * `instance_id`: The id of the instance.
+    /// * `address`: The address of the instance.
+    ///
+    /// Returns:
+    ///
+    /// An error if the address can't be given to an instance of the node, or is given to
+    /// another instance.
+    pub fn restore(
+        &mut self,
+        node_id: &str,
+        instance_id: &str,
+        address: Ipv4Addr,
+    ) -> Result<(), SchedulerError> {
+        let pool = self
+            .pools
+            .get_mut(node_id)
+            .ok_or_else(|| SchedulerError::NodeNotRegistered(node_id.to_string()))?;
+        if !pool.subnet.contains(&address) || pool.is_reserved(address) {
+            return Err(SchedulerError::InvalidAddress(address, node_id.to_string()));
+        }
+        match pool.addresses.get(&address) {
+            Some(id) if id != instance_id => {
+                Err(SchedulerError::InvalidAddress(address, node_id.to_string()))
+            }
+            _ => {
+                pool.addresses.insert(address, instance_id.to_string());
+                Ok(())
+            }
+        }
+    }
+
+    /// It gives an address of the subnet of a node to an instance. An instance which already
+    /// has an address on this node keeps it.
+    ///
+    /// Arguments:
+    ///
+    /// * `node_id`: The node the instance is placed on.
+    /// * `instance_id`: The id of the instance.
+    ///
+    /// Returns:
+    ///
+    /// The address of the instance along with the length of the subnet.
+    pub fn allocate(
+        &mut self,
+        node_id: &str,
+        instance_id: &str,
+    ) -> Result<Ipv4Inet, SchedulerError> {
+        let pool = self
+            .pools
+            .get_mut(node_id)
+            .ok_or_else(|| SchedulerError::NodeNotRegistered(node_id.to_string()))?;
+        let length = pool.subnet.network_length();
+
+        if let Some((address, _)) = pool.addresses.iter().find(|(_, id)| *id == instance_id) {
+            return Ok(Ipv4Inet::new(*address, length).unwrap());
+        }
+
+        let address = pool
+            .subnet
+            .iter()
+            .map(|inet| inet.address())
+            .find(|address| !pool.is_reserved(*address) && !pool.addresses.contains_key(address))
+            .ok_or_else(|| SchedulerError::AddressPoolExhausted(node_id.to_string()))?;
+        pool.addresses.insert(address, instance_id.to_string());
+        Ok(Ipv4Inet::new(address, length).unwrap())
+    }
+
+    /// It releases the address of an instance, if it has one
+    pub fn release(&mut self, instance_id: &str) {
+        for pool in self.pools.values_mut() {
+            pool.addresses.retain(|_, id| id != instance_id);
+        }
+    }
+
+    /// It releases the address of an instance on a node, keeping its addresses on other nodes
+    pub fn release_on(&mut self, node_id: &str, instance_id: &str) {
+        if let Some(pool) = self.pools.get_mut(node_id) {
+            pool.addresses.retain(|_, id| id != instance_id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn subnet() -> Ipv4Cidr {
+        "10.0.1.0/29".parse().unwrap()
+    }
+
+    #[test]
+    fn test_allocate_skips_reserved_and_used_addresses() {
+        let mut ipam = Ipam::new();
+        ipam.add_node("node", subnet());
+
+        let addresses: Vec<String> = ["a", "b", "c", "d", "e"]
+            .iter()
+            .map(|id| ipam.allocate("node", id).unwrap().to_string())
+            .collect();
+        assert_eq!(
+            vec![
+                "10.0.1.2/29",
+                "10.0.1.3/29",
+                "10.0.1.4/29",
+                "10.0.1.5/29",
+                "10.0.1.6/29"
+            ],
+            addresses
+        );
+        assert_eq!(
+            "10.0.1.2/29",
+            ipam.allocate("node", "a").unwrap().to_string()
+        );
+        assert!(matches!(
+            ipam.allocate("node", "f"),
+            Err(SchedulerError::AddressPoolExhausted(_))
+        ));
+
+        ipam.release("c");
+        assert_eq!(
+            "10.0.1.4/29",
+            ipam.allocate("node", "f").unwrap().to_string()
+        );
+    }
+
+    #[test]
+    fn test_allocate_on_unknown_node() {
+        let mut ipam = Ipam::new();
+        assert!(matches!(
+            ipam.allocate("node", "a"),
+            Err(SchedulerError::NodeNotRegistered(_))
+        ));
+    }
+
+    #[test]
+    fn test_release_on_keeps_other_nodes() {
+        let mut ipam = Ipam::new();
+        ipam.add_node("old", subnet());
+        ipam.add_node("new", "10.0.2.0/29".parse().unwrap());
+        ipam.allocate("old", "a").unwrap();
+        ipam.allocate("new", "a").unwrap();
+
+        ipam.release_on("old", "a");
+        assert_eq!(
+            "10.0.1.2/29",
+            ipam.allocate("old", "b").unwrap().to_string()
+        );
+        assert_eq!(
+            "10.0.2.2/29",
+            ipam.allocate("new", "a").unwrap().to_string()
+        );
+    }
+
+    #[test]
+    fn test_restored_addresses_stay_in_use() {
+        let mut ipam = Ipam::new();
+        ipam.add_node("node", subnet());
+
+        ipam.restore("node", "a", "10.0.1.3".parse().unwrap())
+            .unwrap();
+        // restoring twice is harmless
+        ipam.restore("node", "a", "10.0.1.3".parse().unwrap())
+            .unwrap();
+        assert_eq!(
+            "10.0.1.3/29",
+            ipam.allocate("node", "a").unwrap().to_string()
+        );
+        assert_eq!(
+            "10.0.1.2/29",
+            ipam.allocate("node", "b").unwrap().to_string()
+        );
+        assert_eq!(
+            "10.0.1.4/29",
+            ipam.allocate("node", "c").unwrap().to_string()
+        );
+    }
+
+    #[test]
+    fn test_restore_invalid_addresses() {
+        let mut ipam = Ipam::new();
+        ipam.add_node("node", subnet());
+        ipam.restore("node", "a", "10.0.1.2".parse().unwrap())
+            .unwrap();
+
+        for address in ["10.0.1.2", "10.0.1.1", "10.0.1.7", "10.0.2.2"] {
+            assert!(matches!(
+                ipam.restore("node", "b", address.parse().unwrap()),
+                Err(SchedulerError::InvalidAddress(_, _))
+            ));
+        }
+        assert!(matches!(
+            ipam.restore("other", "b", "10.0.1.3".parse().unwrap()),
+            Err(SchedulerError::NodeNotRegistered(_))
+        ));
+    }
+}
@@ -1,16 +1,40 @@
+use drain::Drain;
 use proto::scheduler::{
-    Instance, InstanceStatus, NodeRegisterRequest, NodeRegisterResponse, NodeStatus,
-    NodeUnregisterRequest, NodeUnregisterResponse,
+    DryRunRequest, DryRunResponse, Instance, InstanceListRequest, InstanceListResponse,
+    InstanceStatus, NodeCordonRequest, NodeCordonResponse, NodeDrainRequest, NodeDrainStatus,
+    NodeInfo, NodeListResponse, NodeRegisterRequest, NodeRegisterResponse, NodeStatus,
+    NodeUnregisterRequest, NodeUnregisterResponse, NodeUpdateRequest, NodeUpdateResponse,
+    PendingListResponse, PlacementExplanation, Resource, ResourceSummary, Status, Taint,
 };
+use std::collections::HashMap;
+use std::time::Instant;
+
+use cidr::Ipv4Cidr;
+use storage::{IStorage, Storage};
 use thiserror::Error;
 use tokio::sync::{mpsc, oneshot};
 use tonic::Response;
 
+pub mod agent_client;
 pub mod config;
+pub mod constraints;
+pub mod drain;
+pub mod health;
 pub mod instance_listener;
+pub mod ipam;
 pub mod manager;
 pub mod node_listener;
+pub mod pending;
+pub mod persistence;
+pub mod placement;
+pub mod plugins;
+pub mod preemption;
+pub mod query;
+pub mod simulation;
 pub mod storage;
+pub mod subnet;
+#[cfg(test)]
+pub(crate) mod test_support;
 
 #[derive(Error, Debug)]
 pub enum SchedulerError {
@@ -20,27 +44,158 @@ pub enum SchedulerError {
     ConfigReadError(#[from] confy::ConfyError),
     #[error("invalid grpc address in configuration file")]
     InvalidGrpcAddress,
+    #[error("invalid cluster cidr in configuration file: {0}")]
+    InvalidClusterCidr(String),
+    #[error("node {0} is not registered")]
+    NodeNotRegistered(String),
+    #[error("invalid node agent address: {0}")]
+    InvalidNodeAddress(String),
+    #[error("node {0} failed the request: {1}")]
+    AgentRequest(String, String),
+    #[error("instance {0} is not scheduled")]
+    InstanceNotFound(String),
+    #[error("no subnet left in the cluster cidr")]
+    NoSubnetLeft,
+    #[error("unknown scheduling plugin in configuration file: {0}")]
+    UnknownPlugin(String),
+    #[error("invalid overcommit in configuration file: {0}")]
+    InvalidOvercommit(String),
+    #[error("no node can run the instance: {0}")]
+    Unschedulable(String),
+    #[error("no address left in the subnet of node {0}")]
+    AddressPoolExhausted(String),
+    #[error("address {0} can't be given back to an instance of node {1}")]
+    InvalidAddress(std::net::Ipv4Addr, String),
+    #[error("unable to persist the scheduler state: {0}")]
+    StatePersistence(String),
+    #[error("requests still in flight after {0} second(s)")]
+    ShutdownTimeout(u64),
+    #[error("event loop stopped: {0}")]
+    EventLoop(String),
     #[error(transparent)]
     Other(#[from] anyhow::Error),
     #[error("unknown scheduler error")]
     Unknown,
 }
 
-#[derive(Debug)]
-#[allow(dead_code)]
+/// `Node` is a node registered to the scheduler, as last reported by its status stream.
+///
+/// Properties:
+///
+/// * `id`: The id assigned to the node when it registered.
+/// * `address`: The address of the gRPC server of the node agent.
+/// * `subnet`: The subnet the node takes the addresses of its instances from.
+/// * `status`: The status of the node, instances are only placed on running nodes.
+/// * `resource`: The capacity of the node and its current usage.
+/// * `last_seen`: When the node last sent its status.
+/// * `labels`: The labels the node selectors and affinities of the instances are matched against.
+/// * `taints`: The taints repelling the instances which don't tolerate them.
+/// * `cordoned`: True if no new instance is placed on the node.
+/// * `drain`: The progress of moving the instances of the node to the other nodes, if it is
+///   drained.
+#[derive(Debug, Clone)]
 pub struct Node {
-    id: String,
+    pub id: String,
+    pub address: String,
+    pub subnet: Ipv4Cidr,
+    pub status: Status,
+    pub resource: Option<Resource>,
+    pub last_seen: Instant,
+    pub labels: HashMap<String, String>,
+    pub taints: Vec<Taint>,
+    pub cordoned: bool,
+    pub drain: Option<Drain>,
+}
+
+impl Node {
+    /// It returns the resources of the node which can be allocated to instances
+    pub fn capacity(&self) -> Option<ResourceSummary> {
+        self.resource.as_ref()?.limit.clone()
+    }
 }
 
 pub type NodeIdentifier = String;
 
+/// The stream the statuses of an instance are sent to the controller over.
+pub type StatusSender = mpsc::Sender<Result<InstanceStatus, tonic::Status>>;
+
+/// `PlacedInstance` is an instance along with the node the scheduler placed it on.
+///
+/// Properties:
+///
+/// * `node_id`: The id of the node the instance runs on.
+/// * `instance`: The instance, with the address it was given.
+/// * `lost_since`: When the node of the instance failed, None while the node is healthy.
+/// * `watcher`: The stream the statuses of the instance are sent to.
+#[derive(Debug, Clone, Default)]
+pub struct PlacedInstance {
+    pub node_id: NodeIdentifier,
+    pub instance: Instance,
+    pub lost_since: Option<Instant>,
+    pub watcher: Option<StatusSender>,
+}
+
+impl PlacedInstance {
+    /// The index of the instances by the id of their node
+    pub const BY_NODE: &'static str = "node";
+    /// The index of the instances by namespace, taken from their workload
+    pub const BY_NAMESPACE: &'static str = "namespace";
+    /// The index of the instances by status, as last reported by their node
+    pub const BY_STATUS: &'static str = "status";
+
+    /// It creates an empty storage of instances, indexed by node, namespace and status
+    pub fn storage() -> Storage<PlacedInstance> {
+        Storage::new()
+            .with_index(Self::BY_NODE, |placed: &PlacedInstance| {
+                Some(placed.node_id.clone())
+            })
+            .with_index(Self::BY_NAMESPACE, |placed| {
+                let (namespace, _) = placed.instance.workload.split_once('/')?;
+                Some(namespace.to_string())
+            })
+            .with_index(Self::BY_STATUS, |placed| {
+                Some(format!("{:?}", placed.instance.status()))
+            })
+    }
+
+    /// It returns the instances placed on a node, through the node index if the storage has one
+    pub fn on_node<'a>(
+        node_id: &str,
+        instances: &'a Storage<PlacedInstance>,
+    ) -> Vec<&'a PlacedInstance> {
+        instances.find(Self::BY_NODE, node_id).unwrap_or_else(|| {
+            instances
+                .get_all()
+                .values()
+                .filter(|placed| placed.node_id == node_id)
+                .collect()
+        })
+    }
+}
+
+impl From<SchedulerError> for tonic::Status {
+    fn from(err: SchedulerError) -> Self {
+        match err {
+            SchedulerError::NodeNotRegistered(_) | SchedulerError::InstanceNotFound(_) => {
+                tonic::Status::not_found(err.to_string())
+            }
+            SchedulerError::InvalidNodeAddress(_) => {
+                tonic::Status::invalid_argument(err.to_string())
+            }
+            SchedulerError::AgentRequest(_, _) => tonic::Status::unavailable(err.to_string()),
+            SchedulerError::NoSubnetLeft | SchedulerError::AddressPoolExhausted(_) => {
+                tonic::Status::resource_exhausted(err.to_string())
+            }
+            _ => tonic::Status::internal(err.to_string()),
+        }
+    }
+}
+
 #[derive(Debug)]
 pub enum Event {
     // Instance events
-    InstanceCreate(
-        Instance,
-        mpsc::Sender<Result<InstanceStatus, tonic::Status>>,
-    ),
+    InstanceCreate(Box<Instance>, StatusSender),
+    InstanceWatch(String, StatusSender),
     InstanceStart(
         NodeIdentifier,
         oneshot::Sender<Result<Response<()>, tonic::Status>>,
@@ -53,6 +208,19 @@ pub enum Event {
         NodeIdentifier,
         oneshot::Sender<Result<Response<()>, tonic::Status>>,
     ),
+    InstanceList(
+        InstanceListRequest,
+        oneshot::Sender<Result<Response<InstanceListResponse>, tonic::Status>>,
+    ),
+    InstancePending(oneshot::Sender<Result<Response<PendingListResponse>, tonic::Status>>),
+    InstanceExplain(
+        String,
+        oneshot::Sender<Result<Response<PlacementExplanation>, tonic::Status>>,
+    ),
+    InstanceDryRun(
+        DryRunRequest,
+        oneshot::Sender<Result<Response<DryRunResponse>, tonic::Status>>,
+    ),
 
     // Node events
     NodeRegister(
@@ -63,5 +231,55 @@ pub enum Event {
         NodeUnregisterRequest,
         oneshot::Sender<Result<Response<NodeUnregisterResponse>, tonic::Status>>,
     ),
-    NodeStatus(NodeStatus, mpsc::Sender<Result<(), tonic::Status>>),
+    NodeUpdate(
+        NodeUpdateRequest,
+        oneshot::Sender<Result<Response<NodeUpdateResponse>, tonic::Status>>,
+    ),
+    NodeStatus(NodeStatus, oneshot::Sender<Result<(), tonic::Status>>),
+    NodeCordon(
+        NodeCordonRequest,
+        oneshot::Sender<Result<Response<NodeCordonResponse>, tonic::Status>>,
+    ),
+    NodeDrain(
+        NodeDrainRequest,
+        oneshot::Sender<Result<Response<NodeDrainStatus>, tonic::Status>>,
+    ),
+    NodeDrainStatus(
+        NodeIdentifier,
+        oneshot::Sender<Result<Response<NodeDrainStatus>, tonic::Status>>,
+    ),
+    NodeList(oneshot::Sender<Result<Response<NodeListResponse>, tonic::Status>>),
+    NodeGet(
+        NodeIdentifier,
+        oneshot::Sender<Result<Response<NodeInfo>, tonic::Status>>,
+    ),
+    /// Sent periodically to find the failed nodes and reschedule their instances
+    NodeCheck,
+}
+
+impl Event {
+    /// It returns the name of the event, to report it without its content
+    pub fn name(&self) -> &'static str {
+        match self {
+            Event::InstanceCreate(..) => "InstanceCreate",
+            Event::InstanceWatch(..) => "InstanceWatch",
+            Event::InstanceStart(..) => "InstanceStart",
+            Event::InstanceStop(..) => "InstanceStop",
+            Event::InstanceDestroy(..) => "InstanceDestroy",
+            Event::InstanceList(..) => "InstanceList",
+            Event::InstancePending(..) => "InstancePending",
+            Event::InstanceExplain(..) => "InstanceExplain",
+            Event::InstanceDryRun(..) => "InstanceDryRun",
+            Event::NodeRegister(..) => "NodeRegister",
+            Event::NodeUnregister(..) => "NodeUnregister",
+            Event::NodeUpdate(..) => "NodeUpdate",
+            Event::NodeStatus(..) => "NodeStatus",
+            Event::NodeCordon(..) => "NodeCordon",
+            Event::NodeDrain(..) => "NodeDrain",
+            Event::NodeDrainStatus(..) => "NodeDrainStatus",
+            Event::NodeList(..) => "NodeList",
+            Event::NodeGet(..) => "NodeGet",
+            Event::NodeCheck => "NodeCheck",
+        }
+    }
 }
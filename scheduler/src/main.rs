@@ -1,6 +1,6 @@
 use std::env;
 
-use log::{debug, info};
+use log::{debug, error, info};
 use scheduler::{config::Config, manager::Manager, SchedulerError};
 
 #[tokio::main]
@@ -17,11 +17,15 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         confy::load_path(dir.as_path()).map_err(SchedulerError::ConfigReadError)?;
     debug!("config: {:?}", config);
 
-    let manager = Manager::new(config);
+    let manager = Manager::new(config)?;
     debug!("initialized manager struct with data : {:?}", manager);
 
-    manager.run().await?;
+    let result = manager.run().await;
+    match &result {
+        Ok(()) => info!("shut down"),
+        Err(err) => error!("shutdown failed : {}", err),
+    }
 
-    info!("shutting down");
-    Ok(())
+    log::logger().flush();
+    result
 }
@@ -1,25 +1,58 @@
-use std::sync::Arc;
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
 use anyhow::Result;
-use log::{debug, info};
+use log::{debug, error, info, warn};
+use proto::agent::Signal;
 use proto::scheduler::{
     instance_service_server::InstanceServiceServer, node_service_server::NodeServiceServer,
-    Instance, InstanceStatus, NodeRegisterResponse, NodeUnregisterResponse,
+    DrainState, DryRunPlacement, DryRunRequest, DryRunResponse, Instance, InstanceListRequest,
+    InstanceListResponse, InstanceStatus, NodeCordonRequest, NodeCordonResponse, NodeDrainRequest,
+    NodeDrainStatus, NodeInfo, NodeListResponse, NodeRegisterRequest, NodeRegisterResponse,
+    NodeStatus, NodeUnregisterRequest, NodeUnregisterResponse, NodeUpdateRequest,
+    NodeUpdateResponse, PendingListResponse, PlacementExplanation, Resource, Status,
 };
-use tokio::sync::mpsc;
+use tokio::sync::mpsc::{self, error::TrySendError};
 use tokio::{sync::oneshot, task::JoinHandle};
 use tonic::{transport::Server, Response};
 
+use crate::agent_client::{self, AgentClient};
+use crate::drain::Drain;
+use crate::health;
+use crate::ipam::Ipam;
+use crate::pending::{PendingInstance, PendingQueue};
+use crate::persistence::{
+    self, FileBackend, MemoryBackend, StateBackend, StateBackendKind, StateLog,
+};
+use crate::placement::{Decision, Pipeline};
+use crate::preemption;
+use crate::query;
+use crate::simulation;
+use crate::storage::{ConcurrentStorage, IStorage};
+use crate::subnet::SubnetAllocator;
 use crate::SchedulerError;
 use crate::{
     config::Config, instance_listener::InstanceListener, node_listener::NodeListener,
-    storage::Storage, Event, Node,
+    storage::Storage, Event, Node, NodeIdentifier, PlacedInstance, StatusSender,
 };
+use shutdown::{self, Shutdown};
+
+/// The number of events failing in a row after which the event loop stops
+const MAX_CONSECUTIVE_FAILURES: u32 = 3;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Manager {
-    instances: Arc<Storage<Instance>>,
-    nodes: Arc<Storage<Node>>,
+    instances: Arc<ConcurrentStorage<PlacedInstance>>,
+    nodes: Arc<ConcurrentStorage<Node>>,
+    subnets: Arc<Mutex<SubnetAllocator>>,
+    ipam: Arc<Mutex<Ipam>>,
+    agents: Arc<ConcurrentStorage<AgentClient>>,
+    pending: Arc<Mutex<PendingQueue>>,
+    pipeline: Arc<Pipeline>,
+    decisions: Arc<ConcurrentStorage<Decision>>,
+    state: Arc<Mutex<StateLog>>,
+    restored: Arc<Mutex<HashSet<NodeIdentifier>>>,
     config: Arc<Config>,
 }
 
@@ -28,13 +61,106 @@ impl Manager {
     ///
     /// Returns:
     ///
-    /// A new Manager struct
-    pub fn new(config: Config) -> Self {
-        Manager {
-            instances: Arc::new(Storage::new()),
-            nodes: Arc::new(Storage::new()),
-            config: Arc::new(config),
+    /// A new Manager struct, or an error if the cluster CIDR or the plugin weights of the config
+    /// are invalid or the saved state can't be read
+    pub fn new(config: Config) -> Result<Self, SchedulerError> {
+        let backend: Box<dyn StateBackend> = match config.state_backend {
+            StateBackendKind::File => Box::new(FileBackend::new(&config.state_path)),
+            StateBackendKind::Memory => Box::new(MemoryBackend::new()),
+        };
+        Self::with_state_backend(config, backend)
+    }
+
+    /// `with_state_backend` creates a new `Manager` saving its state to `backend`, with the
+    /// nodes, placements and instance addresses previously saved there. The restored nodes are `STARTING` until
+    /// their first status, which tells the instances they still run.
+    ///
+    /// Arguments:
+    ///
+    /// * `config`: The configuration of the scheduler.
+    /// * `backend`: Where the state of the scheduler is saved.
+    ///
+    /// Returns:
+    ///
+    /// A new Manager struct, or an error if the config is invalid or the saved state can't be
+    /// read
+    pub fn with_state_backend(
+        config: Config,
+        backend: Box<dyn StateBackend>,
+    ) -> Result<Self, SchedulerError> {
+        let mut subnets = SubnetAllocator::new(&config.cluster_cidr, config.node_subnet_length)?;
+        let mut ipam = Ipam::new();
+
+        let state = StateLog::open(backend, config.state_snapshot_interval)?;
+        let mut nodes = Storage::new();
+        let mut agents = Storage::new();
+        for stored in state.state().nodes.values() {
+            let subnet = stored.subnet.parse().map_err(|_| {
+                SchedulerError::StatePersistence(format!(
+                    "invalid subnet {} of node {}",
+                    stored.subnet, stored.id
+                ))
+            })?;
+            subnets.reserve(subnet);
+            ipam.add_node(&stored.id, subnet);
+            agents.update(&stored.id, AgentClient::new(&stored.id, &stored.address)?);
+            nodes.update(
+                &stored.id,
+                Node {
+                    id: stored.id.clone(),
+                    address: stored.address.clone(),
+                    subnet,
+                    status: Status::Starting,
+                    resource: Some(Resource {
+                        limit: stored.capacity.clone(),
+                        usage: None,
+                        request: None,
+                    }),
+                    last_seen: Instant::now(),
+                    labels: stored.labels.clone(),
+                    taints: stored.taints.clone(),
+                    cordoned: stored.cordoned,
+                    drain: None,
+                },
+            );
         }
+        let mut instances = PlacedInstance::storage();
+        for stored in state.state().instances.values() {
+            if let Some(instance) = &stored.instance {
+                // the addresses given before a restart stay in use
+                restore_address(&mut ipam, &stored.node_id, instance);
+                instances.update(
+                    &instance.id,
+                    PlacedInstance {
+                        node_id: stored.node_id.clone(),
+                        instance: instance.clone(),
+                        ..Default::default()
+                    },
+                );
+            }
+        }
+        if !nodes.is_empty() {
+            info!(
+                "restored {} node(s) and {} instance(s)",
+                nodes.len(),
+                instances.len()
+            );
+        }
+        let restored = nodes.get_all().keys().cloned().collect();
+
+        Ok(Manager {
+            instances: Arc::new(ConcurrentStorage::new(instances)),
+            nodes: Arc::new(ConcurrentStorage::new(nodes)),
+            subnets: Arc::new(Mutex::new(subnets)),
+            ipam: Arc::new(Mutex::new(ipam)),
+            agents: Arc::new(ConcurrentStorage::new(agents)),
+            pending: Arc::new(Mutex::new(PendingQueue::new())),
+            pipeline: Arc::new(Pipeline::from_config(&config)?),
+            decisions: Arc::new(ConcurrentStorage::default()),
+            state: Arc::new(Mutex::new(state)),
+            restored: Arc::new(Mutex::new(restored)),
+            config: Arc::new(config),
+        })
     }
 
     /// This function returns a reference to the instances storage.
@@ -42,7 +168,7 @@ impl Manager {
     /// Returns:
     ///
     /// A reference to the instances storage.
-    pub fn instances(&self) -> Arc<Storage<Instance>> {
+    pub fn instances(&self) -> Arc<ConcurrentStorage<PlacedInstance>> {
         self.instances.clone()
     }
 
@@ -51,27 +177,42 @@ impl Manager {
     /// Returns:
     ///
     /// A reference to the nodes storage.
-    pub fn nodes(&self) -> Arc<Storage<Node>> {
+    pub fn nodes(&self) -> Arc<ConcurrentStorage<Node>> {
         self.nodes.clone()
     }
 
+    /// This function returns a reference to the last placement decision of each instance.
+    ///
+    /// Returns:
+    ///
+    /// A reference to the decisions storage.
+    pub fn decisions(&self) -> Arc<ConcurrentStorage<Decision>> {
+        self.decisions.clone()
+    }
+
     /// It creates a gRPC server that listens on port 50051 and spawns a new thread to handle incoming
-    /// requests
+    /// requests. The server stops accepting connections once `shutdown` is triggered and then
+    /// waits for the requests in flight.
     ///
     /// Arguments:
     ///
     /// * `tx`: mpsc::Sender<Event>
+    /// * `shutdown`: Shutdown
     ///
     /// Returns:
     ///
-    /// A JoinHandle<()>
-    fn create_grpc_server(&self, tx: mpsc::Sender<Event>) -> Result<JoinHandle<()>> {
+    /// A JoinHandle<Result<(), tonic::transport::Error>>
+    fn create_grpc_server(
+        &self,
+        tx: mpsc::Sender<Event>,
+        mut shutdown: Shutdown,
+    ) -> Result<JoinHandle<Result<(), tonic::transport::Error>>> {
         info!("creating grpc server ...");
         let addr = format!("{}:{}", self.config.host, self.config.port)
             .parse()
             .map_err(|_| SchedulerError::InvalidGrpcAddress)?;
 
-        let node_listener = NodeListener::new(tx.clone());
+        let node_listener = NodeListener::new(tx.clone(), shutdown.clone());
         debug!("create node listener with data : {:?}", node_listener);
 
         let instance_listener = InstanceListener::new(tx);
@@ -86,9 +227,8 @@ impl Manager {
             Server::builder()
                 .add_service(NodeServiceServer::new(node_listener))
                 .add_service(InstanceServiceServer::new(instance_listener))
-                .serve(addr)
+                .serve_with_shutdown(addr, shutdown.wait())
                 .await
-                .unwrap();
         }))
     }
 
@@ -108,8 +248,27 @@ impl Manager {
         (tx, rx)
     }
 
+    /// It waits for the reply of the manager to a request. The request fails if the manager
+    /// dropped it without replying, because handling it took longer than `event_timeout` or
+    /// panicked.
+    pub async fn wait_reply<T>(
+        rx: oneshot::Receiver<Result<T, tonic::Status>>,
+    ) -> Result<T, tonic::Status> {
+        match rx.await {
+            Ok(reply) => reply,
+            Err(_) => Err(tonic::Status::deadline_exceeded(
+                "manager dropped the request",
+            )),
+        }
+    }
+
     /// This function listens for incoming events from the event bus and dispatches them to the
-    /// orchestrator
+    /// orchestrator, one at a time. Each event is handled in its own task, so that an event
+    /// whose handling panics only fails its own request. An event still handled after
+    /// `event_timeout` seconds, e.g. stuck on a lock, fails as well, and the event loop stops
+    /// once `MAX_CONSECUTIVE_FAILURES` events in a row failed, e.g. because a lock was poisoned.
+    /// The handlers don't wait on the clients nor the agents, so that they can't be stopped
+    /// halfway through, e.g. with the pending queue taken or a placement not dispatched.
     ///
     /// Arguments:
     ///
@@ -117,72 +276,1544 @@ impl Manager {
     ///
     /// Returns:
     ///
-    /// A JoinHandle<()>
-    fn listen_events(&self, mut rx: mpsc::Receiver<Event>) -> JoinHandle<()> {
+    /// A JoinHandle resolving to an error if the event loop stopped because of failed events
+    fn listen_events(
+        &self,
+        mut rx: mpsc::Receiver<Event>,
+    ) -> JoinHandle<Result<(), SchedulerError>> {
         info!("listening for incoming events ...");
+        let manager = self.clone();
 
         tokio::spawn(async move {
+            let timeout = Duration::from_secs(manager.config.event_timeout);
+            let mut failures = 0;
             while let Some(event) = rx.recv().await {
                 debug!("received event : {:?}", event);
-                match event {
-                    Event::InstanceCreate(instance, tx) => {
-                        info!("received instance create event : {:?}", instance);
-                        tx.send(Ok(InstanceStatus::default())).await.unwrap();
+                let name = event.name();
+
+                let mut handler = tokio::spawn(manager.clone().handle(event));
+                let handled = match tokio::time::timeout(timeout, &mut handler).await {
+                    Ok(handled) => handled.map_err(|err| err.to_string()),
+                    Err(_) => {
+                        handler.abort();
+                        Err(format!(
+                            "still handled after {} second(s)",
+                            manager.config.event_timeout
+                        ))
                     }
-                    Event::InstanceStart(id, tx) => {
-                        info!("received instance start event : {:?}", id);
-                        tx.send(Ok(Response::new(()))).unwrap();
+                };
+
+                match handled {
+                    Ok(()) => failures = 0,
+                    // the reply of the event is dropped, which fails its request
+                    Err(err) => {
+                        failures += 1;
+                        error!("{} event failed : {}", name, err);
+                        if failures >= MAX_CONSECUTIVE_FAILURES {
+                            return Err(SchedulerError::EventLoop(format!(
+                                "{} events failed in a row",
+                                failures
+                            )));
+                        }
                     }
-                    Event::InstanceStop(id, tx) => {
-                        info!("received instance stop event : {:?}", id);
-                        tx.send(Ok(Response::new(()))).unwrap();
+                }
+            }
+            info!("stopped listening for events");
+            Ok(())
+        })
+    }
+
+    /// It handles an event, replying to its request
+    ///
+    /// Arguments:
+    ///
+    /// * `event`: The event to handle.
+    async fn handle(self, event: Event) {
+        match event {
+            Event::InstanceCreate(instance, tx) => {
+                info!("received instance create event : {:?}", instance);
+                self.schedule(*instance, tx);
+            }
+            Event::InstanceWatch(id, tx) => {
+                info!("received instance watch event : {:?}", id);
+                self.watch(&id, tx);
+            }
+            Event::InstanceStart(id, tx) => {
+                info!("received instance start event : {:?}", id);
+                Self::signal(self.agent_of(&id), Signal::Start, Some(tx));
+            }
+            Event::InstanceStop(id, tx) => {
+                info!("received instance stop event : {:?}", id);
+                Self::signal(self.agent_of(&id), Signal::Stop, Some(tx));
+            }
+            Event::InstanceDestroy(id, tx) => {
+                info!("received instance destroy event : {:?}", id);
+                match self.agent_of(&id) {
+                    Ok(target) => Self::signal(Ok(target), Signal::Kill, Some(tx)),
+                    // the instance isn't running anywhere
+                    Err(_) => reply(tx.send(Ok(Response::new(()))).is_ok()),
+                }
+                self.pending.lock().unwrap().remove(&id);
+                self.forget(&id);
+                self.retry_pending();
+            }
+            Event::InstanceList(request, tx) => {
+                debug!("received instance list event : {:?}", request);
+                let response = self.list_instances(&request);
+                reply(tx.send(Ok(Response::new(response))).is_ok());
+            }
+            Event::InstancePending(tx) => {
+                debug!("received instance pending event");
+                let response = self.list_pending();
+                reply(tx.send(Ok(Response::new(response))).is_ok());
+            }
+            Event::InstanceExplain(id, tx) => {
+                debug!("received instance explain event : {:?}", id);
+                let response = self.explain(&id);
+                reply(
+                    tx.send(response.map(Response::new).map_err(Into::into))
+                        .is_ok(),
+                );
+            }
+            Event::InstanceDryRun(request, tx) => {
+                info!(
+                    "received instance dry run event for {} instance(s)",
+                    request.instances.len()
+                );
+                let response = self.dry_run(&request);
+                reply(tx.send(Ok(Response::new(response))).is_ok());
+            }
+            Event::NodeRegister(request, tx) => {
+                info!("received node register event : {:?}", request);
+                let response = self.register_node(request);
+                reply(
+                    tx.send(response.map(Response::new).map_err(Into::into))
+                        .is_ok(),
+                );
+            }
+            Event::NodeUnregister(request, tx) => {
+                info!("received node unregister event : {:?}", request);
+                let response = self.unregister_node(request);
+                reply(
+                    tx.send(response.map(Response::new).map_err(Into::into))
+                        .is_ok(),
+                );
+            }
+            Event::NodeUpdate(request, tx) => {
+                info!("received node update event : {:?}", request);
+                let response = self.update_node(request);
+                let updated = response.is_ok();
+                reply(
+                    tx.send(response.map(Response::new).map_err(Into::into))
+                        .is_ok(),
+                );
+                // the new labels or taints may let pending instances on the node
+                if updated {
+                    self.retry_pending();
+                }
+            }
+            Event::NodeStatus(status, tx) => {
+                debug!("received node status event : {:?}", status);
+                let response = self.update_node_status(status);
+                let freed = matches!(response, Ok(true));
+                reply(tx.send(response.map(|_| ()).map_err(Into::into)).is_ok());
+                if freed {
+                    self.retry_pending();
+                }
+            }
+            Event::NodeCordon(request, tx) => {
+                info!("received node cordon event : {:?}", request);
+                let response = self.cordon_node(request);
+                let uncordoned = matches!(&response, Ok((false, _)));
+                reply(
+                    tx.send(
+                        response
+                            .map(|(_, response)| Response::new(response))
+                            .map_err(Into::into),
+                    )
+                    .is_ok(),
+                );
+                // the pending instances may be placed on the node again
+                if uncordoned {
+                    self.retry_pending();
+                }
+            }
+            Event::NodeDrain(request, tx) => {
+                info!("received node drain event : {:?}", request);
+                let response = self.drain_node(request);
+                reply(
+                    tx.send(response.map(Response::new).map_err(Into::into))
+                        .is_ok(),
+                );
+            }
+            Event::NodeDrainStatus(id, tx) => {
+                debug!("received node drain status event : {:?}", id);
+                let response = self.drain_status(&id);
+                reply(
+                    tx.send(response.map(Response::new).map_err(Into::into))
+                        .is_ok(),
+                );
+            }
+            Event::NodeList(tx) => {
+                debug!("received node list event");
+                let response = self.list_nodes();
+                reply(tx.send(Ok(Response::new(response))).is_ok());
+            }
+            Event::NodeGet(id, tx) => {
+                debug!("received node get event : {:?}", id);
+                let response = self.get_node(&id);
+                reply(
+                    tx.send(response.map(Response::new).map_err(Into::into))
+                        .is_ok(),
+                );
+            }
+            Event::NodeCheck => self.check_nodes(),
+        }
+    }
+
+    /// It registers a node, giving it a unique id and its own subnet, and creates the client
+    /// of its agent.
+    ///
+    /// Arguments:
+    ///
+    /// * `request`: The address, capacity, labels and taints of the node.
+    ///
+    /// Returns:
+    ///
+    /// The id and subnet of the node, or an error if its address is invalid or there is no
+    /// subnet left.
+    fn register_node(
+        &self,
+        request: NodeRegisterRequest,
+    ) -> Result<NodeRegisterResponse, SchedulerError> {
+        self.nodes.write(|nodes| {
+            let id = loop {
+                let id = format!("node-{:08x}", rand::random::<u32>());
+                if nodes.get(&id).is_none() {
+                    break id;
+                }
+            };
+            let agent = AgentClient::new(&id, &request.address)?;
+
+            let mut subnets = self.subnets.lock().unwrap();
+            let subnet = subnets.allocate().ok_or(SchedulerError::NoSubnetLeft)?;
+            self.ipam.lock().unwrap().add_node(&id, subnet);
+            info!(
+                "registered node {} at {} with subnet {}",
+                id, request.address, subnet
+            );
+
+            self.agents.update(&id, agent);
+            let node = Node {
+                id: id.clone(),
+                address: request.address,
+                subnet,
+                status: Status::Starting,
+                resource: Some(Resource {
+                    limit: request.capacity,
+                    usage: None,
+                    request: None,
+                }),
+                last_seen: Instant::now(),
+                labels: request.labels,
+                taints: request.taints,
+                cordoned: false,
+                drain: None,
+            };
+            self.state
+                .lock()
+                .unwrap()
+                .record(persistence::node_put(&node));
+            nodes.update(&id, node);
+
+            Ok(NodeRegisterResponse {
+                code: 0,
+                description: "node registered".to_string(),
+                subnet: subnet.to_string(),
+                id,
+            })
+        })
+    }
+
+    /// It unregisters a node, releases its subnet and drops the client of its agent.
+    ///
+    /// Arguments:
+    ///
+    /// * `request`: The id of the node.
+    fn unregister_node(
+        &self,
+        request: NodeUnregisterRequest,
+    ) -> Result<NodeUnregisterResponse, SchedulerError> {
+        self.nodes.write(|nodes| {
+            let node = nodes
+                .get(&request.id)
+                .cloned()
+                .ok_or(SchedulerError::NodeNotRegistered(request.id))?;
+
+            self.ipam.lock().unwrap().remove_node(&node.id);
+            self.subnets.lock().unwrap().release(&node.subnet);
+            self.agents.delete(&node.id);
+            nodes.delete(&node.id);
+            self.restored.lock().unwrap().remove(&node.id);
+            self.state
+                .lock()
+                .unwrap()
+                .record(persistence::node_delete(&node.id));
+            info!("unregistered node {}", node.id);
+
+            Ok(NodeUnregisterResponse {
+                code: 0,
+                description: "node unregistered".to_string(),
+            })
+        })
+    }
+
+    /// It replaces the labels or the taints of a node. The instances already placed on the node
+    /// are kept even if they no longer match, only the next placements are affected.
+    ///
+    /// Arguments:
+    ///
+    /// * `request`: The id of the node, and its new labels or taints.
+    fn update_node(
+        &self,
+        request: NodeUpdateRequest,
+    ) -> Result<NodeUpdateResponse, SchedulerError> {
+        self.nodes.write(|nodes| {
+            let node = nodes
+                .get_mut(&request.id)
+                .ok_or_else(|| SchedulerError::NodeNotRegistered(request.id.clone()))?;
+
+            if let Some(labels) = request.labels {
+                node.labels = labels.labels;
+            }
+            if let Some(taints) = request.taints {
+                node.taints = taints.taints;
+            }
+            self.state
+                .lock()
+                .unwrap()
+                .record(persistence::node_put(node));
+            info!(
+                "updated node {} with labels {:?} and taints {:?}",
+                node.id, node.labels, node.taints
+            );
+
+            Ok(NodeUpdateResponse {
+                code: 0,
+                description: "node updated".to_string(),
+            })
+        })
+    }
+
+    /// It cordons a node so that no new instance is placed on it, the instances already placed
+    /// there keep running. Uncordoning a node stops its drain, if any.
+    ///
+    /// Arguments:
+    ///
+    /// * `request`: The id of the node, and whether it is cordoned.
+    ///
+    /// Returns:
+    ///
+    /// Whether the node is now cordoned along with the response, or an error if the node isn't
+    /// registered.
+    fn cordon_node(
+        &self,
+        request: NodeCordonRequest,
+    ) -> Result<(bool, NodeCordonResponse), SchedulerError> {
+        self.nodes.write(|nodes| {
+            let node = nodes
+                .get_mut(&request.id)
+                .ok_or_else(|| SchedulerError::NodeNotRegistered(request.id.clone()))?;
+
+            node.cordoned = request.cordoned;
+            if !node.cordoned {
+                node.drain = None;
+            }
+            self.state
+                .lock()
+                .unwrap()
+                .record(persistence::node_put(node));
+            let description = match node.cordoned {
+                true => "node cordoned",
+                false => "node uncordoned",
+            };
+            info!("{} {}", description, node.id);
+
+            Ok((
+                node.cordoned,
+                NodeCordonResponse {
+                    code: 0,
+                    description: description.to_string(),
+                },
+            ))
+        })
+    }
+
+    /// It cordons a node then starts moving its instances to the other nodes, at most
+    /// `max_unavailable` instances of a workload at a time. The drain goes on with each node
+    /// check, until no instance is left on the node. Draining a node already drained only
+    /// changes its budget.
+    ///
+    /// Arguments:
+    ///
+    /// * `request`: The id of the node, and the instances of a workload moved at the same time.
+    ///
+    /// Returns:
+    ///
+    /// The progress of the drain, or an error if the node isn't registered.
+    fn drain_node(&self, request: NodeDrainRequest) -> Result<NodeDrainStatus, SchedulerError> {
+        self.nodes.write(|nodes| {
+            let node = nodes
+                .get_mut(&request.id)
+                .ok_or_else(|| SchedulerError::NodeNotRegistered(request.id.clone()))?;
+
+            match &mut node.drain {
+                Some(drain) => drain.max_unavailable = request.max_unavailable.max(1),
+                None => node.drain = Some(Drain::new(request.max_unavailable)),
+            }
+            if !node.cordoned {
+                node.cordoned = true;
+                self.state
+                    .lock()
+                    .unwrap()
+                    .record(persistence::node_put(node));
+            }
+            info!(
+                "draining node {}, {} instance(s) of a workload at a time",
+                node.id,
+                request.max_unavailable.max(1)
+            );
+            Ok::<(), SchedulerError>(())
+        })?;
+
+        self.drain(&request.id);
+        self.drain_status(&request.id)
+    }
+
+    /// It returns the progress of the drain of a node, `NOT_DRAINED` if it isn't drained
+    fn drain_status(&self, id: &str) -> Result<NodeDrainStatus, SchedulerError> {
+        self.nodes.read(|nodes| {
+            let node = nodes
+                .get(id)
+                .ok_or_else(|| SchedulerError::NodeNotRegistered(id.to_string()))?;
+            let remaining = self
+                .instances
+                .read(|instances| PlacedInstance::on_node(id, instances).len());
+
+            Ok(match &node.drain {
+                Some(drain) => drain.status(id, remaining),
+                None => NodeDrainStatus {
+                    id: id.to_string(),
+                    state: DrainState::NotDrained.into(),
+                    remaining: remaining as u32,
+                    description: format!("node {} is not drained", id),
+                    ..Default::default()
+                },
+            })
+        })
+    }
+
+    /// It lists the nodes by id, with the resources allocated to the instances placed on them
+    fn list_nodes(&self) -> NodeListResponse {
+        self.nodes.read(|nodes| {
+            self.instances.read(|instances| {
+                let mut listed: Vec<NodeInfo> = nodes
+                    .get_all()
+                    .values()
+                    .map(|node| query::node_info(node, instances, &self.config.overcommit))
+                    .collect();
+                listed.sort_by(|node, other| node.id.cmp(&other.id));
+                NodeListResponse { nodes: listed }
+            })
+        })
+    }
+
+    /// It describes a node, with the resources allocated to the instances placed on it
+    fn get_node(&self, id: &str) -> Result<NodeInfo, SchedulerError> {
+        self.nodes.read(|nodes| {
+            let node = nodes
+                .get(id)
+                .ok_or_else(|| SchedulerError::NodeNotRegistered(id.to_string()))?;
+            Ok(self
+                .instances
+                .read(|instances| query::node_info(node, instances, &self.config.overcommit)))
+        })
+    }
+
+    /// It lists the placed instances, on a node or in a namespace if the request says so
+    fn list_instances(&self, request: &InstanceListRequest) -> InstanceListResponse {
+        self.instances
+            .read(|instances| query::instances(request, instances))
+    }
+
+    /// It lists the pending instances in the order they are retried
+    fn list_pending(&self) -> PendingListResponse {
+        let now = Instant::now();
+        PendingListResponse {
+            instances: self
+                .pending
+                .lock()
+                .unwrap()
+                .list()
+                .into_iter()
+                .map(|pending| query::pending_info(pending, now))
+                .collect(),
+        }
+    }
+
+    /// It explains the last placement decision of an instance, placed or pending
+    fn explain(&self, id: &str) -> Result<PlacementExplanation, SchedulerError> {
+        self.decisions
+            .get(id)
+            .map(|decision| query::explain(&decision))
+            .ok_or_else(|| SchedulerError::InstanceNotFound(id.to_string()))
+    }
+
+    /// It tells where instances would be placed, one after the other, without placing them nor
+    /// recording the decisions.
+    fn dry_run(&self, request: &DryRunRequest) -> DryRunResponse {
+        let simulations = self.nodes.read(|nodes| {
+            self.instances.read(|instances| {
+                simulation::simulate(
+                    &request.instances,
+                    &request.replaces,
+                    &self.pipeline,
+                    nodes,
+                    instances,
+                )
+            })
+        });
+
+        DryRunResponse {
+            placements: simulations
+                .into_iter()
+                .map(|simulation| {
+                    debug!("dry run: {}", simulation.decision);
+                    DryRunPlacement {
+                        explanation: Some(query::explain(&simulation.decision)),
+                        preempted: simulation.preempted,
                     }
-                    Event::InstanceDestroy(id, tx) => {
-                        info!("received instance destroy event : {:?}", id);
-                        tx.send(Ok(Response::new(()))).unwrap();
+                })
+                .collect(),
+        }
+    }
+
+    /// It records the status and resource usage sent by a node, as well as when it was sent.
+    /// A node unknown to the scheduler, e.g. one which isn't in the saved state, is refused so
+    /// that it registers again. The first status of a node restored from the saved state is
+    /// reconciled with the instances placed on it.
+    ///
+    /// Returns:
+    ///
+    /// True if the node may have more free capacity than before, because it just started
+    /// running, e.g. after it registered, because its usage dropped, or because instances it
+    /// no longer runs were forgotten.
+    fn update_node_status(&self, status: NodeStatus) -> Result<bool, SchedulerError> {
+        let mut freed = self.nodes.write(|nodes| {
+            let node = nodes
+                .get_mut(&status.id)
+                .ok_or_else(|| SchedulerError::NodeNotRegistered(status.id.clone()))?;
+
+            let mut freed = node.status != Status::Running && status.status() == Status::Running;
+            node.status = status.status();
+            node.last_seen = Instant::now();
+            if let Some(usage) = status.resource.clone().and_then(|resource| resource.usage) {
+                let resource = node.resource.get_or_insert_with(Resource::default);
+                if let Some(previous) = &resource.usage {
+                    freed |= usage.cpu < previous.cpu
+                        || usage.memory < previous.memory
+                        || usage.disk < previous.disk;
+                }
+                resource.usage = Some(usage);
+            }
+            Ok::<_, SchedulerError>(freed)
+        })?;
+
+        if self.restored.lock().unwrap().remove(&status.id) {
+            freed |= self.reconcile(&status.id, &status.instances);
+        }
+        Ok(freed)
+    }
+
+    /// It reconciles the instances restored on a node with the ones its agent runs. The
+    /// instances which no longer run are forgotten, and the ones the scheduler doesn't know are
+    /// killed, since their resources are accounted nowhere.
+    ///
+    /// Returns:
+    ///
+    /// True if instances were forgotten, freeing capacity on the node.
+    fn reconcile(&self, node_id: &str, running: &[String]) -> bool {
+        let reconciliation = self
+            .instances
+            .read(|instances| persistence::reconcile(node_id, running, instances));
+        info!(
+            "node {} reconciled : {} instance(s) running, {} gone, {} unknown",
+            node_id,
+            running.len(),
+            reconciliation.gone.len(),
+            reconciliation.unknown.len()
+        );
+
+        for id in &reconciliation.gone {
+            info!("instance {} no longer runs on node {}", id, node_id);
+            self.forget(id);
+        }
+        if let Some(agent) = self.agents.get(node_id) {
+            for id in reconciliation.unknown {
+                warn!("killing instance {} unknown on node {}", id, node_id);
+                let instance = Instance {
+                    id,
+                    ..Default::default()
+                };
+                Self::signal(Ok((agent.clone(), instance)), Signal::Kill, None);
+            }
+        }
+        !reconciliation.gone.is_empty()
+    }
+
+    /// It chooses the node of an instance and gives it an address, streaming the `SCHEDULING`
+    /// status and then `SCHEDULED`, or `FAILED` if the instance can't be scheduled. A scheduled
+    /// instance is then created by the agent of its node, whose statuses are relayed. An
+    /// instance no node can run yet is `PENDING` until capacity frees up.
+    ///
+    /// Arguments:
+    ///
+    /// * `instance`: The instance to schedule.
+    /// * `tx`: The stream of the statuses of the instance.
+    fn schedule(&self, instance: Instance, tx: StatusSender) {
+        let id = instance.id.clone();
+        let status = instance_status(&id, Status::Scheduling, "Looking for a node".to_string());
+        self.notify(&tx, status);
+
+        match self.place_or_preempt(instance.clone(), tx.clone()) {
+            Ok(placed) => {
+                info!(
+                    "instance {} placed on node {} with address {}",
+                    id, placed.node_id, placed.instance.ip
+                );
+                self.dispatch(placed, tx);
+            }
+            Err(SchedulerError::Unschedulable(reason)) => {
+                info!("instance {} is pending : {}", id, reason);
+                self.pending.lock().unwrap().push(PendingInstance {
+                    instance,
+                    watcher: tx.clone(),
+                    reason: reason.clone(),
+                    deadline: self.pending_deadline(),
+                });
+                let status = instance_status(&id, Status::Pending, reason);
+                self.notify(&tx, status);
+            }
+            Err(err) => {
+                info!("instance {} can't be scheduled : {}", id, err);
+                let status = instance_status(&id, Status::Failed, err.to_string());
+                self.notify(&tx, status);
+            }
+        }
+    }
+
+    /// It sends the next statuses of an instance to a new stream, e.g. the one of the controller
+    /// once the scheduler restarted. No status is sent for the instances the scheduler knows, so
+    /// that the client keeps the last one, while the ones it doesn't know are failed.
+    ///
+    /// Arguments:
+    ///
+    /// * `id`: The id of the instance.
+    /// * `tx`: The new stream of the statuses of the instance.
+    fn watch(&self, id: &str, tx: StatusSender) {
+        let placed = self
+            .instances
+            .modify(id, |placed| placed.watcher = Some(tx.clone()));
+        if placed || self.pending.lock().unwrap().watch(id, tx.clone()) {
+            return;
+        }
+
+        info!("instance {} is unknown, it can't be watched", id);
+        let err = SchedulerError::InstanceNotFound(id.to_string());
+        self.notify(&tx, instance_status(id, Status::Failed, err.to_string()));
+    }
+
+    /// It tries again to place the pending instances, highest priority then oldest first, as
+    /// capacity may have freed up. The outcome of each retry is streamed to its instance, unless the instance is still
+    /// pending for the same reason. The instances whose stream was closed are dropped.
+    fn retry_pending(&self) {
+        let queued = self.pending.lock().unwrap().take();
+        if !queued.is_empty() {
+            debug!("retrying {} pending instance(s)", queued.len());
+        }
+
+        for mut pending in queued {
+            if pending.watcher.is_closed() {
+                continue;
+            }
+            let id = pending.instance.id.clone();
+            match self.place_or_preempt(pending.instance.clone(), pending.watcher.clone()) {
+                Ok(placed) => {
+                    info!(
+                        "pending instance {} placed on node {} with address {}",
+                        id, placed.node_id, placed.instance.ip
+                    );
+                    self.dispatch(placed, pending.watcher);
+                }
+                Err(SchedulerError::Unschedulable(reason)) => {
+                    if reason != pending.reason {
+                        debug!("instance {} still pending : {}", id, reason);
+                        let status = instance_status(&id, Status::Pending, reason.clone());
+                        self.notify(&pending.watcher, status);
+                        pending.reason = reason;
                     }
-                    Event::NodeRegister(request, tx) => {
-                        info!("received node register event : {:?}", request);
-                        tx.send(Ok(Response::new(NodeRegisterResponse::default())))
-                            .unwrap();
+                    self.pending.lock().unwrap().push(pending);
+                }
+                Err(err) => {
+                    info!("pending instance {} can't be scheduled : {}", id, err);
+                    let status = instance_status(&id, Status::Failed, err.to_string());
+                    self.notify(&pending.watcher, status);
+                }
+            }
+        }
+    }
+
+    /// It fails the nodes which stopped sending their status and marks their instances as
+    /// lost, then reschedules the instances whose node didn't come back within the grace period.
+    /// The pending instances which waited longer than the pending timeout are failed.
+    fn check_nodes(&self) {
+        let check = self.nodes.write(|nodes| {
+            self.instances.write(|instances| {
+                health::check(
+                    nodes,
+                    instances,
+                    Instant::now(),
+                    Duration::from_secs(self.config.node_heartbeat_timeout),
+                    Duration::from_secs(self.config.reschedule_grace_period),
+                )
+            })
+        });
+
+        for placed in &check.lost {
+            warn!(
+                "instance {} lost with node {}, rescheduled in {} second(s) unless the node comes back",
+                placed.instance.id, placed.node_id, self.config.reschedule_grace_period
+            );
+        }
+        for placed in &check.recovered {
+            info!(
+                "node {} of instance {} is back",
+                placed.node_id, placed.instance.id
+            );
+        }
+        for placed in check.expired {
+            self.reschedule(placed);
+        }
+
+        let expired = self.pending.lock().unwrap().expire(Instant::now());
+        for pending in expired {
+            let description = format!(
+                "No node could run the instance within {} second(s): {}",
+                self.config.pending_timeout.unwrap_or_default(),
+                pending.reason
+            );
+            info!("instance {} failed : {}", pending.instance.id, description);
+            let status = instance_status(&pending.instance.id, Status::Failed, description);
+            self.notify(&pending.watcher, status);
+        }
+
+        if !check.recovered.is_empty() {
+            self.retry_pending();
+        }
+
+        let drained: Vec<NodeIdentifier> = self.nodes.read(|nodes| {
+            nodes
+                .get_all()
+                .values()
+                .filter(|node| node.drain.is_some())
+                .map(|node| node.id.clone())
+                .collect()
+        });
+        for id in drained {
+            self.drain(&id);
+        }
+    }
+
+    /// It moves on with the drain of a node: the moves which completed are settled, then the
+    /// next instances of the node are moved within the budget of their workload.
+    fn drain(&self, node_id: &str) {
+        let moves = self.nodes.write(|nodes| {
+            self.instances.read(|instances| {
+                let drain = nodes.get_mut(node_id)?.drain.as_mut()?;
+                let pending = self.pending.lock().unwrap();
+                drain.settle(node_id, instances, |id| pending.contains(id));
+                Some(drain.next(&PlacedInstance::on_node(node_id, instances)))
+            })
+        });
+
+        for placed in moves.unwrap_or_default() {
+            if !self.move_instance(placed) {
+                self.nodes.write(|nodes| {
+                    if let Some(drain) = nodes.get_mut(node_id).and_then(|node| node.drain.as_mut())
+                    {
+                        drain.evicted += 1;
                     }
-                    Event::NodeUnregister(request, tx) => {
-                        info!("received node unregister event : {:?}", request);
-                        tx.send(Ok(Response::new(NodeUnregisterResponse::default())))
-                            .unwrap();
+                });
+            }
+        }
+    }
+
+    /// It moves an instance off a drained node: it is placed on another node with a new address,
+    /// then the agent of the drained node is asked to kill it. An instance no other node can run
+    /// is evicted to the pending queue. The statuses of an instance restored without stream are
+    /// dropped.
+    ///
+    /// Returns:
+    ///
+    /// True if the instance was placed on another node.
+    fn move_instance(&self, mut placed: PlacedInstance) -> bool {
+        let id = placed.instance.id.clone();
+        let tx = placed.watcher.get_or_insert_with(detached_watcher).clone();
+
+        let mut instance = placed.instance.clone();
+        instance.ip.clear();
+        // the instance counts as moved once its new node reports it running
+        instance.status = Status::Scheduling.into();
+        match self.place_or_preempt(instance, tx.clone()) {
+            Ok(moved) => {
+                self.ipam.lock().unwrap().release_on(&placed.node_id, &id);
+                info!(
+                    "instance {} moved from drained node {} to node {} with address {}",
+                    id, placed.node_id, moved.node_id, moved.instance.ip
+                );
+                if let Some(agent) = self.agents.get(&placed.node_id) {
+                    Self::signal(Ok((agent, placed.instance)), Signal::Kill, None);
+                }
+                self.dispatch(moved, tx);
+                true
+            }
+            Err(err) => {
+                let description = format!("Drained from node {}: {}", placed.node_id, err);
+                self.forget(&id);
+                self.evict(placed, "Drained", description);
+                false
+            }
+        }
+    }
+
+    /// It places an instance lost with its node on a healthy node, with a new address. An
+    /// instance which can't be placed yet stays lost until the next check. The agent of the
+    /// failed node is asked to kill the instance, in case the node comes back. The statuses of
+    /// an instance restored without stream are dropped.
+    fn reschedule(&self, mut lost: PlacedInstance) {
+        let id = lost.instance.id.clone();
+        let tx = lost.watcher.get_or_insert_with(detached_watcher).clone();
+
+        let mut instance = lost.instance.clone();
+        instance.ip.clear();
+        let placed = match self.place_instance(instance, tx.clone()) {
+            Ok(placed) => placed,
+            Err(err) => {
+                debug!("instance {} can't be rescheduled yet : {}", id, err);
+                return;
+            }
+        };
+        self.ipam.lock().unwrap().release_on(&lost.node_id, &id);
+        info!(
+            "instance {} rescheduled from node {} to node {} with address {}",
+            id, lost.node_id, placed.node_id, placed.instance.ip
+        );
+
+        if let Some(agent) = self.agents.get(&lost.node_id) {
+            Self::signal(Ok((agent, lost.instance)), Signal::Kill, None);
+        }
+        self.dispatch(placed, tx);
+    }
+
+    /// It reports an instance as scheduled, then has it created by the agent of its node.
+    fn dispatch(&self, placed: PlacedInstance, tx: StatusSender) {
+        let id = placed.instance.id.clone();
+        let agent = self.agents.get(&placed.node_id);
+        let status = match agent {
+            Some(_) => InstanceStatus {
+                ip: placed.instance.ip.clone(),
+                ..instance_status(
+                    &id,
+                    Status::Scheduled,
+                    format!("Scheduled on node {}", placed.node_id),
+                )
+            },
+            None => {
+                // the node has been unregistered in the meantime
+                self.forget(&id);
+                let err = SchedulerError::NodeNotRegistered(placed.node_id.clone());
+                instance_status(&id, Status::Failed, err.to_string())
+            }
+        };
+        self.notify(&tx, status);
+
+        if let Some(agent) = agent {
+            self.relay(agent, placed.instance, tx);
+        }
+    }
+
+    /// It spawns a task creating an instance on the agent of its node, and relaying the
+    /// statuses reported by the agent to the stream of the instance. An instance which fails is
+    /// forgotten, releasing its resources and address. The relay stops once the instance has
+    /// been rescheduled on another node.
+    fn relay(&self, mut agent: AgentClient, instance: Instance, tx: StatusSender) {
+        let manager = self.clone();
+
+        tokio::spawn(async move {
+            let id = instance.id.clone();
+            let mut stream = match agent.create(instance).await {
+                Ok(stream) => stream,
+                Err(err) => {
+                    warn!("instance {} can't be created : {}", id, err);
+                    manager.forget(&id);
+                    let status = instance_status(&id, Status::Failed, err.to_string());
+                    manager.notify(&tx, status);
+                    return;
+                }
+            };
+
+            loop {
+                let status = match stream.message().await {
+                    Ok(Some(status)) => agent_client::instance_status(status),
+                    Ok(None) => break,
+                    // the node is failed by the node check if it doesn't come back
+                    Err(err) => {
+                        warn!("status stream of instance {} failed : {}", id, err);
+                        break;
                     }
-                    Event::NodeStatus(status, tx) => {
-                        info!("received node status event : {:?}", status);
-                        tx.send(Ok(())).await.unwrap();
+                };
+
+                // the status is kept to index the instances by status
+                let placed_here = manager.instances.write(|instances| {
+                    let placed_here = instances
+                        .get(&id)
+                        .is_some_and(|placed| placed.node_id == agent.node_id());
+                    if placed_here {
+                        instances.modify(&id, |placed| placed.instance.status = status.status);
                     }
+                    placed_here
+                });
+                if !placed_here {
+                    debug!("instance {} no longer runs on node {}", id, agent.node_id());
+                    break;
+                }
+
+                let failed = status.status() == Status::Failed;
+                if failed {
+                    manager.forget(&id);
+                }
+                if tx.send(Ok(status)).await.is_err() {
+                    debug!("status stream of instance {} closed by the controller", id);
+                    break;
+                }
+                if failed {
+                    break;
+                }
+            }
+        });
+    }
+
+    /// It returns the client of the agent running an instance, along with the instance
+    fn agent_of(&self, id: &str) -> Result<(AgentClient, Instance), SchedulerError> {
+        let placed = self
+            .instances
+            .get(id)
+            .ok_or_else(|| SchedulerError::InstanceNotFound(id.to_string()))?;
+        let agent = self
+            .agents
+            .get(&placed.node_id)
+            .ok_or(SchedulerError::NodeNotRegistered(placed.node_id))?;
+
+        Ok((agent, placed.instance))
+    }
+
+    /// It spawns a task sending a signal to an instance through the agent of its node, then
+    /// replying to the request with the result, if any.
+    fn signal(
+        target: Result<(AgentClient, Instance), SchedulerError>,
+        signal: Signal,
+        tx: Option<oneshot::Sender<Result<Response<()>, tonic::Status>>>,
+    ) {
+        tokio::spawn(async move {
+            let result = match target {
+                Ok((mut agent, instance)) => agent.signal(instance, signal).await,
+                Err(err) => Err(err),
+            };
+            if let Err(err) = &result {
+                warn!("{:?} signal failed : {}", signal, err);
+            }
+            if let Some(tx) = tx {
+                reply(
+                    tx.send(result.map(Response::new).map_err(Into::into))
+                        .is_ok(),
+                );
+            }
+        });
+    }
+
+    /// It chooses the node of an instance through the scheduling pipeline, gives it an address
+    /// from the subnet of the node and records the placement along with the decision.
+    ///
+    /// Returns:
+    ///
+    /// The placed instance, or `Unschedulable` with the reason no node can run the instance.
+    fn place_instance(
+        &self,
+        mut instance: Instance,
+        watcher: StatusSender,
+    ) -> Result<PlacedInstance, SchedulerError> {
+        self.nodes.read(|nodes| {
+            self.instances.write(|instances| {
+                let decision = self.pipeline.place(&instance, nodes, instances);
+                debug!("{}", decision);
+                let node_id = decision
+                    .node_id
+                    .clone()
+                    .ok_or_else(|| SchedulerError::Unschedulable(decision.reason()));
+                self.decisions.update(&instance.id, decision);
+                let node_id = node_id?;
+                let address = self.ipam.lock().unwrap().allocate(&node_id, &instance.id)?;
+                instance.ip = address.address().to_string();
+
+                let placed = PlacedInstance {
+                    node_id,
+                    instance,
+                    lost_since: None,
+                    watcher: Some(watcher),
+                };
+                self.state
+                    .lock()
+                    .unwrap()
+                    .record(persistence::instance_put(&placed));
+                instances.update(&placed.instance.id, placed.clone());
+                Ok(placed)
+            })
+        })
+    }
+
+    /// It places an instance, or if no node can run it, evicts the cheapest set of instances with
+    /// a lower priority from a single node to make room for it there.
+    ///
+    /// Returns:
+    ///
+    /// The placed instance, or `Unschedulable` with the reason no node can run the instance even
+    /// by preempting other instances.
+    fn place_or_preempt(
+        &self,
+        instance: Instance,
+        watcher: StatusSender,
+    ) -> Result<PlacedInstance, SchedulerError> {
+        let reason = match self.place_instance(instance.clone(), watcher.clone()) {
+            Err(SchedulerError::Unschedulable(reason)) => reason,
+            result => return result,
+        };
+
+        let preemption = self.nodes.read(|nodes| {
+            self.instances
+                .read(|instances| preemption::find(&instance, &self.pipeline, nodes, instances))
+        });
+        let preemption = match preemption {
+            Some(preemption) => preemption,
+            None => return Err(SchedulerError::Unschedulable(reason)),
+        };
+
+        for victim in &preemption.victims {
+            self.forget(&victim.instance.id);
+        }
+        let placed = match self.place_instance(instance, watcher) {
+            Ok(placed) => placed,
+            Err(err) => {
+                // the victims keep running on their node
+                for victim in preemption.victims {
+                    self.give_back(victim);
+                }
+                return Err(err);
+            }
+        };
+        for victim in preemption.victims {
+            let description = format!(
+                "Preempted by instance {} on node {}",
+                placed.instance.id, placed.node_id
+            );
+            self.evict(victim, "Preempted", description);
+        }
+        Ok(placed)
+    }
+
+    /// It stops an instance through the agent of its node, e.g. when it is preempted by another
+    /// one, then puts it back in the pending queue and reports why on its stream.
+    ///
+    /// Arguments:
+    ///
+    /// * `victim`: The instance to evict, already forgotten.
+    /// * `reason`: Why the instance is evicted, e.g. `Preempted`.
+    /// * `description`: What evicted the instance.
+    fn evict(&self, victim: PlacedInstance, reason: &str, description: String) {
+        let id = victim.instance.id.clone();
+        info!("instance {} evicted : {}", id, description);
+
+        if let Some(agent) = self.agents.get(&victim.node_id) {
+            Self::signal(Ok((agent, victim.instance.clone())), Signal::Kill, None);
+        }
+
+        let tx = match victim.watcher {
+            Some(tx) => tx,
+            None => return,
+        };
+        let mut instance = victim.instance;
+        instance.ip.clear();
+        self.pending.lock().unwrap().push(PendingInstance {
+            instance,
+            watcher: tx.clone(),
+            reason: description.clone(),
+            deadline: self.pending_deadline(),
+        });
+
+        let status = InstanceStatus {
+            reason: reason.to_string(),
+            ..instance_status(&id, Status::Pending, description)
+        };
+        self.notify(&tx, status);
+    }
+
+    /// It sends a status to the stream of an instance. A client whose stream is full misses it,
+    /// so that a slow client can neither hold up the event loop nor fail the event.
+    fn notify(&self, tx: &StatusSender, status: InstanceStatus) {
+        let id = status.id.clone();
+        match tx.try_send(Ok(status)) {
+            Ok(()) => {}
+            Err(TrySendError::Full(_)) => warn!(
+                "status of instance {} dropped, its client doesn't take its statuses",
+                id
+            ),
+            Err(TrySendError::Closed(_)) => reply(false),
+        }
+    }
+
+    /// It returns when an instance becoming pending now is failed, if ever
+    fn pending_deadline(&self) -> Option<Instant> {
+        self.config
+            .pending_timeout
+            .map(|timeout| Instant::now() + Duration::from_secs(timeout))
+    }
+
+    /// It removes the placement of an instance along with its saved state, its last placement
+    /// decision, and releases its address
+    fn forget(&self, id: &str) {
+        self.instances.write(|instances| {
+            if instances.get(id).is_some() {
+                instances.delete(id);
+                self.state
+                    .lock()
+                    .unwrap()
+                    .record(persistence::instance_delete(id));
+            }
+        });
+        self.decisions.delete(id);
+        self.ipam.lock().unwrap().release(id);
+    }
+
+    /// It stores again an instance forgotten while it still runs on its node, with its address
+    fn give_back(&self, placed: PlacedInstance) {
+        let id = placed.instance.id.clone();
+        restore_address(
+            &mut self.ipam.lock().unwrap(),
+            &placed.node_id,
+            &placed.instance,
+        );
+        self.instances.write(|instances| {
+            self.state
+                .lock()
+                .unwrap()
+                .record(persistence::instance_put(&placed));
+            instances.update(&id, placed);
+        });
+    }
+
+    /// It spawns a task sending a `NodeCheck` event three times per heartbeat timeout, until
+    /// `shutdown` is triggered.
+    fn check_nodes_periodically(
+        &self,
+        tx: mpsc::Sender<Event>,
+        mut shutdown: Shutdown,
+    ) -> JoinHandle<()> {
+        let period = (Duration::from_secs(self.config.node_heartbeat_timeout) / 3)
+            .max(Duration::from_secs(1));
+
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(period);
+            loop {
+                tokio::select! {
+                    _ = interval.tick() => {},
+                    _ = shutdown.wait() => break,
+                }
+                if tx.send(Event::NodeCheck).await.is_err() {
+                    break;
                 }
             }
         })
     }
 
     /// The function creates a channel to communicate with the orchestrator, creates a gRPC server and a
-    /// listener for incoming events, and then waits for the end of all the threads
+    /// listener for incoming events, and then runs until the process receives SIGINT or SIGTERM.
+    /// On shutdown, the requests in flight are given `shutdown_timeout` seconds to complete.
     ///
     /// Returns:
     ///
     /// A Result<(), Box<dyn std::error::Error>>
     pub async fn run(&self) -> Result<(), Box<dyn std::error::Error>> {
-        let mut handlers = vec![];
+        let (shutdown_trigger, shutdown) = shutdown::channel();
         let (tx, rx) = Self::create_mpsc_channel();
 
+        // fail the nodes which stop sending their status
+        let node_checks = self.check_nodes_periodically(tx.clone(), shutdown.clone());
+
         // create listeners and serve the grpc server
-        handlers.push(self.create_grpc_server(tx)?);
+        let grpc_server = self.create_grpc_server(tx, shutdown)?;
 
-        // listen for incoming events and pass them to the orchestrator
-        handlers.push(self.listen_events(rx));
+        // listen for incoming events and pass them to the orchestrator,
+        // until the listeners are dropped along with the grpc server
+        let mut events = self.listen_events(rx);
 
         info!("scheduler running and ready to receive incoming requests ...");
 
+        // the scheduler can't serve any request without its event loop
+        tokio::select! {
+            _ = shutdown::signal() => {},
+            stopped = &mut events => {
+                shutdown_trigger.trigger();
+                return match stopped {
+                    Ok(Ok(())) => Err(SchedulerError::EventLoop("no event left".to_string()).into()),
+                    Ok(Err(err)) => Err(err.into()),
+                    Err(err) => Err(err.into()),
+                };
+            }
+        }
+        info!(
+            "draining requests in flight for up to {} second(s) ...",
+            self.config.shutdown_timeout
+        );
+        shutdown_trigger.trigger();
+
         // wait the end of all the threads
-        for handler in handlers {
-            handler.await?;
+        let drained =
+            tokio::time::timeout(Duration::from_secs(self.config.shutdown_timeout), async {
+                grpc_server.await??;
+                node_checks.await?;
+                events.await??;
+                Ok::<(), Box<dyn std::error::Error>>(())
+            })
+            .await;
+
+        match drained {
+            Ok(result) => result,
+            Err(_) => Err(SchedulerError::ShutdownTimeout(self.config.shutdown_timeout).into()),
+        }
+    }
+}
+
+fn instance_status(id: &str, status: Status, description: String) -> InstanceStatus {
+    InstanceStatus {
+        id: id.to_string(),
+        status: status.into(),
+        status_description: description,
+        ..Default::default()
+    }
+}
+
+/// It marks the address of an instance placed on a node as used, if it has one
+fn restore_address(ipam: &mut Ipam, node_id: &str, instance: &Instance) {
+    if instance.ip.is_empty() {
+        return;
+    }
+    let restored = instance
+        .ip
+        .parse()
+        .map_err(|_| SchedulerError::StatePersistence(format!("invalid address {}", instance.ip)))
+        .and_then(|address| ipam.restore(node_id, &instance.id, address));
+    if let Err(err) = restored {
+        warn!("address of instance {} not restored : {}", instance.id, err);
+    }
+}
+
+/// It creates a stream for the statuses of an instance nobody watches, e.g. an instance restored
+/// from the saved state, which drops them
+fn detached_watcher() -> StatusSender {
+    let (tx, mut rx) = Manager::create_mpsc_channel();
+    tokio::spawn(async move { while rx.recv().await.is_some() {} });
+    tx
+}
+
+/// It logs a reply to a request which couldn't be sent, because the request has been dropped
+fn reply(sent: bool) {
+    if !sent {
+        debug!("request dropped before its reply was sent");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{instance, node, place_on, placed};
+    use proto::scheduler::{instance_service_server::InstanceService, InstanceIdentifier};
+    use tonic::{Code, Request};
+
+    fn manager(event_timeout: u64) -> Manager {
+        let config = Config {
+            event_timeout,
+            ..Default::default()
+        };
+        Manager::with_state_backend(config, Box::new(MemoryBackend::new())).unwrap()
+    }
+
+    fn register(manager: &Manager, node: Node) {
+        manager.ipam.lock().unwrap().add_node(&node.id, node.subnet);
+        manager
+            .agents
+            .update(&node.id, AgentClient::new(&node.id, &node.address).unwrap());
+        manager.nodes.update(&node.id.clone(), node);
+    }
+
+    async fn list_nodes(events: &mpsc::Sender<Event>) -> Result<(), tonic::Status> {
+        let (tx, rx) = Manager::create_oneshot_channel();
+        events.send(Event::NodeList(tx)).await.unwrap();
+        let reply = tokio::time::timeout(Duration::from_secs(5), Manager::wait_reply(rx));
+        reply.await.expect("event loop blocked").map(|_| ())
+    }
+
+    async fn list_pending(events: &mpsc::Sender<Event>) -> Result<(), tonic::Status> {
+        let (tx, rx) = Manager::create_oneshot_channel();
+        events.send(Event::InstancePending(tx)).await.unwrap();
+        Manager::wait_reply(rx).await.map(|_| ())
+    }
+
+    #[tokio::test]
+    async fn test_dropped_clients() {
+        let manager = manager(10);
+        let (events, rx) = Manager::create_mpsc_channel();
+        let _loop = manager.listen_events(rx);
+
+        // the client cancels its request before the reply
+        let (tx, rx) = Manager::create_oneshot_channel();
+        drop(rx);
+        events.send(Event::NodeList(tx)).await.unwrap();
+
+        // the client closes the status stream of its instance
+        let (tx, rx) = Manager::create_mpsc_channel();
+        drop(rx);
+        let instance = Instance {
+            id: "web-1".to_string(),
+            ..Default::default()
+        };
+        events
+            .send(Event::InstanceCreate(Box::new(instance), tx))
+            .await
+            .unwrap();
+
+        assert!(list_nodes(&events).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_stuck_client_does_not_block_event_loop() {
+        let manager = manager(1);
+        let (events, rx) = Manager::create_mpsc_channel();
+        let _loop = manager.listen_events(rx);
+
+        // the client doesn't read the statuses of its instance anymore
+        let (tx, _rx) = mpsc::channel(1);
+        tx.send(Ok(InstanceStatus::default())).await.unwrap();
+        let instance = Instance {
+            id: "web-1".to_string(),
+            ..Default::default()
+        };
+        events
+            .send(Event::InstanceCreate(Box::new(instance), tx))
+            .await
+            .unwrap();
+
+        assert!(list_nodes(&events).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_failed_events() {
+        let manager = manager(10);
+        let (events, rx) = Manager::create_mpsc_channel();
+        let event_loop = manager.listen_events(rx);
+
+        // a panic while the pending queue is locked poisons it
+        let pending = manager.pending.clone();
+        std::thread::spawn(move || {
+            let _queue = pending.lock().unwrap();
+            panic!("poisoning the pending queue");
+        })
+        .join()
+        .unwrap_err();
+
+        let failed = list_pending(&events).await.unwrap_err();
+        assert_eq!(Code::DeadlineExceeded, failed.code());
+        assert!(list_nodes(&events).await.is_ok());
+
+        for _ in 0..MAX_CONSECUTIVE_FAILURES {
+            assert!(list_pending(&events).await.is_err());
+        }
+        let stopped = tokio::time::timeout(Duration::from_secs(5), event_loop)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(matches!(stopped, Err(SchedulerError::EventLoop(_))));
+    }
+
+    #[tokio::test]
+    async fn test_listener_without_reply() {
+        let (events, mut rx) = Manager::create_mpsc_channel();
+        // the manager drops the requests without replying
+        tokio::spawn(async move { while rx.recv().await.is_some() {} });
+
+        let listener = InstanceListener::new(events);
+        let request = Request::new(InstanceIdentifier {
+            id: "web-1".to_string(),
+        });
+        let failed = listener.start(request).await.unwrap_err();
+        assert_eq!(Code::DeadlineExceeded, failed.code());
+    }
+
+    #[tokio::test]
+    async fn test_restart_keeps_the_addresses_of_the_instances() {
+        let directory = tempfile::tempdir().unwrap();
+        let mut state = StateLog::open(Box::new(FileBackend::new(directory.path())), 1000).unwrap();
+        state.record(persistence::node_put(&node("node-a")));
+        let mut placed = placed("web-1", "node-a");
+        placed.instance.ip = "10.0.1.2".to_string();
+        state.record(persistence::instance_put(&placed));
+        drop(state);
+
+        let manager = Manager::with_state_backend(
+            Config::default(),
+            Box::new(FileBackend::new(directory.path())),
+        )
+        .unwrap();
+        let mut ipam = manager.ipam.lock().unwrap();
+        assert_eq!(
+            "10.0.1.2/24",
+            ipam.allocate("node-a", "web-1").unwrap().to_string()
+        );
+        assert_eq!(
+            "10.0.1.3/24",
+            ipam.allocate("node-a", "web-2").unwrap().to_string()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_watch_after_restart() {
+        let directory = tempfile::tempdir().unwrap();
+        let mut state = StateLog::open(Box::new(FileBackend::new(directory.path())), 1000).unwrap();
+        state.record(persistence::node_put(&node("node-a")));
+        state.record(persistence::instance_put(&placed("web-1", "node-a")));
+        drop(state);
+
+        let manager = Manager::with_state_backend(
+            Config::default(),
+            Box::new(FileBackend::new(directory.path())),
+        )
+        .unwrap();
+        let (events, rx) = Manager::create_mpsc_channel();
+        let _loop = manager.listen_events(rx);
+
+        let (tx, mut known) = Manager::create_mpsc_channel();
+        events
+            .send(Event::InstanceWatch("web-1".to_string(), tx))
+            .await
+            .unwrap();
+        let (tx, mut unknown) = Manager::create_mpsc_channel();
+        events
+            .send(Event::InstanceWatch("web-2".to_string(), tx))
+            .await
+            .unwrap();
+        assert!(list_nodes(&events).await.is_ok());
+
+        // the instance keeps running on its node, its client keeps its last status
+        let placed = manager.instances.get("web-1").expect("instance forgotten");
+        assert!(placed.watcher.is_some());
+        assert!(known.try_recv().is_err());
+        assert_eq!(1, manager.instances.len());
+
+        let status = unknown.recv().await.unwrap().unwrap();
+        assert_eq!(Status::Failed, status.status());
+    }
+
+    #[tokio::test]
+    async fn test_reschedule_instance_without_stream() {
+        let manager = manager(10);
+        register(
+            &manager,
+            Node {
+                subnet: "10.0.2.0/24".parse().unwrap(),
+                ..node("node-b")
+            },
+        );
+        let mut lost = placed("web-1", "node-a");
+        lost.instance.ip = "10.0.1.2".to_string();
+        lost.lost_since = Some(Instant::now());
+        manager.instances.update("web-1", lost.clone());
+
+        manager.reschedule(lost);
+
+        let placed = manager.instances.get("web-1").expect("instance forgotten");
+        assert_eq!("node-b", placed.node_id);
+        assert_eq!("10.0.2.2", placed.instance.ip);
+        assert!(placed.watcher.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_failed_preemption_keeps_the_victims() {
+        let manager = manager(10);
+        register(&manager, node("node-a"));
+        // the node is unregistered while the preemption is computed
+        manager.ipam.lock().unwrap().remove_node("node-a");
+        manager
+            .instances
+            .update("batch-1", place_on("node-a", instance("batch-1", 800, 0)));
+
+        let placed = manager.place_or_preempt(instance("web-1", 500, 10), detached_watcher());
+
+        assert!(matches!(placed, Err(SchedulerError::NodeNotRegistered(_))));
+        let victim = manager.instances.get("batch-1").expect("victim forgotten");
+        assert_eq!("node-a", victim.node_id);
+        assert!(manager.instances.get("web-1").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_stuck_clients_do_not_fail_the_drain() {
+        let manager = manager(1);
+        register(&manager, node("node-a"));
+        // the clients of the instances of the node don't read their statuses anymore
+        let mut clients = Vec::new();
+        for id in ["web-1", "web-2"] {
+            let (tx, rx) = mpsc::channel(1);
+            tx.send(Ok(InstanceStatus::default())).await.unwrap();
+            let mut placed = placed(id, "node-a");
+            placed.watcher = Some(tx);
+            manager.instances.update(id, placed);
+            clients.push(rx);
+        }
+        let (events, rx) = Manager::create_mpsc_channel();
+        let event_loop = manager.listen_events(rx);
+
+        for _ in 0..MAX_CONSECUTIVE_FAILURES {
+            let (tx, rx) = Manager::create_oneshot_channel();
+            let request = NodeDrainRequest {
+                id: "node-a".to_string(),
+                max_unavailable: 2,
+            };
+            events.send(Event::NodeDrain(request, tx)).await.unwrap();
+            assert!(Manager::wait_reply(rx).await.is_ok());
         }
 
-        Ok(())
+        // no other node can run the instances, they are evicted to the pending queue
+        assert!(manager.instances.get("web-1").is_none());
+        assert!(manager.pending.lock().unwrap().contains("web-1"));
+        assert!(manager.pending.lock().unwrap().contains("web-2"));
+        assert!(list_nodes(&events).await.is_ok());
+        assert!(!event_loop.is_finished());
     }
 }
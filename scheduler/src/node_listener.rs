@@ -1,22 +1,26 @@
 use log::debug;
 use proto::scheduler::{
-    node_service_server::NodeService, NodeRegisterRequest, NodeRegisterResponse, NodeStatus,
-    NodeUnregisterRequest, NodeUnregisterResponse,
+    node_service_server::NodeService, NodeCordonRequest, NodeCordonResponse, NodeDrainRequest,
+    NodeDrainStatus, NodeDrainStatusRequest, NodeIdentifier, NodeInfo, NodeListResponse,
+    NodeRegisterRequest, NodeRegisterResponse, NodeStatus, NodeUnregisterRequest,
+    NodeUnregisterResponse, NodeUpdateRequest, NodeUpdateResponse,
 };
 use tokio::sync::mpsc;
 use tonic::{Request, Response, Status, Streaming};
 
 use crate::{manager::Manager, Event};
+use shutdown::Shutdown;
 
 #[derive(Debug)]
 #[allow(dead_code)]
 pub struct NodeListener {
     sender: mpsc::Sender<Event>,
+    shutdown: Shutdown,
 }
 
 impl NodeListener {
-    pub fn new(sender: mpsc::Sender<Event>) -> Self {
-        NodeListener { sender }
+    pub fn new(sender: mpsc::Sender<Event>, shutdown: Shutdown) -> Self {
+        NodeListener { sender, shutdown }
     }
 }
 
@@ -27,27 +31,37 @@ impl NodeService for NodeListener {
         request: Request<Streaming<NodeStatus>>,
     ) -> Result<Response<()>, Status> {
         let mut stream = request.into_inner();
-        let (tx, mut rx) = Manager::create_mpsc_channel();
+        let mut shutdown = self.shutdown.clone();
 
         loop {
-            let message = stream.message().await?;
+            // the stream is closed on shutdown, the node reconnects once the scheduler is back
+            let message = tokio::select! {
+                message = stream.message() => message?,
+                _ = shutdown.wait() => {
+                    debug!("Closing node status stream on shutdown");
+                    return Ok(Response::new(()));
+                }
+            };
             match message {
                 Some(node_status) => {
                     debug!("Node status: {:?}", node_status);
-                    self.sender
-                        .send(Event::NodeStatus(node_status, tx.clone()))
+                    let (tx, rx) = Manager::create_oneshot_channel();
+                    if self
+                        .sender
+                        .send(Event::NodeStatus(node_status, tx))
                         .await
-                        .unwrap();
-
-                    if let Some(res) = rx.recv().await {
-                        match res {
-                            Ok(()) => {
-                                debug!("Node status updated successfully");
-                            }
-                            Err(err) => {
-                                debug!("Error updating node status: {:?}", err);
-                                return Err(err);
-                            }
+                        .is_err()
+                    {
+                        return Err(Status::internal("could not send event to manager"));
+                    }
+
+                    match Manager::wait_reply(rx).await {
+                        Ok(()) => {
+                            debug!("Node status updated successfully");
+                        }
+                        Err(err) => {
+                            debug!("Error updating node status: {:?}", err);
+                            return Err(err);
                         }
                     }
                 }
@@ -71,7 +85,7 @@ impl NodeService for NodeListener {
             .await
         {
             Ok(_) => {
-                return rx.await.unwrap();
+                return Manager::wait_reply(rx).await;
             }
             Err(_) => {
                 return Err(Status::internal("could not send event to manager"));
@@ -92,7 +106,123 @@ impl NodeService for NodeListener {
             .await
         {
             Ok(_) => {
-                return rx.await.unwrap();
+                return Manager::wait_reply(rx).await;
+            }
+            Err(_) => {
+                return Err(Status::internal("could not send event to manager"));
+            }
+        }
+    }
+
+    async fn update(
+        &self,
+        request: Request<NodeUpdateRequest>,
+    ) -> Result<Response<NodeUpdateResponse>, Status> {
+        debug!("{:?}", request);
+        let (tx, rx) = Manager::create_oneshot_channel();
+
+        match self
+            .sender
+            .send(Event::NodeUpdate(request.into_inner(), tx))
+            .await
+        {
+            Ok(_) => {
+                return Manager::wait_reply(rx).await;
+            }
+            Err(_) => {
+                return Err(Status::internal("could not send event to manager"));
+            }
+        }
+    }
+
+    async fn cordon(
+        &self,
+        request: Request<NodeCordonRequest>,
+    ) -> Result<Response<NodeCordonResponse>, Status> {
+        debug!("{:?}", request);
+        let (tx, rx) = Manager::create_oneshot_channel();
+
+        match self
+            .sender
+            .send(Event::NodeCordon(request.into_inner(), tx))
+            .await
+        {
+            Ok(_) => {
+                return Manager::wait_reply(rx).await;
+            }
+            Err(_) => {
+                return Err(Status::internal("could not send event to manager"));
+            }
+        }
+    }
+
+    async fn drain(
+        &self,
+        request: Request<NodeDrainRequest>,
+    ) -> Result<Response<NodeDrainStatus>, Status> {
+        debug!("{:?}", request);
+        let (tx, rx) = Manager::create_oneshot_channel();
+
+        match self
+            .sender
+            .send(Event::NodeDrain(request.into_inner(), tx))
+            .await
+        {
+            Ok(_) => {
+                return Manager::wait_reply(rx).await;
+            }
+            Err(_) => {
+                return Err(Status::internal("could not send event to manager"));
+            }
+        }
+    }
+
+    async fn drain_status(
+        &self,
+        request: Request<NodeDrainStatusRequest>,
+    ) -> Result<Response<NodeDrainStatus>, Status> {
+        debug!("{:?}", request);
+        let (tx, rx) = Manager::create_oneshot_channel();
+
+        match self
+            .sender
+            .send(Event::NodeDrainStatus(request.into_inner().id, tx))
+            .await
+        {
+            Ok(_) => {
+                return Manager::wait_reply(rx).await;
+            }
+            Err(_) => {
+                return Err(Status::internal("could not send event to manager"));
+            }
+        }
+    }
+
+    async fn list(&self, request: Request<()>) -> Result<Response<NodeListResponse>, Status> {
+        debug!("{:?}", request);
+        let (tx, rx) = Manager::create_oneshot_channel();
+
+        match self.sender.send(Event::NodeList(tx)).await {
+            Ok(_) => {
+                return Manager::wait_reply(rx).await;
+            }
+            Err(_) => {
+                return Err(Status::internal("could not send event to manager"));
+            }
+        }
+    }
+
+    async fn get(&self, request: Request<NodeIdentifier>) -> Result<Response<NodeInfo>, Status> {
+        debug!("{:?}", request);
+        let (tx, rx) = Manager::create_oneshot_channel();
+
+        match self
+            .sender
+            .send(Event::NodeGet(request.into_inner().id, tx))
+            .await
+        {
+            Ok(_) => {
+                return Manager::wait_reply(rx).await;
             }
             Err(_) => {
                 return Err(Status::internal("could not send event to manager"));
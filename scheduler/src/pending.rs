@@ -0,0 +1,189 @@
+use std::collections::VecDeque;
+use std::time::Instant;
+
+use proto::scheduler::Instance;
+
+use crate::StatusSender;
+
+/// `PendingInstance` is an instance no node can run yet, waiting for capacity to free up.
+///
+/// Properties:
+///
+/// * `instance`: The instance to place.
+/// * `watcher`: The stream the statuses of the instance are sent to.
+/// * `reason`: Why the instance couldn't be placed on the last attempt.
+/// * `deadline`: When the instance stops waiting and is failed, if ever.
+#[derive(Debug, Clone)]
+pub struct PendingInstance {
+    pub instance: Instance,
+    pub watcher: StatusSender,
+    pub reason: String,
+    pub deadline: Option<Instant>,
+}
+
+/// `PendingQueue` holds the pending instances, in the order they were first created, so that
+/// the oldest ones are retried first among the ones with the same priority.
+#[derive(Debug, Default)]
+pub struct PendingQueue {
+    instances: VecDeque<PendingInstance>,
+}
+
+impl PendingQueue {
+    /// `new` creates an empty queue
+    pub fn new() -> Self {
+        PendingQueue::default()
+    }
+
+    /// It returns the number of pending instances
+    pub fn len(&self) -> usize {
+        self.instances.len()
+    }
+
+    /// It returns true if no instance is pending
+    pub fn is_empty(&self) -> bool {
+        self.instances.is_empty()
+    }
+
+    /// It adds an instance at the end of the queue, or updates it if it is already pending
+    pub fn push(&mut self, pending: PendingInstance) {
+        match self
+            .instances
+            .iter_mut()
+            .find(|queued| queued.instance.id == pending.instance.id)
+        {
+            Some(queued) => *queued = pending,
+            None => self.instances.push_back(pending),
+        }
+    }
+
+    /// It returns true if an instance is pending
+    pub fn contains(&self, id: &str) -> bool {
+        self.instances
+            .iter()
+            .any(|pending| pending.instance.id == id)
+    }
+
+    /// It sends the statuses of a pending instance to another stream, and returns true if the
+    /// instance is pending
+    pub fn watch(&mut self, id: &str, watcher: StatusSender) -> bool {
+        match self
+            .instances
+            .iter_mut()
+            .find(|pending| pending.instance.id == id)
+        {
+            Some(pending) => {
+                pending.watcher = watcher;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// It removes an instance from the queue, e.g. when it is destroyed
+    pub fn remove(&mut self, id: &str) -> Option<PendingInstance> {
+        let index = self
+            .instances
+            .iter()
+            .position(|pending| pending.instance.id == id)?;
+        self.instances.remove(index)
+    }
+
+    /// It returns the pending instances in the order they are retried, highest priority first
+    /// then oldest first
+    pub fn list(&self) -> Vec<&PendingInstance> {
+        let mut instances: Vec<_> = self.instances.iter().collect();
+        instances.sort_by_key(|pending| std::cmp::Reverse(pending.instance.priority));
+        instances
+    }
+
+    /// It takes all the pending instances, highest priority first then oldest first, to try to
+    /// place them again. The ones which still can't be placed are pushed back in the same order.
+    pub fn take(&mut self) -> Vec<PendingInstance> {
+        let mut instances: Vec<_> = self.instances.drain(..).collect();
+        instances.sort_by_key(|pending| std::cmp::Reverse(pending.instance.priority));
+        instances
+    }
+
+    /// It removes the instances whose deadline is over and returns them
+    pub fn expire(&mut self, now: Instant) -> Vec<PendingInstance> {
+        let (expired, pending): (VecDeque<_>, VecDeque<_>) = self
+            .instances
+            .drain(..)
+            .partition(|pending| pending.deadline.is_some_and(|deadline| deadline <= now));
+        self.instances = pending;
+        expired.into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use tokio::sync::mpsc;
+
+    fn pending(id: &str, deadline: Option<Instant>) -> PendingInstance {
+        prioritized(id, 0, deadline)
+    }
+
+    fn prioritized(id: &str, priority: i32, deadline: Option<Instant>) -> PendingInstance {
+        PendingInstance {
+            instance: Instance {
+                id: id.to_string(),
+                priority,
+                ..Default::default()
+            },
+            watcher: mpsc::channel(1).0,
+            reason: "insufficient memory on all 2 nodes".to_string(),
+            deadline,
+        }
+    }
+
+    fn ids(instances: &[PendingInstance]) -> Vec<&str> {
+        instances
+            .iter()
+            .map(|pending| pending.instance.id.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn test_retry_keeps_order() {
+        let mut queue = PendingQueue::new();
+        queue.push(pending("a", None));
+        queue.push(pending("b", None));
+        queue.push(pending("c", None));
+        queue.push(pending("a", None));
+        assert_eq!(3, queue.len());
+
+        assert_eq!("b", queue.remove("b").unwrap().instance.id);
+        let taken = queue.take();
+        assert_eq!(vec!["a", "c"], ids(&taken));
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn test_retry_by_priority() {
+        let mut queue = PendingQueue::new();
+        queue.push(prioritized("batch-1", 0, None));
+        queue.push(prioritized("web", 100, None));
+        queue.push(prioritized("batch-2", 0, None));
+        queue.push(prioritized("critical", 1000, None));
+
+        assert_eq!(
+            vec!["critical", "web", "batch-1", "batch-2"],
+            ids(&queue.take())
+        );
+    }
+
+    #[test]
+    fn test_expire() {
+        let now = Instant::now();
+        let mut queue = PendingQueue::new();
+        queue.push(pending("a", Some(now)));
+        queue.push(pending("b", None));
+        queue.push(pending("c", Some(now + Duration::from_secs(60))));
+
+        let expired = queue.expire(now);
+        assert_eq!(vec!["a"], ids(&expired));
+        assert_eq!(vec!["b", "c"], ids(&queue.take()));
+    }
+}
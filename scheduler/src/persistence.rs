@@ -0,0 +1,526 @@
+use std::collections::{BTreeMap, HashSet};
+use std::fmt::Debug;
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use log::{debug, error, warn};
+use prost::Message;
+use proto::scheduler::{
+    state_change::Change, StateChange, StateSnapshot, StoredInstance, StoredNode,
+};
+use serde_derive::{Deserialize, Serialize};
+
+use crate::storage::{IStorage, Storage};
+use crate::{Node, NodeIdentifier, PlacedInstance, SchedulerError};
+
+/// `StateBackendKind` is where the state of the scheduler is saved.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum StateBackendKind {
+    /// A snapshot and a write-ahead log in a directory on local disk
+    #[default]
+    File,
+    /// Nothing is saved, the state is lost when the scheduler stops
+    Memory,
+}
+
+/// `StateBackend` saves the state of the scheduler as a snapshot followed by the changes made
+/// since the snapshot was taken.
+pub trait StateBackend: Debug + Send {
+    /// It returns the last snapshot saved, if any, and the changes logged after it, oldest first
+    fn load(&mut self) -> Result<(Option<StateSnapshot>, Vec<StateChange>), SchedulerError>;
+
+    /// It logs a change made to the state, the change is saved once it returns
+    fn append(&mut self, change: &StateChange) -> Result<(), SchedulerError>;
+
+    /// It replaces the saved snapshot and drops the changes logged before it
+    fn compact(&mut self, snapshot: &StateSnapshot) -> Result<(), SchedulerError>;
+}
+
+/// `MemoryBackend` keeps the state in memory, it doesn't survive a restart.
+///
+/// Properties:
+///
+/// * `snapshot`: The last snapshot taken.
+/// * `changes`: The changes made since the snapshot was taken.
+#[derive(Debug, Default)]
+pub struct MemoryBackend {
+    snapshot: Option<StateSnapshot>,
+    changes: Vec<StateChange>,
+}
+
+impl MemoryBackend {
+    /// `new` creates an empty backend
+    pub fn new() -> Self {
+        MemoryBackend::default()
+    }
+}
+
+impl StateBackend for MemoryBackend {
+    fn load(&mut self) -> Result<(Option<StateSnapshot>, Vec<StateChange>), SchedulerError> {
+        Ok((self.snapshot.clone(), self.changes.clone()))
+    }
+
+    fn append(&mut self, change: &StateChange) -> Result<(), SchedulerError> {
+        self.changes.push(change.clone());
+        Ok(())
+    }
+
+    fn compact(&mut self, snapshot: &StateSnapshot) -> Result<(), SchedulerError> {
+        self.snapshot = Some(snapshot.clone());
+        self.changes.clear();
+        Ok(())
+    }
+}
+
+/// `FileBackend` saves the state to a directory, as a snapshot file and a write-ahead log of
+/// the changes made since. Each change is synced to disk before it is applied.
+///
+/// Properties:
+///
+/// * `directory`: The directory of the snapshot and the log.
+/// * `wal`: The log, opened on the first change.
+#[derive(Debug)]
+pub struct FileBackend {
+    directory: PathBuf,
+    wal: Option<File>,
+}
+
+impl FileBackend {
+    const SNAPSHOT: &'static str = "snapshot.pb";
+    const WAL: &'static str = "wal.log";
+
+    /// `new` creates a backend saving the state to `directory`, created if needed
+    pub fn new(directory: impl Into<PathBuf>) -> Self {
+        FileBackend {
+            directory: directory.into(),
+            wal: None,
+        }
+    }
+
+    fn path(&self, file: &str) -> PathBuf {
+        self.directory.join(file)
+    }
+}
+
+fn persistence_error(err: impl ToString) -> SchedulerError {
+    SchedulerError::StatePersistence(err.to_string())
+}
+
+/// It reads a file, or returns None if it doesn't exist
+fn read(path: &Path) -> Result<Option<Vec<u8>>, SchedulerError> {
+    match fs::read(path) {
+        Ok(content) => Ok(Some(content)),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(persistence_error(err)),
+    }
+}
+
+impl StateBackend for FileBackend {
+    fn load(&mut self) -> Result<(Option<StateSnapshot>, Vec<StateChange>), SchedulerError> {
+        fs::create_dir_all(&self.directory).map_err(persistence_error)?;
+
+        let snapshot = match read(&self.path(Self::SNAPSHOT))? {
+            Some(content) => {
+                Some(StateSnapshot::decode(content.as_slice()).map_err(persistence_error)?)
+            }
+            None => None,
+        };
+
+        let mut changes = Vec::new();
+        let content = read(&self.path(Self::WAL))?.unwrap_or_default();
+        let mut buffer = content.as_slice();
+        while !buffer.is_empty() {
+            match StateChange::decode_length_delimited(&mut buffer) {
+                Ok(change) => changes.push(change),
+                // the scheduler stopped while writing the last change, which was never applied
+                Err(err) => {
+                    warn!(
+                        "ignoring the truncated end of {} : {}",
+                        self.path(Self::WAL).display(),
+                        err
+                    );
+                    break;
+                }
+            }
+        }
+        debug!(
+            "loaded scheduler state from {} with {} logged change(s)",
+            self.directory.display(),
+            changes.len()
+        );
+        Ok((snapshot, changes))
+    }
+
+    fn append(&mut self, change: &StateChange) -> Result<(), SchedulerError> {
+        if self.wal.is_none() {
+            let wal = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(self.path(Self::WAL))
+                .map_err(persistence_error)?;
+            self.wal = Some(wal);
+        }
+        let wal = self.wal.as_mut().unwrap();
+
+        wal.write_all(&change.encode_length_delimited_to_vec())
+            .and_then(|_| wal.sync_data())
+            .map_err(persistence_error)
+    }
+
+    /// The snapshot is written to a temporary file, then moved over the previous one so that a
+    /// crash never leaves a partially written snapshot. A crash before the log is emptied only
+    /// replays changes already in the snapshot, which is harmless as each change sets or
+    /// deletes a whole node or instance.
+    fn compact(&mut self, snapshot: &StateSnapshot) -> Result<(), SchedulerError> {
+        let path = self.path(Self::SNAPSHOT);
+        let temporary = path.with_extension("tmp");
+        fs::write(&temporary, snapshot.encode_to_vec())
+            .and_then(|_| File::open(&temporary)?.sync_all())
+            .and_then(|_| fs::rename(&temporary, &path))
+            .map_err(persistence_error)?;
+
+        self.wal = None;
+        File::create(self.path(Self::WAL))
+            .and_then(|wal| wal.sync_all())
+            .map_err(persistence_error)
+    }
+}
+
+/// `State` is the part of the state of the scheduler which survives a restart: the registered
+/// nodes and the instances placed on them.
+///
+/// Properties:
+///
+/// * `nodes`: The registered nodes by id.
+/// * `instances`: The placed instances by id.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct State {
+    pub nodes: BTreeMap<NodeIdentifier, StoredNode>,
+    pub instances: BTreeMap<String, StoredInstance>,
+}
+
+impl State {
+    /// It creates the state saved in a snapshot
+    fn restore(snapshot: StateSnapshot) -> Self {
+        State {
+            nodes: snapshot
+                .nodes
+                .into_iter()
+                .map(|node| (node.id.clone(), node))
+                .collect(),
+            instances: snapshot
+                .instances
+                .into_iter()
+                .filter_map(|stored| Some((stored.instance.as_ref()?.id.clone(), stored)))
+                .collect(),
+        }
+    }
+
+    /// It applies a change to the state
+    pub fn apply(&mut self, change: StateChange) {
+        match change.change {
+            Some(Change::NodePut(node)) => {
+                self.nodes.insert(node.id.clone(), node);
+            }
+            Some(Change::NodeDelete(id)) => {
+                self.nodes.remove(&id);
+            }
+            Some(Change::InstancePut(stored)) => {
+                if let Some(instance) = &stored.instance {
+                    self.instances.insert(instance.id.clone(), stored);
+                }
+            }
+            Some(Change::InstanceDelete(id)) => {
+                self.instances.remove(&id);
+            }
+            None => {}
+        }
+    }
+
+    /// It returns a snapshot of the state
+    pub fn snapshot(&self) -> StateSnapshot {
+        StateSnapshot {
+            nodes: self.nodes.values().cloned().collect(),
+            instances: self.instances.values().cloned().collect(),
+        }
+    }
+}
+
+/// `StateLog` keeps the state of the scheduler in sync with its backend. Each change is logged
+/// by the backend, and a new snapshot is taken every `snapshot_interval` changes so that the
+/// log doesn't grow forever.
+///
+/// Properties:
+///
+/// * `state`: The state as saved by the backend.
+/// * `backend`: Where the state is saved.
+/// * `logged`: The number of changes logged since the last snapshot.
+/// * `snapshot_interval`: The number of changes after which a snapshot is taken.
+#[derive(Debug)]
+pub struct StateLog {
+    state: State,
+    backend: Box<dyn StateBackend>,
+    logged: u64,
+    snapshot_interval: u64,
+}
+
+impl StateLog {
+    /// `open` restores the state saved by a backend, replaying the changes logged after the
+    /// last snapshot, then takes a new snapshot so that the log starts empty.
+    ///
+    /// Arguments:
+    ///
+    /// * `backend`: Where the state is saved.
+    /// * `snapshot_interval`: The number of changes after which a snapshot is taken.
+    ///
+    /// Returns:
+    ///
+    /// The log, or an error if the saved state can't be read.
+    pub fn open(
+        mut backend: Box<dyn StateBackend>,
+        snapshot_interval: u64,
+    ) -> Result<Self, SchedulerError> {
+        let (snapshot, changes) = backend.load()?;
+        let mut state = snapshot.map(State::restore).unwrap_or_default();
+        let replayed = !changes.is_empty();
+        for change in changes {
+            state.apply(change);
+        }
+        if replayed {
+            backend.compact(&state.snapshot())?;
+        }
+
+        Ok(StateLog {
+            state,
+            backend,
+            logged: 0,
+            snapshot_interval: snapshot_interval.max(1),
+        })
+    }
+
+    /// It returns the state as saved by the backend
+    pub fn state(&self) -> &State {
+        &self.state
+    }
+
+    /// It saves a change to the state. A change which can't be saved is logged and only kept
+    /// in memory, it is saved with the next snapshot.
+    pub fn record(&mut self, change: StateChange) {
+        if let Err(err) = self.backend.append(&change) {
+            error!("failed to save scheduler state change : {}", err);
+        }
+        self.state.apply(change);
+
+        self.logged += 1;
+        if self.logged >= self.snapshot_interval {
+            match self.backend.compact(&self.state.snapshot()) {
+                Ok(()) => self.logged = 0,
+                Err(err) => error!("failed to save scheduler state snapshot : {}", err),
+            }
+        }
+    }
+}
+
+/// It returns the change saving a node
+pub fn node_put(node: &Node) -> StateChange {
+    StateChange {
+        change: Some(Change::NodePut(StoredNode {
+            id: node.id.clone(),
+            address: node.address.clone(),
+            subnet: node.subnet.to_string(),
+            capacity: node.capacity(),
+            labels: node.labels.clone(),
+            taints: node.taints.clone(),
+            cordoned: node.cordoned,
+        })),
+    }
+}
+
+/// It returns the change deleting a node
+pub fn node_delete(id: &str) -> StateChange {
+    StateChange {
+        change: Some(Change::NodeDelete(id.to_string())),
+    }
+}
+
+/// It returns the change saving the placement of an instance
+pub fn instance_put(placed: &PlacedInstance) -> StateChange {
+    StateChange {
+        change: Some(Change::InstancePut(StoredInstance {
+            node_id: placed.node_id.clone(),
+            instance: Some(placed.instance.clone()),
+        })),
+    }
+}
+
+/// It returns the change deleting the placement of an instance
+pub fn instance_delete(id: &str) -> StateChange {
+    StateChange {
+        change: Some(Change::InstanceDelete(id.to_string())),
+    }
+}
+
+/// `Reconciliation` is how the instances restored on a node differ from the ones its agent
+/// reports running.
+///
+/// Properties:
+///
+/// * `gone`: The instances placed on the node which no longer run there.
+/// * `unknown`: The instances running on the node which aren't placed there.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Reconciliation {
+    pub gone: Vec<String>,
+    pub unknown: Vec<String>,
+}
+
+/// It compares the instances placed on a node with the ones its agent reports running.
+///
+/// Arguments:
+///
+/// * `node_id`: The id of the node.
+/// * `running`: The ids of the instances the agent runs.
+/// * `instances`: The placed instances.
+///
+/// Returns:
+///
+/// The instances to forget and the ones to stop, sorted by id.
+pub fn reconcile(
+    node_id: &str,
+    running: &[String],
+    instances: &Storage<PlacedInstance>,
+) -> Reconciliation {
+    let running: HashSet<&String> = running.iter().collect();
+
+    let mut gone: Vec<String> = PlacedInstance::on_node(node_id, instances)
+        .into_iter()
+        .filter(|placed| !running.contains(&placed.instance.id))
+        .map(|placed| placed.instance.id.clone())
+        .collect();
+    let mut unknown: Vec<String> = running
+        .into_iter()
+        .filter(|id| {
+            instances
+                .get(id)
+                .is_none_or(|placed| placed.node_id != node_id)
+        })
+        .cloned()
+        .collect();
+    gone.sort();
+    unknown.sort();
+
+    Reconciliation { gone, unknown }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{node, place_on};
+    use proto::scheduler::Instance;
+
+    fn cordoned(id: &str) -> Node {
+        Node {
+            cordoned: true,
+            ..node(id)
+        }
+    }
+
+    fn placed(id: &str, node_id: &str) -> PlacedInstance {
+        place_on(
+            node_id,
+            Instance {
+                id: id.to_string(),
+                ip: "10.0.1.2".to_string(),
+                ..Default::default()
+            },
+        )
+    }
+
+    fn changes(log: &mut StateLog) {
+        log.record(node_put(&node("node-a")));
+        log.record(node_put(&cordoned("node-b")));
+        log.record(instance_put(&placed("web-1", "node-a")));
+        log.record(instance_put(&placed("web-2", "node-b")));
+        log.record(instance_delete("web-1"));
+        log.record(node_delete("node-a"));
+    }
+
+    fn expected() -> State {
+        let mut state = State::default();
+        state.apply(node_put(&cordoned("node-b")));
+        state.apply(instance_put(&placed("web-2", "node-b")));
+        state
+    }
+
+    #[test]
+    fn test_state_survives_restart() {
+        let directory = tempfile::tempdir().unwrap();
+        let backend = || Box::new(FileBackend::new(directory.path().join("state")));
+
+        let mut log = StateLog::open(backend(), 1000).unwrap();
+        assert_eq!(&State::default(), log.state());
+        changes(&mut log);
+        assert_eq!(&expected(), log.state());
+
+        // the changes are replayed from the log, then compacted into a snapshot
+        let log = StateLog::open(backend(), 1000).unwrap();
+        assert_eq!(&expected(), log.state());
+        let log = StateLog::open(backend(), 1000).unwrap();
+        assert_eq!(&expected(), log.state());
+    }
+
+    #[test]
+    fn test_snapshot_interval() {
+        let directory = tempfile::tempdir().unwrap();
+        let backend = || Box::new(FileBackend::new(directory.path()));
+
+        let mut log = StateLog::open(backend(), 4).unwrap();
+        changes(&mut log);
+        let wal = fs::read(directory.path().join(FileBackend::WAL)).unwrap();
+        let mut buffer = wal.as_slice();
+        // the first 4 changes are in the snapshot
+        assert_eq!(
+            instance_delete("web-1"),
+            StateChange::decode_length_delimited(&mut buffer).unwrap()
+        );
+
+        let log = StateLog::open(backend(), 4).unwrap();
+        assert_eq!(&expected(), log.state());
+    }
+
+    #[test]
+    fn test_truncated_change_is_ignored() {
+        let directory = tempfile::tempdir().unwrap();
+        let backend = || Box::new(FileBackend::new(directory.path()));
+
+        let mut log = StateLog::open(backend(), 1000).unwrap();
+        changes(&mut log);
+        let mut wal = OpenOptions::new()
+            .append(true)
+            .open(directory.path().join(FileBackend::WAL))
+            .unwrap();
+        let change = node_put(&node("node-c")).encode_length_delimited_to_vec();
+        wal.write_all(&change[..change.len() / 2]).unwrap();
+
+        let log = StateLog::open(backend(), 1000).unwrap();
+        assert_eq!(&expected(), log.state());
+    }
+
+    #[test]
+    fn test_reconcile() {
+        let mut instances = Storage::new();
+        instances.update("web-1", placed("web-1", "node-a"));
+        instances.update("web-2", placed("web-2", "node-a"));
+        instances.update("web-3", placed("web-3", "node-b"));
+
+        let running = vec!["web-2".to_string(), "web-3".to_string(), "job".to_string()];
+        assert_eq!(
+            Reconciliation {
+                gone: vec!["web-1".to_string()],
+                unknown: vec!["job".to_string(), "web-3".to_string()],
+            },
+            reconcile("node-a", &running, &instances)
+        );
+    }
+}
@@ -0,0 +1,787 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+
+use proto::scheduler::{Instance, ResourceSummary, Status};
+use serde_derive::{Deserialize, Serialize};
+
+use crate::config::Config;
+use crate::plugins::{CapacityPlugin, CordonPlugin, LabelsPlugin, SpreadPlugin};
+use crate::storage::{IStorage, Storage};
+use crate::{Node, NodeIdentifier, PlacedInstance, SchedulerError};
+
+/// `PlacementStrategy` is how the capacity plugin scores the nodes which can run an instance.
+///
+/// Variants:
+///
+/// * `BinPack`: The most used nodes are filled first, keeping the other nodes free.
+/// * `Spread`: The least used nodes are filled first, spreading the instances over the nodes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum PlacementStrategy {
+    BinPack,
+    #[default]
+    Spread,
+}
+
+/// `Overcommit` is the share of the capacity of each node the instances placed on it can
+/// request, by resource. A ratio above 1 lets the requests exceed the capacity, counting on the
+/// instances not all using what they request at the same time.
+///
+/// Properties:
+///
+/// * `cpu`: The ratio of the cpu of the nodes.
+/// * `memory`: The ratio of the memory of the nodes.
+/// * `disk`: The ratio of the disk of the nodes.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Overcommit {
+    pub cpu: f64,
+    pub memory: f64,
+    pub disk: f64,
+}
+
+impl Default for Overcommit {
+    fn default() -> Self {
+        Overcommit {
+            cpu: 1.0,
+            memory: 1.0,
+            disk: 1.0,
+        }
+    }
+}
+
+impl Overcommit {
+    /// It checks that every ratio is a positive number
+    pub fn validate(&self) -> Result<(), SchedulerError> {
+        for (resource, ratio) in [
+            ("cpu", self.cpu),
+            ("memory", self.memory),
+            ("disk", self.disk),
+        ] {
+            if !ratio.is_finite() || ratio <= 0.0 {
+                return Err(SchedulerError::InvalidOvercommit(format!(
+                    "{} ratio {} is not a positive number",
+                    resource, ratio
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// It returns the resources of a node the instances can request
+    pub fn allocatable(&self, capacity: &ResourceSummary) -> ResourceSummary {
+        let scale = |amount: u64, ratio: f64| (amount as f64 * ratio) as u64;
+        ResourceSummary {
+            cpu: scale(capacity.cpu, self.cpu),
+            memory: scale(capacity.memory, self.memory),
+            disk: scale(capacity.disk, self.disk),
+        }
+    }
+}
+
+/// It returns the resources an instance reserves on its node, its requests, or its limits for
+/// the instances which don't set requests
+pub fn requested(instance: &Instance) -> ResourceSummary {
+    instance
+        .resource
+        .as_ref()
+        .and_then(|resource| resource.request.clone().or_else(|| resource.limit.clone()))
+        .unwrap_or_default()
+}
+
+/// It returns the resources of a node reserved by the instances placed on it
+pub fn allocated(node_id: &str, instances: &Storage<PlacedInstance>) -> ResourceSummary {
+    PlacedInstance::on_node(node_id, instances)
+        .into_iter()
+        .fold(ResourceSummary::default(), |total, placed| {
+            let requested = requested(&placed.instance);
+            ResourceSummary {
+                cpu: total.cpu.saturating_add(requested.cpu),
+                memory: total.memory.saturating_add(requested.memory),
+                disk: total.disk.saturating_add(requested.disk),
+            }
+        })
+}
+
+/// `FilterPlugin` is a step of the pipeline which rules out the nodes which can't run an
+/// instance.
+pub trait FilterPlugin: fmt::Debug + Send + Sync {
+    /// It returns the name of the plugin, recorded along with the reasons it gives
+    fn name(&self) -> &'static str;
+
+    /// It checks whether a running node can run an instance.
+    ///
+    /// Arguments:
+    ///
+    /// * `instance`: The instance to place.
+    /// * `node`: The node to check.
+    /// * `instances`: The instances already placed.
+    ///
+    /// Returns:
+    ///
+    /// Why the node can't run the instance, e.g. `insufficient memory`, empty if it can.
+    fn filter(
+        &self,
+        instance: &Instance,
+        node: &Node,
+        instances: &Storage<PlacedInstance>,
+    ) -> Vec<String>;
+}
+
+/// `ScorePlugin` is a step of the pipeline which ranks the nodes left by the filters.
+pub trait ScorePlugin: fmt::Debug + Send + Sync {
+    /// It returns the name of the plugin, its weight is set under this name in the config
+    fn name(&self) -> &'static str;
+
+    /// It scores a node which can run an instance.
+    ///
+    /// Arguments:
+    ///
+    /// * `instance`: The instance to place.
+    /// * `node`: The node to score.
+    /// * `instances`: The instances already placed.
+    ///
+    /// Returns:
+    ///
+    /// The score of the node between 0 and 1, higher is better.
+    fn score(&self, instance: &Instance, node: &Node, instances: &Storage<PlacedInstance>) -> f64;
+}
+
+/// `Filtered` is a reason a filter plugin gave to rule out a node.
+///
+/// Properties:
+///
+/// * `plugin`: The name of the filter plugin.
+/// * `reason`: Why the node can't run the instance.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Filtered {
+    pub plugin: &'static str,
+    pub reason: String,
+}
+
+/// `Scored` is the score a score plugin gave to a node.
+///
+/// Properties:
+///
+/// * `plugin`: The name of the score plugin.
+/// * `score`: The score of the node, between 0 and 1.
+/// * `weight`: The weight of the plugin.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Scored {
+    pub plugin: &'static str,
+    pub score: f64,
+    pub weight: u32,
+}
+
+/// `NodeEvaluation` is how the pipeline judged a node for an instance.
+///
+/// Properties:
+///
+/// * `node_id`: The id of the node.
+/// * `status`: The status of the node, only running nodes are evaluated.
+/// * `filtered`: Why the node was ruled out, empty if it can run the instance.
+/// * `scores`: The scores of the node, if it can run the instance.
+/// * `total`: The sum of the scores, by the weights of their plugins.
+#[derive(Debug, Clone)]
+pub struct NodeEvaluation {
+    pub node_id: NodeIdentifier,
+    pub status: Status,
+    pub filtered: Vec<Filtered>,
+    pub scores: Vec<Scored>,
+    pub total: f64,
+}
+
+impl NodeEvaluation {
+    /// It returns true if the node can run the instance
+    pub fn feasible(&self) -> bool {
+        self.status == Status::Running && self.filtered.is_empty()
+    }
+}
+
+/// `Decision` is the outcome of the placement of an instance, kept for debugging.
+///
+/// Properties:
+///
+/// * `instance_id`: The id of the instance.
+/// * `node_id`: The id of the chosen node, None if no node can run the instance.
+/// * `nodes`: How each node was judged, the best ones first.
+#[derive(Debug, Clone)]
+pub struct Decision {
+    pub instance_id: String,
+    pub node_id: Option<NodeIdentifier>,
+    pub nodes: Vec<NodeEvaluation>,
+}
+
+impl Decision {
+    /// It explains why no node can run the instance, e.g. `insufficient memory on all 3 nodes`
+    pub fn reason(&self) -> String {
+        let total = self.nodes.len();
+        if total == 0 {
+            return "no node is registered".to_string();
+        }
+
+        let mut not_running = 0;
+        // sorted so that the reason doesn't depend on the order of the nodes
+        let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+        for node in &self.nodes {
+            if node.status != Status::Running {
+                not_running += 1;
+                continue;
+            }
+            for filtered in &node.filtered {
+                *counts.entry(filtered.reason.as_str()).or_default() += 1;
+            }
+        }
+
+        let share = |count: usize| match (count, total) {
+            (1, 1) => "the only node".to_string(),
+            (count, total) if count == total => format!("all {} nodes", total),
+            (count, total) => format!("{} of {} nodes", count, total),
+        };
+        let mut reasons: Vec<String> = counts
+            .iter()
+            .map(|(reason, count)| format!("{} on {}", reason, share(*count)))
+            .collect();
+        if not_running > 0 {
+            reasons.push(format!("{} not running", share(not_running)));
+        }
+
+        match reasons.is_empty() {
+            true => "no node can run the instance".to_string(),
+            false => reasons.join(", "),
+        }
+    }
+}
+
+// e.g. `instance a on node-1: node-1 scored 1.40 (capacity 0.40x1, labels 1.00x1), node-2
+// filtered (capacity: insufficient cpu)`
+impl fmt::Display for Decision {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.node_id {
+            Some(node_id) => write!(f, "instance {} on {}:", self.instance_id, node_id)?,
+            None => write!(f, "instance {} unschedulable:", self.instance_id)?,
+        }
+
+        let nodes: Vec<String> = self
+            .nodes
+            .iter()
+            .map(|node| {
+                if node.status != Status::Running {
+                    return format!("{} not running", node.node_id);
+                }
+                if !node.filtered.is_empty() {
+                    let reasons: Vec<String> = node
+                        .filtered
+                        .iter()
+                        .map(|filtered| format!("{}: {}", filtered.plugin, filtered.reason))
+                        .collect();
+                    return format!("{} filtered ({})", node.node_id, reasons.join(", "));
+                }
+                let scores: Vec<String> = node
+                    .scores
+                    .iter()
+                    .map(|scored| {
+                        format!("{} {:.2}x{}", scored.plugin, scored.score, scored.weight)
+                    })
+                    .collect();
+                format!(
+                    "{} scored {:.2} ({})",
+                    node.node_id,
+                    node.total,
+                    scores.join(", ")
+                )
+            })
+            .collect();
+        write!(f, " {}", nodes.join(", "))
+    }
+}
+
+/// `Pipeline` chooses the node of an instance: the filter plugins rule out the nodes which
+/// can't run it, then the score plugins rank the remaining nodes, the best total wins and ties
+/// are broken by node id.
+///
+/// Properties:
+///
+/// * `filters`: The filter plugins, all run on each running node.
+/// * `scorers`: The score plugins along with their weights.
+#[derive(Debug, Default)]
+pub struct Pipeline {
+    filters: Vec<Box<dyn FilterPlugin>>,
+    scorers: Vec<(Box<dyn ScorePlugin>, u32)>,
+}
+
+impl Pipeline {
+    /// `new` creates a pipeline without plugins, which places instances on any running node
+    pub fn new() -> Self {
+        Pipeline::default()
+    }
+
+    /// It creates the pipeline of the built-in plugins: `cordon`, which only filters, then
+    /// `capacity`, `labels` and `spread`, scored with the weights of the config. A plugin without
+    /// weight has a weight of 1.
+    ///
+    /// Returns:
+    ///
+    /// The pipeline, or an error if the config weighs a plugin which doesn't exist
+    pub fn from_config(config: &Config) -> Result<Self, SchedulerError> {
+        config.overcommit.validate()?;
+        let capacity = CapacityPlugin::new(config.placement_strategy, config.overcommit);
+        let builtins = [CapacityPlugin::NAME, LabelsPlugin::NAME, SpreadPlugin::NAME];
+        if let Some(unknown) = config
+            .plugin_weights
+            .keys()
+            .find(|name| !builtins.contains(&name.as_str()))
+        {
+            return Err(SchedulerError::UnknownPlugin(unknown.clone()));
+        }
+        let weight = |name: &str| config.plugin_weights.get(name).copied().unwrap_or(1);
+
+        Ok(Pipeline::new()
+            .with_filter(CordonPlugin)
+            .with_filter(capacity)
+            .with_filter(LabelsPlugin)
+            .with_filter(SpreadPlugin)
+            .with_scorer(capacity, weight(CapacityPlugin::NAME))
+            .with_scorer(LabelsPlugin, weight(LabelsPlugin::NAME))
+            .with_scorer(SpreadPlugin, weight(SpreadPlugin::NAME)))
+    }
+
+    /// It adds a filter plugin, run after the ones already added
+    pub fn with_filter(mut self, plugin: impl FilterPlugin + 'static) -> Self {
+        self.filters.push(Box::new(plugin));
+        self
+    }
+
+    /// It adds a score plugin, a weight of 0 disables it
+    pub fn with_scorer(mut self, plugin: impl ScorePlugin + 'static, weight: u32) -> Self {
+        if weight > 0 {
+            self.scorers.push((Box::new(plugin), weight));
+        }
+        self
+    }
+
+    /// It chooses the node an instance is placed on.
+    ///
+    /// Arguments:
+    ///
+    /// * `instance`: The instance to place.
+    /// * `nodes`: The nodes of the cluster.
+    /// * `instances`: The instances already placed.
+    ///
+    /// Returns:
+    ///
+    /// The decision, with the id of the chosen node if a node can run the instance.
+    pub fn place(
+        &self,
+        instance: &Instance,
+        nodes: &Storage<Node>,
+        instances: &Storage<PlacedInstance>,
+    ) -> Decision {
+        let mut evaluations: Vec<NodeEvaluation> = nodes
+            .get_all()
+            .values()
+            .map(|node| self.evaluate(instance, node, instances))
+            .collect();
+
+        evaluations.sort_by(|node, other| {
+            other
+                .feasible()
+                .cmp(&node.feasible())
+                .then_with(|| other.total.total_cmp(&node.total))
+                .then_with(|| node.node_id.cmp(&other.node_id))
+        });
+
+        Decision {
+            instance_id: instance.id.clone(),
+            node_id: evaluations
+                .first()
+                .filter(|node| node.feasible())
+                .map(|node| node.node_id.clone()),
+            nodes: evaluations,
+        }
+    }
+
+    /// It runs the filters on a node, then the scorers if it can run the instance
+    fn evaluate(
+        &self,
+        instance: &Instance,
+        node: &Node,
+        instances: &Storage<PlacedInstance>,
+    ) -> NodeEvaluation {
+        let mut evaluation = NodeEvaluation {
+            node_id: node.id.clone(),
+            status: node.status,
+            filtered: Vec::new(),
+            scores: Vec::new(),
+            total: 0.0,
+        };
+        if node.status != Status::Running {
+            return evaluation;
+        }
+
+        evaluation.filtered = self
+            .filters
+            .iter()
+            .flat_map(|plugin| {
+                plugin
+                    .filter(instance, node, instances)
+                    .into_iter()
+                    .map(|reason| Filtered {
+                        plugin: plugin.name(),
+                        reason,
+                    })
+            })
+            .collect();
+        if !evaluation.filtered.is_empty() {
+            return evaluation;
+        }
+
+        evaluation.scores = self
+            .scorers
+            .iter()
+            .map(|(plugin, weight)| Scored {
+                plugin: plugin.name(),
+                score: plugin.score(instance, node, instances).clamp(0.0, 1.0),
+                weight: *weight,
+            })
+            .collect();
+        evaluation.total = evaluation
+            .scores
+            .iter()
+            .map(|scored| scored.score * f64::from(scored.weight))
+            .sum();
+        evaluation
+    }
+}
+
+/// It returns the default weights of the score plugins, written to a new config
+pub fn default_plugin_weights() -> HashMap<String, u32> {
+    [CapacityPlugin::NAME, LabelsPlugin::NAME, SpreadPlugin::NAME]
+        .iter()
+        .map(|name| (name.to_string(), 1))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{node, place_on, requesting, summary};
+    use proto::scheduler::{
+        node_selector_requirement::Operator, Affinity, NodeSelectorRequirement, NodeSelectorTerm,
+        PreferredTerm,
+    };
+
+    fn cluster() -> (Storage<Node>, Storage<PlacedInstance>) {
+        let mut nodes = Storage::new();
+        nodes.update("a", node("a"));
+        nodes.update("b", node("b"));
+
+        let mut instances = Storage::new();
+        instances.update(
+            "placed",
+            place_on("a", requesting("placed", summary(500, 500, 50))),
+        );
+        (nodes, instances)
+    }
+
+    fn pipeline(strategy: PlacementStrategy) -> Pipeline {
+        Pipeline::from_config(&Config {
+            placement_strategy: strategy,
+            ..Default::default()
+        })
+        .unwrap()
+    }
+
+    fn place(
+        instance: &Instance,
+        nodes: &Storage<Node>,
+        instances: &Storage<PlacedInstance>,
+        strategy: PlacementStrategy,
+    ) -> Option<NodeIdentifier> {
+        pipeline(strategy).place(instance, nodes, instances).node_id
+    }
+
+    fn unschedulable_reason(
+        instance: &Instance,
+        nodes: &Storage<Node>,
+        instances: &Storage<PlacedInstance>,
+    ) -> String {
+        pipeline(PlacementStrategy::Spread)
+            .place(instance, nodes, instances)
+            .reason()
+    }
+
+    #[test]
+    fn test_place_filters_full_nodes() {
+        let (nodes, instances) = cluster();
+
+        let large = requesting("large", summary(800, 100, 10));
+        for strategy in [PlacementStrategy::BinPack, PlacementStrategy::Spread] {
+            assert_eq!(
+                Some("b".to_string()),
+                place(&large, &nodes, &instances, strategy)
+            );
+        }
+
+        let too_large = requesting("too-large", summary(2000, 100, 10));
+        assert_eq!(
+            None,
+            place(&too_large, &nodes, &instances, PlacementStrategy::Spread)
+        );
+    }
+
+    #[test]
+    fn test_huge_requests_dont_overflow() {
+        let (nodes, mut instances) = cluster();
+        instances.update(
+            "huge",
+            place_on("a", requesting("huge", summary(u64::MAX, 100, 10))),
+        );
+        assert_eq!(u64::MAX, allocated("a", &instances).cpu);
+
+        let huge = requesting("huge-2", summary(u64::MAX, 100, 10));
+        assert_eq!(
+            None,
+            place(&huge, &nodes, &instances, PlacementStrategy::BinPack)
+        );
+        let small = requesting("small", summary(100, 100, 10));
+        assert_eq!(
+            Some("b".to_string()),
+            place(&small, &nodes, &instances, PlacementStrategy::BinPack)
+        );
+    }
+
+    #[test]
+    fn test_place_by_requests() {
+        let (nodes, instances) = cluster();
+
+        // the limits don't fit on any node, the requests fit on both
+        let mut burstable = requesting("burstable", summary(1200, 100, 10));
+        burstable.resource.as_mut().unwrap().request = Some(summary(400, 100, 10));
+        assert_eq!(summary(400, 100, 10), requested(&burstable));
+        assert_eq!(
+            Some("a".to_string()),
+            place(&burstable, &nodes, &instances, PlacementStrategy::BinPack)
+        );
+    }
+
+    #[test]
+    fn test_place_with_overcommit() {
+        let (nodes, instances) = cluster();
+        let large = requesting("large", summary(1500, 100, 10));
+        assert_eq!(
+            "insufficient cpu on all 2 nodes",
+            unschedulable_reason(&large, &nodes, &instances)
+        );
+
+        let overcommitted = Pipeline::from_config(&Config {
+            overcommit: Overcommit {
+                cpu: 2.0,
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .unwrap();
+        // a has 2000 - 500 cpu left, b has 2000
+        assert_eq!(
+            Some("b".to_string()),
+            overcommitted.place(&large, &nodes, &instances).node_id
+        );
+
+        let invalid = Config {
+            overcommit: Overcommit {
+                memory: 0.0,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        assert!(matches!(
+            Pipeline::from_config(&invalid),
+            Err(SchedulerError::InvalidOvercommit(_))
+        ));
+    }
+
+    #[test]
+    fn test_place_skips_nodes_not_running() {
+        let (mut nodes, instances) = cluster();
+        nodes.get_mut("b").unwrap().status = Status::Failed;
+
+        let small = requesting("small", summary(100, 100, 10));
+        assert_eq!(
+            Some("a".to_string()),
+            place(&small, &nodes, &instances, PlacementStrategy::Spread)
+        );
+    }
+
+    #[test]
+    fn test_place_skips_cordoned_nodes() {
+        let (mut nodes, instances) = cluster();
+        nodes.get_mut("b").unwrap().cordoned = true;
+
+        let small = requesting("small", summary(100, 100, 10));
+        assert_eq!(
+            Some("a".to_string()),
+            place(&small, &nodes, &instances, PlacementStrategy::Spread)
+        );
+
+        nodes.get_mut("a").unwrap().cordoned = true;
+        assert_eq!(
+            "node cordoned on all 2 nodes",
+            unschedulable_reason(&small, &nodes, &instances)
+        );
+
+        // the cordon is a filter like the others, which run on the cordoned nodes too
+        let large = requesting("large", summary(800, 100, 10));
+        let decision = pipeline(PlacementStrategy::Spread).place(&large, &nodes, &instances);
+        let node_a = decision
+            .nodes
+            .iter()
+            .find(|node| node.node_id == "a")
+            .unwrap();
+        let plugins: Vec<&str> = node_a
+            .filtered
+            .iter()
+            .map(|filtered| filtered.plugin)
+            .collect();
+        assert_eq!(vec![CordonPlugin::NAME, CapacityPlugin::NAME], plugins);
+    }
+
+    #[test]
+    fn test_unschedulable_reason() {
+        let (mut nodes, instances) = cluster();
+        let large = requesting("large", summary(100, 800, 10));
+        assert_eq!(
+            "insufficient memory on 1 of 2 nodes",
+            unschedulable_reason(&large, &nodes, &instances)
+        );
+
+        nodes.get_mut("b").unwrap().status = Status::Starting;
+        let too_large = requesting("too-large", summary(2000, 2000, 10));
+        assert_eq!(
+            "insufficient cpu on 1 of 2 nodes, insufficient memory on 1 of 2 nodes, 1 of 2 nodes not running",
+            unschedulable_reason(&too_large, &nodes, &instances)
+        );
+
+        let mut selective = requesting("selective", summary(100, 100, 10));
+        selective
+            .node_selector
+            .insert("disk".to_string(), "ssd".to_string());
+        assert_eq!(
+            "node selector not matched on 1 of 2 nodes, 1 of 2 nodes not running",
+            unschedulable_reason(&selective, &nodes, &instances)
+        );
+
+        assert_eq!(
+            "no node is registered",
+            unschedulable_reason(&large, &Storage::new(), &instances)
+        );
+    }
+
+    #[test]
+    fn test_place_honours_constraints() {
+        let (mut nodes, instances) = cluster();
+        nodes
+            .get_mut("b")
+            .unwrap()
+            .labels
+            .insert("disk".to_string(), "ssd".to_string());
+
+        // the preferred node wins over the least used one
+        let mut small = requesting("small", summary(100, 100, 10));
+        small.affinity = Some(Affinity {
+            preferred: vec![PreferredTerm {
+                weight: 100,
+                term: Some(NodeSelectorTerm {
+                    requirements: vec![NodeSelectorRequirement {
+                        key: "disk".to_string(),
+                        operator: Operator::DoesNotExist.into(),
+                        values: vec![],
+                    }],
+                }),
+            }],
+            ..Default::default()
+        });
+        assert_eq!(
+            Some("a".to_string()),
+            place(&small, &nodes, &instances, PlacementStrategy::Spread)
+        );
+
+        let mut selective = requesting("selective", summary(100, 100, 10));
+        selective
+            .node_selector
+            .insert("disk".to_string(), "ssd".to_string());
+        assert_eq!(
+            Some("b".to_string()),
+            place(&selective, &nodes, &instances, PlacementStrategy::BinPack)
+        );
+    }
+
+    #[test]
+    fn test_place_strategies() {
+        let (nodes, instances) = cluster();
+        let small = requesting("small", summary(100, 100, 10));
+
+        assert_eq!(
+            Some("a".to_string()),
+            place(&small, &nodes, &instances, PlacementStrategy::BinPack)
+        );
+        assert_eq!(
+            Some("b".to_string()),
+            place(&small, &nodes, &instances, PlacementStrategy::Spread)
+        );
+    }
+
+    #[test]
+    fn test_decision_records_filters_and_scores() {
+        let (nodes, instances) = cluster();
+        let large = requesting("large", summary(800, 100, 10));
+
+        let decision = pipeline(PlacementStrategy::Spread).place(&large, &nodes, &instances);
+        assert_eq!(Some("b".to_string()), decision.node_id);
+        let (b, a) = (&decision.nodes[0], &decision.nodes[1]);
+        assert_eq!(
+            vec![Filtered {
+                plugin: "capacity",
+                reason: "insufficient cpu".to_string(),
+            }],
+            a.filtered
+        );
+        assert!(a.scores.is_empty());
+        let plugins: Vec<&str> = b.scores.iter().map(|scored| scored.plugin).collect();
+        assert_eq!(vec!["capacity", "labels", "spread"], plugins);
+        assert!(b.total > 0.0);
+        assert_eq!(
+            format!(
+                "instance large on b: b scored {:.2} (capacity {:.2}x1, labels 1.00x1, spread 1.00x1), a filtered (capacity: insufficient cpu)",
+                b.total, b.scores[0].score
+            ),
+            decision.to_string()
+        );
+    }
+
+    #[test]
+    fn test_plugin_weights() {
+        let (nodes, instances) = cluster();
+        let small = requesting("small", summary(100, 100, 10));
+
+        // without the capacity score, the tie is broken by node id
+        let config = Config {
+            plugin_weights: HashMap::from([("capacity".to_string(), 0)]),
+            ..Default::default()
+        };
+        let decision = Pipeline::from_config(&config)
+            .unwrap()
+            .place(&small, &nodes, &instances);
+        assert_eq!(Some("a".to_string()), decision.node_id);
+        assert_eq!(2, decision.nodes[0].scores.len());
+
+        let config = Config {
+            plugin_weights: HashMap::from([("gpu".to_string(), 1)]),
+            ..Default::default()
+        };
+        assert!(matches!(
+            Pipeline::from_config(&config),
+            Err(SchedulerError::UnknownPlugin(name)) if name == "gpu"
+        ));
+    }
+}
@@ -0,0 +1,431 @@
+use proto::scheduler::{Instance, ResourceSummary, TaintEffect};
+
+use crate::constraints::{matches, siblings, tolerates};
+use crate::placement::{
+    allocated, requested, FilterPlugin, Overcommit, PlacementStrategy, ScorePlugin,
+};
+use crate::storage::Storage;
+use crate::{Node, PlacedInstance};
+
+/// `CordonPlugin` keeps the new instances off the cordoned nodes.
+#[derive(Debug, Clone, Copy)]
+pub struct CordonPlugin;
+
+impl CordonPlugin {
+    pub const NAME: &'static str = "cordon";
+}
+
+impl FilterPlugin for CordonPlugin {
+    fn name(&self) -> &'static str {
+        Self::NAME
+    }
+
+    fn filter(
+        &self,
+        _instance: &Instance,
+        node: &Node,
+        _instances: &Storage<PlacedInstance>,
+    ) -> Vec<String> {
+        match node.cordoned {
+            true => vec!["node cordoned".to_string()],
+            false => Vec::new(),
+        }
+    }
+}
+
+/// `CapacityPlugin` keeps the instances off the nodes without enough free resources, and scores
+/// the nodes by their usage following the placement strategy. The resources requested by the
+/// instances are compared to the capacity of the nodes scaled by the overcommit ratios.
+///
+/// Properties:
+///
+/// * `strategy`: Whether the most or the least used nodes are preferred.
+/// * `overcommit`: The share of the capacity of the nodes which can be requested.
+#[derive(Debug, Clone, Copy)]
+pub struct CapacityPlugin {
+    strategy: PlacementStrategy,
+    overcommit: Overcommit,
+}
+
+impl CapacityPlugin {
+    pub const NAME: &'static str = "capacity";
+
+    pub fn new(strategy: PlacementStrategy, overcommit: Overcommit) -> Self {
+        CapacityPlugin {
+            strategy,
+            overcommit,
+        }
+    }
+}
+
+/// It returns the share of the capacity of the node which would be used once the request is
+/// placed on it, averaged over cpu, memory and disk, between 0 and 1
+fn usage(
+    capacity: &ResourceSummary,
+    allocated: &ResourceSummary,
+    requested: &ResourceSummary,
+) -> f64 {
+    let share = |allocated: u64, requested: u64, capacity: u64| match capacity {
+        0 => 0.0,
+        capacity => allocated.saturating_add(requested) as f64 / capacity as f64,
+    };
+
+    (share(allocated.cpu, requested.cpu, capacity.cpu)
+        + share(allocated.memory, requested.memory, capacity.memory)
+        + share(allocated.disk, requested.disk, capacity.disk))
+        / 3.0
+}
+
+impl FilterPlugin for CapacityPlugin {
+    fn name(&self) -> &'static str {
+        Self::NAME
+    }
+
+    fn filter(
+        &self,
+        instance: &Instance,
+        node: &Node,
+        instances: &Storage<PlacedInstance>,
+    ) -> Vec<String> {
+        let capacity = match node.capacity() {
+            Some(capacity) => self.overcommit.allocatable(&capacity),
+            None => return vec!["unknown capacity".to_string()],
+        };
+        let allocated = allocated(&node.id, instances);
+        let requested = requested(instance);
+
+        [
+            (
+                "cpu",
+                allocated.cpu.saturating_add(requested.cpu) > capacity.cpu,
+            ),
+            (
+                "memory",
+                allocated.memory.saturating_add(requested.memory) > capacity.memory,
+            ),
+            (
+                "disk",
+                allocated.disk.saturating_add(requested.disk) > capacity.disk,
+            ),
+        ]
+        .iter()
+        .filter(|(_, insufficient)| *insufficient)
+        .map(|(resource, _)| format!("insufficient {}", resource))
+        .collect()
+    }
+}
+
+impl ScorePlugin for CapacityPlugin {
+    fn name(&self) -> &'static str {
+        Self::NAME
+    }
+
+    fn score(&self, instance: &Instance, node: &Node, instances: &Storage<PlacedInstance>) -> f64 {
+        let capacity = self
+            .overcommit
+            .allocatable(&node.capacity().unwrap_or_default());
+        let usage = usage(
+            &capacity,
+            &allocated(&node.id, instances),
+            &requested(instance),
+        );
+        match self.strategy {
+            PlacementStrategy::BinPack => usage,
+            PlacementStrategy::Spread => 1.0 - usage,
+        }
+    }
+}
+
+/// `LabelsPlugin` matches the node selector, affinity and tolerations of the instances against
+/// the labels and taints of the nodes.
+#[derive(Debug, Clone, Copy)]
+pub struct LabelsPlugin;
+
+impl LabelsPlugin {
+    pub const NAME: &'static str = "labels";
+}
+
+impl FilterPlugin for LabelsPlugin {
+    fn name(&self) -> &'static str {
+        Self::NAME
+    }
+
+    fn filter(
+        &self,
+        instance: &Instance,
+        node: &Node,
+        _instances: &Storage<PlacedInstance>,
+    ) -> Vec<String> {
+        let mut reasons = Vec::new();
+
+        let selected = instance
+            .node_selector
+            .iter()
+            .all(|(key, value)| node.labels.get(key) == Some(value));
+        if !selected {
+            reasons.push("node selector not matched".to_string());
+        }
+
+        if let Some(affinity) = &instance.affinity {
+            let required = &affinity.required;
+            if !required.is_empty() && !required.iter().any(|term| matches(&node.labels, term)) {
+                reasons.push("required affinity not matched".to_string());
+            }
+        }
+
+        let repelled = node
+            .taints
+            .iter()
+            .any(|taint| taint.effect() == TaintEffect::NoSchedule && !tolerates(instance, taint));
+        if repelled {
+            reasons.push("untolerated taint".to_string());
+        }
+        reasons
+    }
+}
+
+impl ScorePlugin for LabelsPlugin {
+    fn name(&self) -> &'static str {
+        Self::NAME
+    }
+
+    /// The share of the weights of the preferred affinity terms the node matches, 1 without
+    /// preferences, divided by one plus the `PREFER_NO_SCHEDULE` taints the instance doesn't
+    /// tolerate.
+    fn score(&self, instance: &Instance, node: &Node, _instances: &Storage<PlacedInstance>) -> f64 {
+        let preferred = instance
+            .affinity
+            .as_ref()
+            .map(|affinity| affinity.preferred.as_slice())
+            .unwrap_or_default();
+        let weight = |matching: bool| -> f64 {
+            preferred
+                .iter()
+                .filter(|preferred| {
+                    !matching
+                        || preferred
+                            .term
+                            .as_ref()
+                            .is_some_and(|term| matches(&node.labels, term))
+                })
+                .map(|preferred| f64::from(preferred.weight.clamp(0, 100)))
+                .sum()
+        };
+        let affinity = match weight(false) {
+            total if total > 0.0 => weight(true) / total,
+            _ => 1.0,
+        };
+
+        let avoided = node
+            .taints
+            .iter()
+            .filter(|taint| {
+                taint.effect() == TaintEffect::PreferNoSchedule && !tolerates(instance, taint)
+            })
+            .count();
+        affinity / (1 + avoided) as f64
+    }
+}
+
+/// `SpreadPlugin` spreads the instances of a workload with an anti-affinity over the nodes.
+#[derive(Debug, Clone, Copy)]
+pub struct SpreadPlugin;
+
+impl SpreadPlugin {
+    pub const NAME: &'static str = "spread";
+}
+
+impl FilterPlugin for SpreadPlugin {
+    fn name(&self) -> &'static str {
+        Self::NAME
+    }
+
+    fn filter(
+        &self,
+        instance: &Instance,
+        node: &Node,
+        instances: &Storage<PlacedInstance>,
+    ) -> Vec<String> {
+        let required = instance
+            .affinity
+            .as_ref()
+            .and_then(|affinity| affinity.anti_affinity.as_ref())
+            .is_some_and(|anti_affinity| anti_affinity.required);
+
+        match required && siblings(instance, &node.id, instances) > 0 {
+            true => vec!["anti-affinity conflict".to_string()],
+            false => Vec::new(),
+        }
+    }
+}
+
+impl ScorePlugin for SpreadPlugin {
+    fn name(&self) -> &'static str {
+        Self::NAME
+    }
+
+    /// One divided by one plus the instances of the workload on the node, by the weight of the
+    /// preferred anti-affinity. The nodes are equal without preferred anti-affinity.
+    fn score(&self, instance: &Instance, node: &Node, instances: &Storage<PlacedInstance>) -> f64 {
+        let strength = match instance
+            .affinity
+            .as_ref()
+            .and_then(|affinity| affinity.anti_affinity.as_ref())
+        {
+            Some(anti_affinity) if !anti_affinity.required => {
+                f64::from(anti_affinity.weight.clamp(0, 100)) / 100.0
+            }
+            _ => return 1.0,
+        };
+        1.0 / (1.0 + strength * siblings(instance, &node.id, instances) as f64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::IStorage;
+    use crate::test_support::{self, place_on};
+    use proto::scheduler::{
+        node_selector_requirement::Operator, Affinity, AntiAffinity, NodeSelectorRequirement,
+        NodeSelectorTerm, PreferredTerm, Taint, Toleration,
+    };
+    use std::collections::HashMap;
+
+    fn node(id: &str, labels: &[(&str, &str)], taints: Vec<Taint>) -> Node {
+        Node {
+            labels: labels
+                .iter()
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .collect(),
+            taints,
+            ..test_support::node(id)
+        }
+    }
+
+    fn taint(key: &str, effect: TaintEffect) -> Taint {
+        Taint {
+            key: key.to_string(),
+            value: "true".to_string(),
+            effect: effect.into(),
+        }
+    }
+
+    fn term(key: &str, operator: Operator, values: &[&str]) -> NodeSelectorTerm {
+        NodeSelectorTerm {
+            requirements: vec![NodeSelectorRequirement {
+                key: key.to_string(),
+                operator: operator.into(),
+                values: values.iter().map(|value| value.to_string()).collect(),
+            }],
+        }
+    }
+
+    #[test]
+    fn test_labels_filter() {
+        let ssd = node("ssd", &[("disk", "ssd")], vec![]);
+        let gpu = node("gpu", &[], vec![taint("gpu", TaintEffect::NoSchedule)]);
+        let instances = Storage::new();
+
+        let mut instance = Instance {
+            id: "instance".to_string(),
+            node_selector: HashMap::from([("disk".to_string(), "ssd".to_string())]),
+            ..Default::default()
+        };
+        assert!(LabelsPlugin.filter(&instance, &ssd, &instances).is_empty());
+        assert_eq!(
+            vec!["node selector not matched", "untolerated taint"],
+            LabelsPlugin.filter(&instance, &gpu, &instances)
+        );
+
+        instance.node_selector.clear();
+        instance.tolerations.push(Toleration {
+            key: "gpu".to_string(),
+            ..Default::default()
+        });
+        assert!(LabelsPlugin.filter(&instance, &gpu, &instances).is_empty());
+
+        instance.affinity = Some(Affinity {
+            required: vec![term("disk", Operator::In, &["hdd", "ssd"])],
+            ..Default::default()
+        });
+        assert!(LabelsPlugin.filter(&instance, &ssd, &instances).is_empty());
+        assert_eq!(
+            vec!["required affinity not matched"],
+            LabelsPlugin.filter(&instance, &gpu, &instances)
+        );
+    }
+
+    #[test]
+    fn test_labels_score() {
+        let instance = Instance {
+            affinity: Some(Affinity {
+                preferred: vec![
+                    PreferredTerm {
+                        weight: 80,
+                        term: Some(term("zone", Operator::In, &["eu-west"])),
+                    },
+                    PreferredTerm {
+                        weight: 20,
+                        term: Some(term("disk", Operator::Exists, &[])),
+                    },
+                ],
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let instances = Storage::new();
+
+        let preferred = node("a", &[("zone", "eu-west")], vec![]);
+        let avoided = node(
+            "b",
+            &[("zone", "eu-west")],
+            vec![taint("spot", TaintEffect::PreferNoSchedule)],
+        );
+        assert_eq!(0.8, LabelsPlugin.score(&instance, &preferred, &instances));
+        assert_eq!(0.4, LabelsPlugin.score(&instance, &avoided, &instances));
+        assert_eq!(
+            1.0,
+            LabelsPlugin.score(&Instance::default(), &preferred, &instances)
+        );
+    }
+
+    #[test]
+    fn test_spread() {
+        let node = node("a", &[], vec![]);
+        let instance = |id: &str, required: bool| Instance {
+            id: id.to_string(),
+            workload: "default/web".to_string(),
+            affinity: Some(Affinity {
+                anti_affinity: Some(AntiAffinity {
+                    required,
+                    weight: 50,
+                }),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        let mut instances = Storage::new();
+        assert!(SpreadPlugin
+            .filter(&instance("web-1", true), &node, &instances)
+            .is_empty());
+        instances.update("web-1", place_on("a", instance("web-1", true)));
+
+        assert_eq!(
+            vec!["anti-affinity conflict"],
+            SpreadPlugin.filter(&instance("web-2", true), &node, &instances)
+        );
+        assert!(SpreadPlugin
+            .filter(&instance("web-2", false), &node, &instances)
+            .is_empty());
+        assert_eq!(
+            1.0 / 1.5,
+            SpreadPlugin.score(&instance("web-2", false), &node, &instances)
+        );
+        // an instance doesn't repel itself, e.g. when it is placed again
+        assert!(SpreadPlugin
+            .filter(&instance("web-1", true), &node, &instances)
+            .is_empty());
+    }
+}
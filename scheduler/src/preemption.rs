@@ -0,0 +1,197 @@
+use proto::scheduler::{Instance, Status};
+
+use crate::placement::Pipeline;
+use crate::storage::{IStorage, Storage};
+use crate::{Node, NodeIdentifier, PlacedInstance};
+
+/// `Preemption` is the eviction of instances making room for an instance on a node.
+///
+/// Properties:
+///
+/// * `node_id`: The id of the node the instance is placed on once the victims are evicted.
+/// * `victims`: The lower-priority instances to evict from the node.
+#[derive(Debug, Clone)]
+pub struct Preemption {
+    pub node_id: NodeIdentifier,
+    pub victims: Vec<PlacedInstance>,
+}
+
+impl Preemption {
+    /// It returns how much the preemption costs, the cheapest one is chosen: the highest
+    /// priority evicted first, then the number of victims, then the sum of their priorities
+    fn cost(&self) -> (i32, usize, i64) {
+        let priorities = self.victims.iter().map(|victim| victim.instance.priority);
+        (
+            priorities.clone().max().unwrap_or(i32::MIN),
+            self.victims.len(),
+            priorities.map(i64::from).sum(),
+        )
+    }
+}
+
+/// It returns true if the pipeline can place the instance on the node
+fn fits(
+    instance: &Instance,
+    node: &Node,
+    instances: &Storage<PlacedInstance>,
+    pipeline: &Pipeline,
+) -> bool {
+    let mut single = Storage::new();
+    single.update(&node.id, node.clone());
+    pipeline
+        .place(instance, &single, instances)
+        .node_id
+        .is_some()
+}
+
+/// It finds the instances of a node to evict to make room for an instance. Every instance with
+/// a lower priority is evicted at first, then they are given back in turn, highest priority
+/// first, as long as the instance still fits.
+fn victims_on(
+    instance: &Instance,
+    node: &Node,
+    instances: &Storage<PlacedInstance>,
+    pipeline: &Pipeline,
+) -> Option<Vec<PlacedInstance>> {
+    let mut candidates: Vec<&PlacedInstance> = PlacedInstance::on_node(&node.id, instances)
+        .into_iter()
+        .filter(|placed| placed.instance.priority < instance.priority)
+        .collect();
+    if candidates.is_empty() {
+        return None;
+    }
+    candidates.sort_by(|placed, other| {
+        other
+            .instance
+            .priority
+            .cmp(&placed.instance.priority)
+            .then_with(|| placed.instance.id.cmp(&other.instance.id))
+    });
+
+    let mut remaining = instances.clone();
+    for candidate in &candidates {
+        remaining.delete(&candidate.instance.id);
+    }
+    if !fits(instance, node, &remaining, pipeline) {
+        return None;
+    }
+
+    let mut victims = Vec::new();
+    for candidate in candidates {
+        remaining.update(&candidate.instance.id, candidate.clone());
+        if !fits(instance, node, &remaining, pipeline) {
+            remaining.delete(&candidate.instance.id);
+            victims.push(candidate.clone());
+        }
+    }
+    Some(victims)
+}
+
+/// It finds the cheapest set of lower-priority instances on a single node whose eviction would
+/// let an instance no node has room for be placed on that node.
+///
+/// Arguments:
+///
+/// * `instance`: The instance to place.
+/// * `pipeline`: The pipeline the instance is placed with.
+/// * `nodes`: The nodes of the cluster.
+/// * `instances`: The instances already placed.
+///
+/// Returns:
+///
+/// The node and the instances to evict, None if evicting instances wouldn't make room.
+pub fn find(
+    instance: &Instance,
+    pipeline: &Pipeline,
+    nodes: &Storage<Node>,
+    instances: &Storage<PlacedInstance>,
+) -> Option<Preemption> {
+    nodes
+        .get_all()
+        .values()
+        .filter(|node| node.status == Status::Running && !node.cordoned)
+        .filter_map(|node| {
+            let victims = victims_on(instance, node, instances, pipeline)?;
+            Some(Preemption {
+                node_id: node.id.clone(),
+                victims,
+            })
+        })
+        .filter(|preemption| !preemption.victims.is_empty())
+        .min_by(|preemption, other| {
+            preemption
+                .cost()
+                .cmp(&other.cost())
+                .then_with(|| preemption.node_id.cmp(&other.node_id))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::test_support::{instance, node, place_on};
+
+    fn place(instances: &mut Storage<PlacedInstance>, node_id: &str, instance: Instance) {
+        instances.update(&instance.id.clone(), place_on(node_id, instance));
+    }
+
+    fn ids(preemption: &Preemption) -> Vec<&str> {
+        let mut ids: Vec<&str> = preemption
+            .victims
+            .iter()
+            .map(|victim| victim.instance.id.as_str())
+            .collect();
+        ids.sort();
+        ids
+    }
+
+    fn cluster() -> (Storage<Node>, Storage<PlacedInstance>) {
+        let mut nodes = Storage::new();
+        nodes.update("a", node("a"));
+        nodes.update("b", node("b"));
+
+        let mut instances = Storage::new();
+        place(&mut instances, "a", instance("batch-1", 400, 0));
+        place(&mut instances, "a", instance("batch-2", 400, 10));
+        place(&mut instances, "a", instance("batch-3", 200, 0));
+        place(&mut instances, "b", instance("web-1", 600, 100));
+        place(&mut instances, "b", instance("web-2", 400, 100));
+        (nodes, instances)
+    }
+
+    #[test]
+    fn test_find_cheapest_victims() {
+        let (nodes, instances) = cluster();
+        let pipeline = Pipeline::from_config(&Config::default()).unwrap();
+
+        // evicting batch-1 is enough, batch-2 has a higher priority and batch-3 is too small
+        let critical = instance("critical", 300, 1000);
+        let preemption = find(&critical, &pipeline, &nodes, &instances).unwrap();
+        assert_eq!("a", preemption.node_id);
+        assert_eq!(vec!["batch-1"], ids(&preemption));
+
+        // batch-3 alone leaves room next to the large instance, evicting node b would cost a
+        // higher priority
+        let large = instance("large", 700, 1000);
+        let preemption = find(&large, &pipeline, &nodes, &instances).unwrap();
+        assert_eq!("a", preemption.node_id);
+        assert_eq!(vec!["batch-1", "batch-2"], ids(&preemption));
+    }
+
+    #[test]
+    fn test_find_only_lower_priorities() {
+        let (nodes, instances) = cluster();
+        let pipeline = Pipeline::from_config(&Config::default()).unwrap();
+
+        // the instances of node b have the same priority
+        let large = instance("large", 900, 100);
+        let preemption = find(&large, &pipeline, &nodes, &instances).unwrap();
+        assert_eq!("a", preemption.node_id);
+
+        let too_large = instance("too-large", 2000, 1000);
+        assert!(find(&too_large, &pipeline, &nodes, &instances).is_none());
+        let low = instance("low", 300, 0);
+        assert!(find(&low, &pipeline, &nodes, &instances).is_none());
+    }
+}
@@ -0,0 +1,279 @@
+use std::time::Instant;
+
+use proto::scheduler::{
+    FilterResult, InstanceListRequest, InstanceListResponse, NodeEvaluationInfo, NodeInfo,
+    PendingInstanceInfo, PlacedInstanceInfo, PlacementExplanation, ScoreResult,
+};
+
+use crate::pending::PendingInstance;
+use crate::placement::{self, Decision, Overcommit};
+use crate::storage::{IStorage, Storage};
+use crate::{Node, PlacedInstance};
+
+/// It describes a node along with the resources allocated to the instances placed on it
+///
+/// Arguments:
+///
+/// * `node`: The node to describe.
+/// * `instances`: The instances placed on the nodes.
+/// * `overcommit`: The share of the capacity of the node the instances can request.
+pub fn node_info(
+    node: &Node,
+    instances: &Storage<PlacedInstance>,
+    overcommit: &Overcommit,
+) -> NodeInfo {
+    let mut placed: Vec<String> = PlacedInstance::on_node(&node.id, instances)
+        .into_iter()
+        .map(|placed| placed.instance.id.clone())
+        .collect();
+    placed.sort();
+
+    NodeInfo {
+        id: node.id.clone(),
+        address: node.address.clone(),
+        subnet: node.subnet.to_string(),
+        status: node.status.into(),
+        capacity: node.capacity(),
+        allocated: Some(placement::allocated(&node.id, instances)),
+        usage: node
+            .resource
+            .as_ref()
+            .and_then(|resource| resource.usage.clone()),
+        labels: node.labels.clone(),
+        taints: node.taints.clone(),
+        cordoned: node.cordoned,
+        instances: placed,
+        allocatable: node
+            .capacity()
+            .map(|capacity| overcommit.allocatable(&capacity)),
+    }
+}
+
+/// It lists the placed instances matching the filters of a request, through the indexes of the
+/// storage.
+///
+/// Arguments:
+///
+/// * `request`: The node or namespace of the instances, all the instances if none is set.
+/// * `instances`: The instances placed on the nodes.
+///
+/// Returns:
+///
+/// The instances by id.
+pub fn instances(
+    request: &InstanceListRequest,
+    instances: &Storage<PlacedInstance>,
+) -> InstanceListResponse {
+    let mut matching: Vec<&PlacedInstance> =
+        match (request.node_id.is_empty(), request.namespace.is_empty()) {
+            (false, _) => PlacedInstance::on_node(&request.node_id, instances),
+            (true, false) => instances
+                .find(PlacedInstance::BY_NAMESPACE, &request.namespace)
+                .unwrap_or_default(),
+            (true, true) => instances.get_all().values().collect(),
+        };
+    if !request.namespace.is_empty() {
+        let prefix = format!("{}/", request.namespace);
+        matching.retain(|placed| placed.instance.workload.starts_with(&prefix));
+    }
+    matching.sort_by(|placed, other| placed.instance.id.cmp(&other.instance.id));
+
+    InstanceListResponse {
+        instances: matching
+            .into_iter()
+            .map(|placed| PlacedInstanceInfo {
+                node_id: placed.node_id.clone(),
+                instance: Some(placed.instance.clone()),
+                lost: placed.lost_since.is_some(),
+            })
+            .collect(),
+    }
+}
+
+/// It describes an instance waiting in the pending queue
+///
+/// Arguments:
+///
+/// * `pending`: The pending instance.
+/// * `now`: The time the seconds left before the instance is failed are counted from.
+pub fn pending_info(pending: &PendingInstance, now: Instant) -> PendingInstanceInfo {
+    PendingInstanceInfo {
+        instance: Some(pending.instance.clone()),
+        reason: pending.reason.clone(),
+        // an instance failed at the next check has at least one second left
+        expires_in: pending
+            .deadline
+            .map(|deadline| deadline.saturating_duration_since(now).as_secs().max(1))
+            .unwrap_or_default(),
+    }
+}
+
+/// It explains the last placement decision of an instance, how each node was filtered or scored
+pub fn explain(decision: &Decision) -> PlacementExplanation {
+    PlacementExplanation {
+        instance_id: decision.instance_id.clone(),
+        node_id: decision.node_id.clone().unwrap_or_default(),
+        reason: match decision.node_id {
+            Some(_) => String::new(),
+            None => decision.reason(),
+        },
+        nodes: decision
+            .nodes
+            .iter()
+            .map(|evaluation| NodeEvaluationInfo {
+                node_id: evaluation.node_id.clone(),
+                status: evaluation.status.into(),
+                filtered: evaluation
+                    .filtered
+                    .iter()
+                    .map(|filtered| FilterResult {
+                        plugin: filtered.plugin.to_string(),
+                        reason: filtered.reason.clone(),
+                    })
+                    .collect(),
+                scores: evaluation
+                    .scores
+                    .iter()
+                    .map(|scored| ScoreResult {
+                        plugin: scored.plugin.to_string(),
+                        score: scored.score,
+                        weight: scored.weight,
+                    })
+                    .collect(),
+                total: evaluation.total,
+            })
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::placement::Pipeline;
+    use crate::test_support::{self, place_on, requesting, summary};
+    use proto::scheduler::Instance;
+    use std::time::Duration;
+    use tokio::sync::mpsc;
+
+    fn node(id: &str) -> Node {
+        let mut node = test_support::node(id);
+        node.resource.as_mut().unwrap().usage = Some(summary(100, 200, 0));
+        node
+    }
+
+    fn placed(id: &str, workload: &str, node_id: &str, cpu: u64) -> PlacedInstance {
+        let instance = Instance {
+            workload: workload.to_string(),
+            ..requesting(id, summary(cpu, 100, 0))
+        };
+        place_on(node_id, instance)
+    }
+
+    fn cluster() -> Storage<PlacedInstance> {
+        let mut instances = PlacedInstance::storage();
+        instances.update("web-2", placed("web-2", "default/web", "a", 300));
+        instances.update("web-1", placed("web-1", "default/web", "a", 200));
+        instances.update("db-1", placed("db-1", "prod/db", "b", 500));
+        instances.update("job-1", placed("job-1", "production/job", "a", 100));
+        instances
+    }
+
+    fn ids(response: &InstanceListResponse) -> Vec<&str> {
+        response
+            .instances
+            .iter()
+            .map(|placed| placed.instance.as_ref().unwrap().id.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn test_node_info() {
+        let overcommit = Overcommit {
+            cpu: 2.0,
+            ..Default::default()
+        };
+        let info = node_info(&node("a"), &cluster(), &overcommit);
+        assert_eq!(Some(summary(1000, 1000, 100)), info.capacity);
+        assert_eq!(Some(summary(2000, 1000, 100)), info.allocatable);
+        assert_eq!(Some(summary(600, 300, 0)), info.allocated);
+        assert_eq!(Some(summary(100, 200, 0)), info.usage);
+        assert_eq!(vec!["job-1", "web-1", "web-2"], info.instances);
+    }
+
+    #[test]
+    fn test_list_instances() {
+        let instances = cluster();
+        let request = |node_id: &str, namespace: &str| InstanceListRequest {
+            node_id: node_id.to_string(),
+            namespace: namespace.to_string(),
+        };
+
+        assert_eq!(
+            vec!["db-1", "job-1", "web-1", "web-2"],
+            ids(&super::instances(&request("", ""), &instances))
+        );
+        assert_eq!(
+            vec!["job-1", "web-1", "web-2"],
+            ids(&super::instances(&request("a", ""), &instances))
+        );
+        assert_eq!(
+            vec!["web-1", "web-2"],
+            ids(&super::instances(&request("a", "default"), &instances))
+        );
+        assert_eq!(
+            vec!["db-1"],
+            ids(&super::instances(&request("", "prod"), &instances))
+        );
+        assert!(super::instances(&request("c", ""), &instances)
+            .instances
+            .is_empty());
+    }
+
+    #[test]
+    fn test_pending_info() {
+        let now = Instant::now();
+        let pending = PendingInstance {
+            instance: Instance {
+                id: "web-1".to_string(),
+                ..Default::default()
+            },
+            watcher: mpsc::channel(1).0,
+            reason: "insufficient cpu on all 2 nodes".to_string(),
+            deadline: Some(now + Duration::from_secs(30)),
+        };
+        assert_eq!(30, pending_info(&pending, now).expires_in);
+        assert_eq!(
+            1,
+            pending_info(&pending, now + Duration::from_secs(60)).expires_in
+        );
+
+        let forever = PendingInstance {
+            deadline: None,
+            ..pending
+        };
+        assert_eq!(0, pending_info(&forever, now).expires_in);
+    }
+
+    #[test]
+    fn test_explain() {
+        let mut nodes = Storage::new();
+        nodes.update("a", node("a"));
+        nodes.update("b", node("b"));
+        let pipeline = Pipeline::from_config(&Config::default()).unwrap();
+
+        let instance = placed("large", "default/large", "", 500).instance;
+        let explanation = explain(&pipeline.place(&instance, &nodes, &cluster()));
+        assert_eq!("b", explanation.node_id);
+        assert!(explanation.reason.is_empty());
+        let (b, a) = (&explanation.nodes[0], &explanation.nodes[1]);
+        assert_eq!(3, b.scores.len());
+        assert_eq!("capacity", a.filtered[0].plugin);
+        assert_eq!("insufficient cpu", a.filtered[0].reason);
+
+        let too_large = placed("too-large", "default/large", "", 2000).instance;
+        let explanation = explain(&pipeline.place(&too_large, &nodes, &cluster()));
+        assert!(explanation.node_id.is_empty());
+        assert_eq!("insufficient cpu on all 2 nodes", explanation.reason);
+    }
+}
@@ -0,0 +1,166 @@
+use proto::scheduler::Instance;
+
+use crate::placement::{Decision, Pipeline};
+use crate::preemption;
+use crate::storage::{IStorage, Storage};
+use crate::{Node, PlacedInstance};
+
+/// `Simulation` is where an instance would be placed, without placing it.
+///
+/// Properties:
+///
+/// * `decision`: The decision of the pipeline, without node if the instance can't be placed.
+/// * `preempted`: The ids of the instances which would be evicted to make room for the instance.
+#[derive(Debug, Clone)]
+pub struct Simulation {
+    pub decision: Decision,
+    pub preempted: Vec<String>,
+}
+
+/// It places instances one after the other on a copy of the cluster, with the same pipeline and
+/// preemption as the real placements, so that each instance takes the room left by the previous
+/// ones. Nothing is changed on the cluster.
+///
+/// Arguments:
+///
+/// * `instances`: The instances to place.
+/// * `replaces`: The workload whose instances are left out, as the instances would replace them,
+///   empty if none.
+/// * `pipeline`: The pipeline the instances are placed with.
+/// * `nodes`: The nodes of the cluster.
+/// * `placed`: The instances already placed.
+///
+/// Returns:
+///
+/// The placement of each instance, in the same order.
+pub fn simulate(
+    instances: &[Instance],
+    replaces: &str,
+    pipeline: &Pipeline,
+    nodes: &Storage<Node>,
+    placed: &Storage<PlacedInstance>,
+) -> Vec<Simulation> {
+    let mut placed = placed.clone();
+    if !replaces.is_empty() {
+        let replaced: Vec<String> = placed
+            .get_all()
+            .values()
+            .filter(|placed| placed.instance.workload == replaces)
+            .map(|placed| placed.instance.id.clone())
+            .collect();
+        for id in replaced {
+            placed.delete(&id);
+        }
+    }
+
+    instances
+        .iter()
+        .map(|instance| {
+            let mut decision = pipeline.place(instance, nodes, &placed);
+            let mut preempted = Vec::new();
+            if decision.node_id.is_none() {
+                if let Some(preemption) = preemption::find(instance, pipeline, nodes, &placed) {
+                    for victim in preemption.victims {
+                        placed.delete(&victim.instance.id);
+                        preempted.push(victim.instance.id);
+                    }
+                    decision = pipeline.place(instance, nodes, &placed);
+                }
+            }
+
+            if let Some(node_id) = &decision.node_id {
+                placed.update(
+                    &instance.id,
+                    PlacedInstance {
+                        node_id: node_id.clone(),
+                        instance: instance.clone(),
+                        ..Default::default()
+                    },
+                );
+            }
+            Simulation {
+                decision,
+                preempted,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::test_support::{self, node, place_on};
+
+    fn instance(id: &str, workload: &str, cpu: u64, priority: i32) -> Instance {
+        Instance {
+            workload: workload.to_string(),
+            ..test_support::instance(id, cpu, priority)
+        }
+    }
+
+    fn cluster() -> (Storage<Node>, Storage<PlacedInstance>) {
+        let mut nodes = Storage::new();
+        nodes.update("a", node("a"));
+        nodes.update("b", node("b"));
+
+        let mut placed = PlacedInstance::storage();
+        for (id, node_id, cpu) in [("web-1", "a", 600), ("web-2", "b", 600)] {
+            placed.update(id, place_on(node_id, instance(id, "default/web", cpu, 0)));
+        }
+        (nodes, placed)
+    }
+
+    fn nodes_of(simulations: &[Simulation]) -> Vec<Option<&str>> {
+        simulations
+            .iter()
+            .map(|simulation| simulation.decision.node_id.as_deref())
+            .collect()
+    }
+
+    #[test]
+    fn test_simulate_takes_room_of_previous_instances() {
+        let (nodes, placed) = cluster();
+        let pipeline = Pipeline::from_config(&Config::default()).unwrap();
+
+        let instances: Vec<Instance> = (1..=3)
+            .map(|index| instance(&format!("api-{}", index), "default/api", 400, 0))
+            .collect();
+        let simulations = simulate(&instances, "", &pipeline, &nodes, &placed);
+        assert_eq!(vec![Some("a"), Some("b"), None], nodes_of(&simulations));
+        assert_eq!(
+            "insufficient cpu on all 2 nodes",
+            simulations[2].decision.reason()
+        );
+
+        // nothing changed on the cluster
+        assert_eq!(2, placed.len());
+    }
+
+    #[test]
+    fn test_simulate_replaces_workload() {
+        let (nodes, placed) = cluster();
+        let pipeline = Pipeline::from_config(&Config::default()).unwrap();
+
+        let instances: Vec<Instance> = (1..=2)
+            .map(|index| instance(&format!("web-new-{}", index), "default/web", 900, 0))
+            .collect();
+        assert_eq!(
+            vec![None, None],
+            nodes_of(&simulate(&instances, "", &pipeline, &nodes, &placed))
+        );
+        let simulations = simulate(&instances, "default/web", &pipeline, &nodes, &placed);
+        assert_eq!(vec![Some("a"), Some("b")], nodes_of(&simulations));
+    }
+
+    #[test]
+    fn test_simulate_preemption() {
+        let (nodes, placed) = cluster();
+        let pipeline = Pipeline::from_config(&Config::default()).unwrap();
+
+        let critical = instance("critical", "default/critical", 800, 100);
+        let simulations = simulate(&[critical], "", &pipeline, &nodes, &placed);
+        assert_eq!(Some("a"), simulations[0].decision.node_id.as_deref());
+        assert_eq!(vec!["web-1"], simulations[0].preempted);
+    }
+}
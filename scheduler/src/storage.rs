@@ -1,4 +1,6 @@
-use std::collections::HashMap;
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeSet, HashMap};
+use std::sync::RwLock;
 
 /// Defining a trait called IStorage that takes a generic type T.
 pub trait IStorage<T> {
@@ -9,14 +11,56 @@ pub trait IStorage<T> {
     fn get_all(&self) -> &HashMap<String, T>;
 }
 
+/// The function giving the key a value is indexed by, None if the value isn't indexed.
+pub type IndexKey<T> = fn(&T) -> Option<String>;
+
+/// `Index` is a secondary index of a storage, it gives the ids of the values with a key.
+///
+/// Properties:
+///
+/// * `key`: The function giving the key of a value.
+/// * `ids`: The ids of the values by key.
+#[derive(Debug, Clone)]
+struct Index<T> {
+    key: IndexKey<T>,
+    ids: HashMap<String, BTreeSet<String>>,
+}
+
+impl<T> Index<T> {
+    fn add(&mut self, id: &str, value: &T) {
+        if let Some(key) = (self.key)(value) {
+            self.ids.entry(key).or_default().insert(id.to_string());
+        }
+    }
+
+    fn remove(&mut self, id: &str, value: &T) {
+        if let Some(key) = (self.key)(value) {
+            if let Entry::Occupied(mut ids) = self.ids.entry(key) {
+                ids.get_mut().remove(id);
+                if ids.get().is_empty() {
+                    ids.remove();
+                }
+            }
+        }
+    }
+}
+
 /// `Storage` is a generic type that stores a `HashMap` of `String` keys and `T` values.
 ///
 /// Properties:
 ///
 /// * `data`: This is the HashMap that will store the data.
-#[derive(Debug, Default)]
+/// * `indexes`: The secondary indexes of the data by name, kept up to date on each change.
+#[derive(Debug, Clone)]
 pub struct Storage<T> {
     data: HashMap<String, T>,
+    indexes: HashMap<&'static str, Index<T>>,
+}
+
+impl<T> Default for Storage<T> {
+    fn default() -> Self {
+        Storage::new()
+    }
 }
 
 impl<T> Storage<T> {
@@ -24,7 +68,79 @@ impl<T> Storage<T> {
     pub fn new() -> Self {
         Storage {
             data: HashMap::new(),
+            indexes: HashMap::new(),
+        }
+    }
+
+    /// `with_index` adds a secondary index to the storage, indexing the values already stored.
+    ///
+    /// Arguments:
+    ///
+    /// * `name`: The name the index is queried by.
+    /// * `key`: The function giving the key of a value.
+    pub fn with_index(mut self, name: &'static str, key: IndexKey<T>) -> Self {
+        let mut index = Index {
+            key,
+            ids: HashMap::new(),
+        };
+        for (id, value) in &self.data {
+            index.add(id, value);
+        }
+        self.indexes.insert(name, index);
+        self
+    }
+
+    /// It returns the values with a key in an index, sorted by id.
+    ///
+    /// Arguments:
+    ///
+    /// * `index`: The name of the index.
+    /// * `key`: The key of the values.
+    ///
+    /// Returns:
+    ///
+    /// The values, or None if the storage has no such index.
+    pub fn find(&self, index: &str, key: &str) -> Option<Vec<&T>> {
+        let ids = match self.indexes.get(index)?.ids.get(key) {
+            Some(ids) => ids,
+            None => return Some(Vec::new()),
+        };
+        Some(ids.iter().filter_map(|id| self.data.get(id)).collect())
+    }
+
+    /// It changes the value associated with the given key, then indexes it again.
+    ///
+    /// Arguments:
+    ///
+    /// * `id`: The id of the value to change.
+    /// * `change`: The change to make to the value.
+    ///
+    /// Returns:
+    ///
+    /// True if the value exists and was changed.
+    pub fn modify(&mut self, id: &str, change: impl FnOnce(&mut T)) -> bool {
+        let value = match self.data.get_mut(id) {
+            Some(value) => value,
+            None => return false,
+        };
+        for index in self.indexes.values_mut() {
+            index.remove(id, value);
+        }
+        change(value);
+        for index in self.indexes.values_mut() {
+            index.add(id, value);
         }
+        true
+    }
+
+    /// It returns the number of values stored
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    /// It returns true if no value is stored
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
     }
 }
 
@@ -43,7 +159,8 @@ impl<T> IStorage<T> for Storage<T> {
         self.data.get(id)
     }
 
-    /// Get a mutable reference to the value associated with the given key.
+    /// Get a mutable reference to the value associated with the given key. The indexed keys of
+    /// the value must not be changed through it, `modify` indexes the value again.
     ///
     /// Arguments:
     ///
@@ -63,6 +180,10 @@ impl<T> IStorage<T> for Storage<T> {
     /// * `id`: The id of the data to update.
     /// * `value`: The value to be stored in the cache.
     fn update(&mut self, id: &str, value: T) {
+        self.delete(id);
+        for index in self.indexes.values_mut() {
+            index.add(id, &value);
+        }
         self.data.insert(id.to_string(), value);
     }
 
@@ -72,7 +193,11 @@ impl<T> IStorage<T> for Storage<T> {
     ///
     /// * `id`: The id of the data to delete.
     fn delete(&mut self, id: &str) {
-        self.data.remove(id);
+        if let Some(previous) = self.data.remove(id) {
+            for index in self.indexes.values_mut() {
+                index.remove(id, &previous);
+            }
+        }
     }
 
     /// It returns a reference to the HashMap.
@@ -85,9 +210,98 @@ impl<T> IStorage<T> for Storage<T> {
     }
 }
 
+/// `ConcurrentStorage` is a `Storage` shared between threads. Its values are read by copy, so
+/// that the lock is never held by the callers, and several values are changed atomically with
+/// `write`.
+///
+/// Properties:
+///
+/// * `storage`: The storage, behind a lock letting several readers in at once.
+#[derive(Debug)]
+pub struct ConcurrentStorage<T> {
+    storage: RwLock<Storage<T>>,
+}
+
+impl<T> Default for ConcurrentStorage<T> {
+    fn default() -> Self {
+        ConcurrentStorage {
+            storage: RwLock::new(Storage::new()),
+        }
+    }
+}
+
+impl<T: Clone> ConcurrentStorage<T> {
+    /// `new` creates a `ConcurrentStorage` with the values and indexes of a storage
+    pub fn new(storage: Storage<T>) -> Self {
+        ConcurrentStorage {
+            storage: RwLock::new(storage),
+        }
+    }
+
+    /// It returns a copy of the value associated with the given key
+    pub fn get(&self, id: &str) -> Option<T> {
+        self.storage.read().unwrap().get(id).cloned()
+    }
+
+    /// It stores a value, replacing the one with the same id
+    pub fn update(&self, id: &str, value: T) {
+        self.storage.write().unwrap().update(id, value);
+    }
+
+    /// It removes the value associated with the given key and returns it
+    pub fn delete(&self, id: &str) -> Option<T> {
+        let mut storage = self.storage.write().unwrap();
+        let value = storage.get(id).cloned();
+        storage.delete(id);
+        value
+    }
+
+    /// It changes the value associated with the given key, see `Storage::modify`
+    pub fn modify(&self, id: &str, change: impl FnOnce(&mut T)) -> bool {
+        self.storage.write().unwrap().modify(id, change)
+    }
+
+    /// It returns a copy of the values with a key in an index, sorted by id, none if the
+    /// storage has no such index
+    pub fn find(&self, index: &str, key: &str) -> Vec<T> {
+        self.storage
+            .read()
+            .unwrap()
+            .find(index, key)
+            .unwrap_or_default()
+            .into_iter()
+            .cloned()
+            .collect()
+    }
+
+    /// It returns the number of values stored
+    pub fn len(&self) -> usize {
+        self.storage.read().unwrap().len()
+    }
+
+    /// It returns true if no value is stored
+    pub fn is_empty(&self) -> bool {
+        self.storage.read().unwrap().is_empty()
+    }
+
+    /// It runs a function reading the storage, which sees no change until it returns
+    pub fn read<R>(&self, read: impl FnOnce(&Storage<T>) -> R) -> R {
+        read(&self.storage.read().unwrap())
+    }
+
+    /// It runs a function changing the storage, the other readers and writers see either all
+    /// its changes or none of them
+    pub fn write<R>(&self, write: impl FnOnce(&mut Storage<T>) -> R) -> R {
+        write(&mut self.storage.write().unwrap())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+    use std::thread;
 
     #[test]
     fn test_storage_insert() {
@@ -117,4 +331,169 @@ mod tests {
         storage.delete("test");
         assert_eq!(storage.get("test"), None);
     }
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct Record {
+        node: String,
+        value: u64,
+    }
+
+    fn record(node: &str, value: u64) -> Record {
+        Record {
+            node: node.to_string(),
+            value,
+        }
+    }
+
+    fn indexed() -> Storage<Record> {
+        Storage::new()
+            .with_index("node", |record: &Record| Some(record.node.clone()))
+            .with_index("large", |record: &Record| {
+                (record.value >= 10).then(|| "large".to_string())
+            })
+    }
+
+    /// It checks that the indexes give the same values as a scan of the storage
+    fn assert_indexed(storage: &Storage<Record>, nodes: &[&str]) {
+        for node in nodes {
+            let scanned: Vec<&Record> = {
+                let mut ids: Vec<&String> = storage
+                    .get_all()
+                    .iter()
+                    .filter(|(_, record)| record.node == *node)
+                    .map(|(id, _)| id)
+                    .collect();
+                ids.sort();
+                ids.into_iter().map(|id| storage.get(id).unwrap()).collect()
+            };
+            assert_eq!(scanned, storage.find("node", node).unwrap());
+        }
+        let large = storage
+            .get_all()
+            .values()
+            .filter(|record| record.value >= 10)
+            .count();
+        assert_eq!(large, storage.find("large", "large").unwrap().len());
+    }
+
+    #[test]
+    fn test_storage_index() {
+        let mut storage = Storage::new();
+        storage.update("a", record("node-1", 1));
+        let mut storage = storage.with_index("node", |record: &Record| Some(record.node.clone()));
+        storage.update("b", record("node-1", 2));
+        storage.update("c", record("node-2", 3));
+        assert_eq!(
+            vec![&record("node-1", 1), &record("node-1", 2)],
+            storage.find("node", "node-1").unwrap()
+        );
+
+        storage.update("a", record("node-2", 1));
+        assert!(storage.modify("b", |record| record.node = "node-3".to_string()));
+        assert!(!storage.modify("d", |record| record.node = "node-3".to_string()));
+        storage.delete("c");
+        assert_eq!(
+            vec![&record("node-2", 1)],
+            storage.find("node", "node-2").unwrap()
+        );
+        assert_eq!(
+            vec![&record("node-3", 2)],
+            storage.find("node", "node-3").unwrap()
+        );
+        assert!(storage.find("node", "node-1").unwrap().is_empty());
+        assert!(storage.find("zone", "eu-west").is_none());
+    }
+
+    #[test]
+    fn test_concurrent_updates_keep_indexes() {
+        let storage = Arc::new(ConcurrentStorage::new(indexed()));
+        let nodes = ["node-1", "node-2", "node-3"];
+
+        let writers: Vec<_> = (0..8usize)
+            .map(|writer| {
+                let storage = storage.clone();
+                thread::spawn(move || {
+                    for i in 0..500usize {
+                        let id = format!("record-{}", (writer * 7 + i) % 40);
+                        match i % 4 {
+                            0 => storage.update(&id, record(nodes[i % 3], i as u64)),
+                            1 => {
+                                storage.modify(&id, |record| {
+                                    record.node = nodes[(i + writer) % 3].to_string();
+                                    record.value += 1;
+                                });
+                            }
+                            2 => {
+                                storage.delete(&id);
+                            }
+                            _ => {
+                                storage.find("node", nodes[writer % 3]);
+                            }
+                        }
+                    }
+                })
+            })
+            .collect();
+        for writer in writers {
+            writer.join().unwrap();
+        }
+
+        storage.read(|storage| assert_indexed(storage, &nodes));
+    }
+
+    #[test]
+    fn test_write_is_atomic() {
+        let mut initial = indexed();
+        for i in 0..20 {
+            initial.update(&format!("record-{}", i), record("node-1", 10));
+        }
+        let storage = Arc::new(ConcurrentStorage::new(initial));
+        let done = Arc::new(AtomicBool::new(false));
+
+        // the writers move value between records, the total never changes
+        let writers: Vec<_> = (0..4)
+            .map(|writer| {
+                let storage = storage.clone();
+                thread::spawn(move || {
+                    for i in 0..1000usize {
+                        let from = format!("record-{}", (writer + i) % 20);
+                        let to = format!("record-{}", (writer * 3 + i * 7) % 20);
+                        storage.write(|storage| {
+                            let moved = storage.get(&from).map_or(0, |record| record.value.min(3));
+                            storage.modify(&from, |record| record.value -= moved);
+                            storage.modify(&to, |record| {
+                                record.value += moved;
+                                record.node = format!("node-{}", record.value % 3 + 1);
+                            });
+                        });
+                    }
+                })
+            })
+            .collect();
+        let readers: Vec<_> = (0..4)
+            .map(|_| {
+                let storage = storage.clone();
+                let done = done.clone();
+                thread::spawn(move || {
+                    while !done.load(Ordering::Relaxed) {
+                        let total: u64 = storage.read(|storage| {
+                            storage.get_all().values().map(|record| record.value).sum()
+                        });
+                        assert_eq!(200, total);
+                    }
+                })
+            })
+            .collect();
+
+        for writer in writers {
+            writer.join().unwrap();
+        }
+        done.store(true, Ordering::Relaxed);
+        for reader in readers {
+            reader.join().unwrap();
+        }
+
+        assert_eq!(20, storage.len());
+        storage.read(|storage| assert_indexed(storage, &["node-1", "node-2", "node-3"]));
+    }
 }
@@ -0,0 +1,110 @@
+use std::collections::HashSet;
+use std::net::Ipv4Addr;
+
+use cidr::Ipv4Cidr;
+
+use crate::SchedulerError;
+
+/// `SubnetAllocator` splits the cluster CIDR into subnets of the same size, each node being
+/// given its own subnet for its instances.
+///
+/// Properties:
+///
+/// * `cluster`: The CIDR all the subnets are taken from.
+/// * `prefix_length`: The network length of the subnets.
+/// * `allocated`: The subnets given to the nodes.
+#[derive(Debug)]
+pub struct SubnetAllocator {
+    cluster: Ipv4Cidr,
+    prefix_length: u8,
+    allocated: HashSet<Ipv4Cidr>,
+}
+
+impl SubnetAllocator {
+    /// `new` creates an allocator of the subnets of `prefix_length` in `cluster_cidr`.
+    ///
+    /// Arguments:
+    ///
+    /// * `cluster_cidr`: The CIDR of the cluster, e.g. `10.0.0.0/16`.
+    /// * `prefix_length`: The network length of the subnets, e.g. `24`.
+    pub fn new(cluster_cidr: &str, prefix_length: u8) -> Result<Self, SchedulerError> {
+        let cluster: Ipv4Cidr = cluster_cidr
+            .parse()
+            .map_err(|_| SchedulerError::InvalidClusterCidr(cluster_cidr.to_string()))?;
+        if prefix_length < cluster.network_length() || prefix_length > 32 {
+            return Err(SchedulerError::InvalidClusterCidr(format!(
+                "{} can't be split in /{} subnets",
+                cluster, prefix_length
+            )));
+        }
+
+        Ok(SubnetAllocator {
+            cluster,
+            prefix_length,
+            allocated: HashSet::new(),
+        })
+    }
+
+    /// It returns the first subnet not given to a node yet, None if they are all taken
+    pub fn allocate(&mut self) -> Option<Ipv4Cidr> {
+        let first = u32::from(self.cluster.first_address());
+        let count = 1u64 << (self.prefix_length - self.cluster.network_length());
+        let size = 1u64 << (32 - self.prefix_length);
+
+        let subnet = (0..count)
+            .map(|index| {
+                let address = Ipv4Addr::from((first as u64 + index * size) as u32);
+                Ipv4Cidr::new(address, self.prefix_length).unwrap()
+            })
+            .find(|subnet| !self.allocated.contains(subnet))?;
+
+        self.allocated.insert(subnet);
+        Some(subnet)
+    }
+
+    /// It marks a subnet as given to a node, e.g. when its node is restored after a restart
+    pub fn reserve(&mut self, subnet: Ipv4Cidr) {
+        self.allocated.insert(subnet);
+    }
+
+    /// It gives back a subnet, so that it can be allocated to another node
+    pub fn release(&mut self, subnet: &Ipv4Cidr) {
+        self.allocated.remove(subnet);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_allocate_distinct_subnets() {
+        let mut allocator = SubnetAllocator::new("10.0.0.0/22", 24).unwrap();
+
+        let subnets: Vec<String> = (0..4)
+            .map(|_| allocator.allocate().unwrap().to_string())
+            .collect();
+        assert_eq!(
+            vec!["10.0.0.0/24", "10.0.1.0/24", "10.0.2.0/24", "10.0.3.0/24"],
+            subnets
+        );
+        assert_eq!(None, allocator.allocate());
+    }
+
+    #[test]
+    fn test_release_subnet() {
+        let mut allocator = SubnetAllocator::new("10.0.0.0/23", 24).unwrap();
+
+        let first = allocator.allocate().unwrap();
+        allocator.allocate().unwrap();
+        allocator.release(&first);
+        assert_eq!(Some(first), allocator.allocate());
+    }
+
+    #[test]
+    fn test_invalid_cidr() {
+        assert!(SubnetAllocator::new("10.0.0.1/16", 24).is_err());
+        assert!(SubnetAllocator::new("10.0.0.0/16", 8).is_err());
+        assert!(SubnetAllocator::new("10.0.0.0/16", 33).is_err());
+    }
+}
@@ -0,0 +1,71 @@
+use std::time::Instant;
+
+use proto::scheduler::{Instance, Resource, ResourceSummary, Status};
+
+use crate::{Node, PlacedInstance};
+
+pub fn summary(cpu: u64, memory: u64, disk: u64) -> ResourceSummary {
+    ResourceSummary { cpu, memory, disk }
+}
+
+/// It returns a running node, with the subnet `10.0.1.0/24` and room for `1000` cpu, `1000`
+/// memory and `100` disk
+pub fn node(id: &str) -> Node {
+    Node {
+        id: id.to_string(),
+        address: "10.0.0.2:50053".to_string(),
+        subnet: "10.0.1.0/24".parse().unwrap(),
+        status: Status::Running,
+        resource: Some(Resource {
+            limit: Some(summary(1000, 1000, 100)),
+            usage: None,
+            request: None,
+        }),
+        last_seen: Instant::now(),
+        labels: Default::default(),
+        taints: Vec::new(),
+        cordoned: false,
+        drain: None,
+    }
+}
+
+/// It returns an instance whose resources are limited to `limit`
+pub fn requesting(id: &str, limit: ResourceSummary) -> Instance {
+    Instance {
+        id: id.to_string(),
+        resource: Some(Resource {
+            limit: Some(limit),
+            usage: None,
+            request: None,
+        }),
+        ..Default::default()
+    }
+}
+
+/// It returns an instance with a priority, whose resources are limited to `cpu`
+pub fn instance(id: &str, cpu: u64, priority: i32) -> Instance {
+    Instance {
+        priority,
+        ..requesting(id, summary(cpu, 0, 0))
+    }
+}
+
+/// It returns an instance placed on a node
+pub fn place_on(node_id: &str, instance: Instance) -> PlacedInstance {
+    PlacedInstance {
+        node_id: node_id.to_string(),
+        instance,
+        ..Default::default()
+    }
+}
+
+/// It returns an instance without resources placed on a node
+pub fn placed(id: &str, node_id: &str) -> PlacedInstance {
+    place_on(
+        node_id,
+        Instance {
+            id: id.to_string(),
+            ..Default::default()
+        },
+    )
+}
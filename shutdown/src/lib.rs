@@ -0,0 +1,76 @@
+use log::{error, info};
+use tokio::sync::watch;
+
+/// `Shutdown` is notified once the process has been asked to stop, so that the servers and the
+/// long running tasks stop accepting work and finish the work in flight.
+/// It can be cloned to be handed to each task.
+#[derive(Clone, Debug)]
+pub struct Shutdown {
+    receiver: watch::Receiver<bool>,
+}
+
+/// `ShutdownTrigger` notifies every `Shutdown` created along with it.
+#[derive(Debug)]
+pub struct ShutdownTrigger {
+    sender: watch::Sender<bool>,
+}
+
+/// It creates a `ShutdownTrigger` and the `Shutdown` it notifies
+pub fn channel() -> (ShutdownTrigger, Shutdown) {
+    let (sender, receiver) = watch::channel(false);
+    (ShutdownTrigger { sender }, Shutdown { receiver })
+}
+
+impl ShutdownTrigger {
+    /// It notifies every `Shutdown`, the ones already notified being left as is
+    pub fn trigger(&self) {
+        self.sender.send_replace(true);
+    }
+}
+
+impl Shutdown {
+    /// It returns true if the shutdown has been triggered
+    pub fn is_triggered(&self) -> bool {
+        *self.receiver.borrow()
+    }
+
+    /// It waits for the shutdown to be triggered. It also returns if the trigger is dropped.
+    pub async fn wait(&mut self) {
+        while !*self.receiver.borrow_and_update() {
+            if self.receiver.changed().await.is_err() {
+                return;
+            }
+        }
+    }
+}
+
+/// It waits for the process to receive SIGINT or SIGTERM
+pub async fn signal() {
+    let ctrl_c = async {
+        if let Err(err) = tokio::signal::ctrl_c().await {
+            error!("Failed to listen for SIGINT : {}", err);
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut terminate) => {
+                terminate.recv().await;
+            }
+            Err(err) => {
+                error!("Failed to listen for SIGTERM : {}", err);
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => info!("Received SIGINT"),
+        _ = terminate => info!("Received SIGTERM"),
+    }
+}